@@ -1,7 +1,5 @@
 use std::fmt;
 
-use chrono::{DateTime, Utc};
-
 use crate::*;
 
 /// Startup event section. Contains global information about a collection as a
@@ -10,13 +8,56 @@ use crate::*;
 pub struct StartupEvent {
     /// Retis version used while collecting events.
     pub retis_version: String,
-    /// CLOCK_MONOTONIC offset in regards to local machine time.
+    /// Offset of the clock used to timestamp events (see `clock_source`) in
+    /// regards to local machine time.
     pub clock_monotonic_offset: TimeSpec,
+    /// Identifier of the collection session. A single file can hold multiple
+    /// sessions, when appended to.
+    pub session_id: Option<u64>,
+    /// Clock used to timestamp events. Not set by older versions, which always
+    /// used CLOCK_MONOTONIC.
+    pub clock_source: Option<ClockSource>,
+}
+
+impl StartupEvent {
+    /// Clock used to timestamp the events of this collection.
+    pub fn clock(&self) -> ClockSource {
+        self.clock_source.unwrap_or_default()
+    }
 }
 
 impl EventFmt for StartupEvent {
     fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
-        write!(f, "Retis version {}", self.retis_version)
+        write!(f, "Retis version {}", self.retis_version)?;
+        if let Some(id) = self.session_id {
+            write!(f, " (session {id:016x})")?;
+        }
+        if let Some(ClockSource::Boottime) = self.clock_source {
+            write!(f, " clock boottime")?;
+        }
+        Ok(())
+    }
+}
+
+/// Clock used to timestamp events.
+#[event_type]
+#[derive(Copy, Default, Eq)]
+pub enum ClockSource {
+    /// CLOCK_MONOTONIC, not counting time while suspended.
+    #[default]
+    #[serde(rename = "monotonic")]
+    Monotonic,
+    /// CLOCK_BOOTTIME, counting time while suspended.
+    #[serde(rename = "boottime")]
+    Boottime,
+}
+
+impl fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ClockSource::Monotonic => "monotonic",
+            ClockSource::Boottime => "boottime",
+        })
     }
 }
 
@@ -42,21 +83,28 @@ pub struct CommonEvent {
     pub smp_id: Option<u32>,
     /// Information about the task linked to the event.
     pub task: Option<TaskEvent>,
+    /// Collection session the event belongs to, see `StartupEvent`.
+    pub session_id: Option<u64>,
+    /// Identifier of the event, unique in a file. See `CommonEvent::event_id`.
+    pub event_id: Option<String>,
+}
+
+impl CommonEvent {
+    /// Builds the identifier of an event from its collection session, the CPU
+    /// it was generated on (if any) and its sequence number among the events of
+    /// that CPU in the session, e.g. `5f3b2a1c-2-1042`. Only the lower 32 bits
+    /// of the session id are used, to keep it short.
+    pub fn event_id(session_id: u64, smp_id: Option<u32>, seq: u64) -> String {
+        match smp_id {
+            Some(cpu) => format!("{:08x}-{cpu}-{seq}", session_id as u32),
+            None => format!("{:08x}-x-{seq}", session_id as u32),
+        }
+    }
 }
 
 impl EventFmt for CommonEvent {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
-        match format.time_format {
-            TimeFormat::MonotonicTimestamp => write!(f, "{}", self.timestamp)?,
-            TimeFormat::UtcDate => match format.monotonic_offset {
-                Some(offset) => {
-                    let timestamp = TimeSpec::new(0, self.timestamp as i64) + offset;
-                    let time: DateTime<Utc> = timestamp.into();
-                    write!(f, "{}", time.format("%F %T.%6f"))?;
-                }
-                None => write!(f, "{}", self.timestamp)?,
-            },
-        }
+        format.write_timestamp(f, self.timestamp)?;
 
         if let Some(smp_id) = self.smp_id {
             write!(f, " ({})", smp_id)?;
@@ -70,6 +118,10 @@ impl EventFmt for CommonEvent {
             write!(f, "{}", current.tgid)?;
         }
 
+        if let (true, Some(id)) = (format.multiline, &self.event_id) {
+            write!(f, " (event {id})")?;
+        }
+
         Ok(())
     }
 }
@@ -3,16 +3,44 @@ use std::{
     result, str,
 };
 
+use chrono::{DateTime, SecondsFormat, Utc};
 use log::warn;
 
 use super::TimeSpec;
 
 /// Controls how the time should be displayed in the events.
+///
+/// Formats based on the wall-clock time need the monotonic clock offset (see
+/// `DisplayFormat::monotonic_offset`), and the relative one a reference
+/// timestamp (see `DisplayFormat::time_reference`). Until they are known,
+/// timestamps are displayed as-is.
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 pub enum TimeFormat {
+    /// Monotonic timestamp, in nanoseconds.
     #[default]
     MonotonicTimestamp,
+    /// Wall-clock date in UTC, with microsecond precision.
     UtcDate,
+    /// Wall-clock date in UTC, following ISO 8601, with nanosecond precision.
+    Iso8601,
+    /// Wall-clock time, in nanoseconds since the Unix epoch.
+    EpochNs,
+    /// Time elapsed since a reference timestamp, in seconds.
+    Relative,
+}
+
+/// Controls how much details sections display.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Verbosity {
+    /// Sections are displayed using their usual rendering.
+    #[default]
+    Normal,
+    /// Sections are displayed as tersely as possible, ideally as a single
+    /// token. Useful when displaying events from many modules, one per line.
+    Compact,
+    /// Sections are labeled with the module which generated them. Useful when
+    /// looking at the events of a single module in details.
+    Verbose,
 }
 
 /// Controls how an event is formatted.
@@ -24,6 +52,13 @@ pub struct DisplayFormat {
     pub time_format: TimeFormat,
     /// Offset of the monotonic clock to the wall-clock time.
     pub monotonic_offset: Option<TimeSpec>,
+    /// Reference timestamp for relative times.
+    pub time_reference: Option<u64>,
+    /// Should sections of events composed by multiple modules be displayed as
+    /// `module.field=value` pairs?
+    pub module_prefix: bool,
+    /// How much details sections display.
+    pub verbosity: Verbosity,
 }
 
 impl DisplayFormat {
@@ -48,6 +83,65 @@ impl DisplayFormat {
         self.monotonic_offset = Some(offset);
         self
     }
+
+    /// Sets the reference timestamp of relative times.
+    pub fn time_reference(mut self, timestamp: u64) -> Self {
+        self.time_reference = Some(timestamp);
+        self
+    }
+
+    /// Configure module-prefixed output for events composed by multiple
+    /// modules.
+    pub fn module_prefix(mut self, enabled: bool) -> Self {
+        self.module_prefix = enabled;
+        self
+    }
+
+    /// Configure how much details sections display.
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Writes a monotonic timestamp (in nanoseconds) following the configured
+    /// time format. All timestamps should be displayed using this.
+    pub fn write_timestamp(&self, f: &mut Formatter, timestamp: u64) -> fmt::Result {
+        let wall_clock = || {
+            self.monotonic_offset
+                .map(|offset| TimeSpec::new(0, timestamp as i64) + offset)
+        };
+
+        match (self.time_format, wall_clock(), self.time_reference) {
+            (TimeFormat::UtcDate, Some(time), _) => {
+                let time: DateTime<Utc> = time.into();
+                write!(f, "{}", time.format("%F %T.%6f"))
+            }
+            (TimeFormat::Iso8601, Some(time), _) => {
+                let time: DateTime<Utc> = time.into();
+                write!(f, "{}", time.to_rfc3339_opts(SecondsFormat::Nanos, true))
+            }
+            (TimeFormat::EpochNs, Some(time), _) => {
+                write!(
+                    f,
+                    "{}",
+                    time.sec() as i128 * 1_000_000_000 + time.nsec() as i128
+                )
+            }
+            (TimeFormat::Relative, _, Some(reference)) => {
+                let (sign, diff) = match timestamp.checked_sub(reference) {
+                    Some(diff) => ('+', diff),
+                    None => ('-', reference - timestamp),
+                };
+                write!(
+                    f,
+                    "{sign}{}.{:09}",
+                    diff / 1_000_000_000,
+                    diff % 1_000_000_000
+                )
+            }
+            _ => write!(f, "{timestamp}"),
+        }
+    }
 }
 
 /// `Formatter` implements `std::fmt::Write` and controls how events are being
@@ -323,3 +417,54 @@ impl DelimWriter {
         !self.first
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Timestamp(DisplayFormat, u64);
+
+    impl fmt::Display for Timestamp {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0
+                .write_timestamp(&mut Formatter::new(f, FormatterConf::new()), self.1)
+        }
+    }
+
+    #[test]
+    fn write_timestamp() {
+        let ts = 1_500_000_042;
+        let format = |time_format| DisplayFormat::new().time_format(time_format);
+        let display = |format, ts| Timestamp(format, ts).to_string();
+
+        // Formats needing information not known yet fall back to the raw
+        // timestamp.
+        for time_format in [
+            TimeFormat::MonotonicTimestamp,
+            TimeFormat::UtcDate,
+            TimeFormat::Iso8601,
+            TimeFormat::EpochNs,
+            TimeFormat::Relative,
+        ] {
+            assert_eq!(display(format(time_format), ts), "1500000042");
+        }
+
+        let offset = TimeSpec::new(1_700_000_000, 0);
+        assert_eq!(
+            display(format(TimeFormat::UtcDate).monotonic_offset(offset), ts),
+            "2023-11-14 22:13:21.500000"
+        );
+        assert_eq!(
+            display(format(TimeFormat::Iso8601).monotonic_offset(offset), ts),
+            "2023-11-14T22:13:21.500000042Z"
+        );
+        assert_eq!(
+            display(format(TimeFormat::EpochNs).monotonic_offset(offset), ts),
+            "1700000001500000042"
+        );
+
+        let relative = format(TimeFormat::Relative).time_reference(1_000_000_000);
+        assert_eq!(display(relative, ts), "+0.500000042");
+        assert_eq!(display(relative, 999_999_000), "-0.000001000");
+    }
+}
@@ -71,6 +71,24 @@ impl Event {
         Ok(event)
     }
 
+    /// Same as `from_json_obj` but sections which are not known are skipped instead of making the
+    /// whole event invalid. Their names are returned along with the event.
+    pub(crate) fn from_json_obj_with_unknown(
+        mut obj: HashMap<String, serde_json::Value>,
+    ) -> Result<(Event, Vec<String>)> {
+        let sections = event_sections()?;
+        let unknown = obj
+            .keys()
+            .filter(|owner| !sections.contains_key(*owner))
+            .cloned()
+            .collect::<Vec<_>>();
+        unknown.iter().for_each(|owner| {
+            obj.remove(owner);
+        });
+
+        Ok((Self::from_json_obj(obj)?, unknown))
+    }
+
     /// Create an Event from a json string.
     pub(crate) fn from_json(line: String) -> Result<Event> {
         let event_js: HashMap<String, serde_json::Value> = serde_json::from_str(line.as_str())
@@ -131,6 +149,61 @@ impl Event {
     pub fn sections(&self) -> impl Iterator<Item = SectionId> + '_ {
         self.0.keys().map(|s| s.to_owned())
     }
+
+    /// Is the event composed of sections from multiple modules?
+    fn is_composite(&self) -> bool {
+        self.sections().filter(|id| id.is_module()).count() > 1
+    }
+
+    /// Format a section as `module.field=value` pairs, using its json
+    /// representation. Nested objects are flattened.
+    fn fmt_prefixed(
+        f: &mut Formatter,
+        section: &dyn EventSection,
+        id: SectionId,
+    ) -> std::fmt::Result {
+        fn fmt_value(
+            f: &mut Formatter,
+            prefix: &str,
+            value: &serde_json::Value,
+            first: &mut bool,
+        ) -> std::fmt::Result {
+            use serde_json::Value::*;
+            match value {
+                Object(obj) => obj.iter().try_for_each(|(key, value)| {
+                    fmt_value(f, &format!("{prefix}.{key}"), value, first)
+                }),
+                value => {
+                    if !*first {
+                        write!(f, " ")?;
+                    }
+                    *first = false;
+
+                    match value {
+                        String(s) => write!(f, "{prefix}={s}"),
+                        Array(array) => {
+                            let values: Vec<_> = array
+                                .iter()
+                                .map(|v| match v {
+                                    String(s) => s.clone(),
+                                    v => v.to_string(),
+                                })
+                                .collect();
+                            write!(f, "{prefix}={}", values.join(","))
+                        }
+                        value => write!(f, "{prefix}={value}"),
+                    }
+                }
+            }
+        }
+
+        fmt_value(
+            f,
+            &id.to_str().replace('-', "_"),
+            &section.to_json(),
+            &mut true,
+        )
+    }
 }
 
 impl EventFmt for Event {
@@ -158,9 +231,13 @@ impl EventFmt for Event {
             write!(f, " ")?;
             skb_tracking.event_fmt(f, format)?;
         }
+        let prefixed = format.module_prefix && self.is_composite();
         if let Some(skb_drop) = self.0.get(&SectionId::SkbDrop) {
             write!(f, " ")?;
-            skb_drop.event_fmt(f, format)?;
+            match prefixed {
+                true => Self::fmt_prefixed(f, skb_drop.as_ref(), SectionId::SkbDrop)?,
+                false => skb_drop.event_fmt(f, format)?,
+            }
         }
 
         // Separator between each following sections.
@@ -182,10 +259,16 @@ impl EventFmt for Event {
         (SectionId::Skb as u8..SectionId::_MAX as u8)
             .collect::<Vec<u8>>()
             .iter()
-            .filter_map(|id| self.0.get(&SectionId::from_u8(*id).unwrap()))
-            .try_for_each(|section| {
+            .filter_map(|id| {
+                let id = SectionId::from_u8(*id).unwrap();
+                self.0.get(&id).map(|section| (id, section))
+            })
+            .try_for_each(|(id, section)| {
                 write!(f, "{sep}")?;
-                section.event_fmt(f, format)
+                match prefixed && id.is_module() {
+                    true => Self::fmt_prefixed(f, section.as_ref(), id),
+                    false => section.event_fmt(f, format),
+                }
             })?;
 
         f.conf.reset_level();
@@ -207,8 +290,9 @@ pub enum SectionId {
     Nft = 9,
     Ct = 10,
     Startup = 11,
+    Extension = 12,
     // TODO: use std::mem::variant_count once in stable.
-    _MAX = 12,
+    _MAX = 13,
 }
 
 impl SectionId {
@@ -227,10 +311,18 @@ impl SectionId {
             9 => Nft,
             10 => Ct,
             11 => Startup,
+            12 => Extension,
             x => bail!("Can't construct a SectionId from {}", x),
         })
     }
 
+    /// Is the section generated by a module (collector), as opposed to core
+    /// and post-processing sections?
+    pub fn is_module(self) -> bool {
+        use SectionId::*;
+        matches!(self, SkbDrop | Skb | Ovs | Nft | Ct)
+    }
+
     /// Converts an SectionId to a section unique str identifier.
     pub fn to_str(self) -> &'static str {
         use SectionId::*;
@@ -246,6 +338,7 @@ impl SectionId {
             Nft => "nft",
             Ct => "ct",
             Startup => "startup",
+            Extension => "extension",
             _MAX => "_max",
         }
     }
@@ -276,6 +369,7 @@ impl FromStr for SectionId {
             "nft" => Nft,
             "ct" => Ct,
             "startup" => Startup,
+            "extension" => Extension,
             x => bail!("Can't construct a SectionId from {}", x),
         })
     }
@@ -308,6 +402,7 @@ fn event_sections() -> Result<&'static EventSectionMap> {
         insert_section!(events, CtEvent);
         insert_section!(events, StartupEvent);
         insert_section!(events, TrackingInfo);
+        insert_section!(events, ExtensionEvent);
 
         Ok(events)
     })
@@ -421,3 +516,139 @@ pub mod test {
 
 #[cfg(feature = "test-events")]
 pub use test::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(sections: Vec<Box<dyn EventSection>>) -> Result<Event> {
+        let mut event = Event::new();
+        event.insert_section(
+            SectionId::Common,
+            Box::new(CommonEvent {
+                timestamp: 23868955449721,
+                ..Default::default()
+            }),
+        )?;
+        for section in sections {
+            event.insert_section(SectionId::from_u8(section.id())?, section)?;
+        }
+        Ok(event)
+    }
+
+    #[test]
+    fn module_prefix() -> Result<()> {
+        let format = DisplayFormat::new().module_prefix(true);
+        let drop = || {
+            Box::new(SkbDropEvent {
+                subsys: None,
+                drop_reason: "NO_SOCKET".to_string(),
+                reason_resolved: true,
+                drop_location: Some("tcp_v4_rcv".to_string()),
+                consumed: false,
+                ct: None,
+            })
+        };
+
+        // Single-module events are not prefixed.
+        let single = event(vec![drop()])?;
+        assert_eq!(
+            format!("{}", single.display(&format, &FormatterConf::new())),
+            "23868955449721 drop (reason NO_SOCKET) in tcp_v4_rcv"
+        );
+
+        let composite = event(vec![
+            drop(),
+            Box::new(OvsEvent {
+                event: OvsEventType::Action(ActionEvent {
+                    action: Some(OvsAction::Output(OvsActionOutput {
+                        port: 2,
+                        ..Default::default()
+                    })),
+                    recirc_id: 0,
+                    queue_id: None,
+                    error: None,
+                    has_detail: None,
+                }),
+            }),
+        ])?;
+        assert_eq!(
+            format!("{}", composite.display(&format, &FormatterConf::new())),
+            "23868955449721 skb_drop.drop_location=tcp_v4_rcv skb_drop.drop_reason=NO_SOCKET \
+             ovs.action=output ovs.event_type=action_execute ovs.port=2 ovs.recirc_id=0"
+        );
+
+        // Composite events are not prefixed by default.
+        assert_eq!(
+            format!(
+                "{}",
+                composite.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "23868955449721 drop (reason NO_SOCKET) in tcp_v4_rcv exec oport 2"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn json_golden() -> Result<()> {
+        // Unset optional fields are omitted and key names are the field ones.
+        let golden = [
+            (
+                r#"{"common":{"timestamp":23868955449721},"ovs":{"cmd":1,"cpu":2,"event_type":"upcall","port":3366920467}}"#,
+                event(vec![Box::new(OvsEvent {
+                    event: OvsEventType::Upcall(UpcallEvent {
+                        cmd: 1,
+                        port: 3366920467,
+                        cpu: 2,
+                    }),
+                })])?,
+            ),
+            (
+                r#"{"common":{"timestamp":23868955449721},"skb-drop":{"drop_reason":"NO_SOCKET"}}"#,
+                event(vec![Box::new(SkbDropEvent {
+                    subsys: None,
+                    drop_reason: "NO_SOCKET".to_string(),
+                    reason_resolved: true,
+                    drop_location: None,
+                    consumed: false,
+                    ct: None,
+                })])?,
+            ),
+            (
+                r#"{"common":{"timestamp":23868955449721},"skb-drop":{"ct":{"state":"new","zone_id":1},"drop_location":"nf_hook_slow","drop_reason":"NETFILTER_DROP","subsys":"netfilter"}}"#,
+                event(vec![Box::new(SkbDropEvent {
+                    subsys: Some("netfilter".to_string()),
+                    drop_reason: "NETFILTER_DROP".to_string(),
+                    reason_resolved: true,
+                    drop_location: Some("nf_hook_slow".to_string()),
+                    consumed: false,
+                    ct: Some(SkbDropCt {
+                        state: CtState::New,
+                        zone_id: 1,
+                        tcp_state: None,
+                    }),
+                })])?,
+            ),
+            (
+                r#"{"common":{"timestamp":23868955449721},"skb-drop":{"drop_reason":"22","reason_resolved":false}}"#,
+                event(vec![Box::new(SkbDropEvent {
+                    subsys: None,
+                    drop_reason: "22".to_string(),
+                    reason_resolved: false,
+                    drop_location: None,
+                    consumed: false,
+                    ct: None,
+                })])?,
+            ),
+        ];
+
+        for (json, event) in golden.iter() {
+            assert_eq!(serde_json::to_string(&event.to_json())?, *json);
+            // Round trip.
+            let parsed = Event::from_json(json.to_string())?;
+            assert_eq!(serde_json::to_string(&parsed.to_json())?, *json);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+//! Event enrichment.
+//!
+//! Users can derive their own fields from the decoded events (e.g. mapping an
+//! output port to a VM name using an external inventory) by registering
+//! enrichment plugins. Plugins run on each event after it is decoded and
+//! before it is formatted or written, and the fields they return are added to
+//! the `extension` section of the event.
+
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::Result;
+use log::warn;
+
+use crate::*;
+
+/// Extension event section. Holds the fields added by enrichment plugins.
+#[event_section(SectionId::Extension)]
+#[derive(Default)]
+pub struct ExtensionEvent {
+    /// Fields added by enrichment plugins, by name.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl EventFmt for ExtensionEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "ext")?;
+        self.fields
+            .iter()
+            .try_for_each(|(name, value)| write!(f, " {name}={value}"))
+    }
+}
+
+/// Enrichment plugin. Given an event, returns the fields to add to it as
+/// (name, value) pairs.
+pub type EnrichFn = Box<dyn Fn(&Event) -> Result<Vec<(String, String)>>>;
+
+/// Enrichment runs the registered plugins over events.
+#[derive(Default)]
+pub struct Enrichment {
+    plugins: Vec<(String, EnrichFn)>,
+}
+
+impl Enrichment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an enrichment plugin. Plugins run in their registration order.
+    pub fn register(&mut self, name: &str, plugin: EnrichFn) {
+        self.plugins.push((name.to_string(), plugin));
+    }
+
+    /// Run all plugins over an event and add the fields they returned to it.
+    /// A plugin failing on an event is reported and does not prevent the other
+    /// plugins from running, nor itself from running on the next events.
+    pub fn process_one(&self, event: &mut Event) -> Result<()> {
+        let mut fields = Vec::new();
+        for (name, plugin) in self.plugins.iter() {
+            match plugin(event) {
+                Ok(mut f) => fields.append(&mut f),
+                Err(e) => warn!("Enrichment plugin {name} failed: {e}"),
+            }
+        }
+
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        if event
+            .get_section::<ExtensionEvent>(SectionId::Extension)
+            .is_none()
+        {
+            event.insert_section(SectionId::Extension, Box::<ExtensionEvent>::default())?;
+        }
+        // Unwrap as the section was inserted above if missing.
+        event
+            .get_section_mut::<ExtensionEvent>(SectionId::Extension)
+            .unwrap()
+            .fields
+            .extend(fields);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+
+    use super::*;
+
+    #[test]
+    fn enrichment() -> Result<()> {
+        let mut enrichment = Enrichment::new();
+        enrichment.register(
+            "vm",
+            Box::new(|event| {
+                Ok(match event.get_section::<OvsEvent>(SectionId::Ovs) {
+                    Some(_) => vec![("vm".to_string(), "vm0".to_string())],
+                    None => Vec::new(),
+                })
+            }),
+        );
+        enrichment.register(
+            "failing",
+            Box::new(
+                |event| match event.get_section::<OvsEvent>(SectionId::Ovs) {
+                    Some(_) => bail!("inventory unavailable"),
+                    None => Ok(vec![("inventory".to_string(), "ok".to_string())]),
+                },
+            ),
+        );
+
+        // The failing plugin does not prevent the other one from running.
+        let mut event = Event::new();
+        event.insert_section(
+            SectionId::Ovs,
+            Box::new(OvsEvent {
+                event: OvsEventType::Upcall(UpcallEvent::default()),
+            }),
+        )?;
+        enrichment.process_one(&mut event)?;
+        let ext = event
+            .get_section::<ExtensionEvent>(SectionId::Extension)
+            .unwrap();
+        assert_eq!(ext.fields.len(), 1);
+        assert_eq!(ext.fields.get("vm").map(String::as_str), Some("vm0"));
+
+        // Nor itself from running on the next events.
+        let mut event = Event::new();
+        enrichment.process_one(&mut event)?;
+        let ext = event
+            .get_section::<ExtensionEvent>(SectionId::Extension)
+            .unwrap();
+        assert_eq!(ext.fields.get("inventory").map(String::as_str), Some("ok"));
+        assert!(!ext.fields.contains_key("vm"));
+
+        // No section is added when no field is returned.
+        let mut enrichment = Enrichment::new();
+        enrichment.register("none", Box::new(|_| Ok(Vec::new())));
+        let mut event = Event::new();
+        enrichment.process_one(&mut event)?;
+        assert!(event
+            .get_section::<ExtensionEvent>(SectionId::Extension)
+            .is_none());
+        Ok(())
+    }
+}
@@ -1,14 +1,253 @@
 //! Handles the file (json) to Rust event retrieval and the unmarshaling process.
 
 use std::{
-    fs::File,
-    io::{BufRead, BufReader, Seek},
-    path::Path,
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom},
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, bail, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
 
-use super::{Event, EventSeries};
+use super::{ClockSource, CommonEvent, Event, EventSeries, SectionId, StartupEvent, TrackingInfo};
+
+/// Version of the index file format. Bump it when making incompatible changes
+/// to `FileIndex`.
+pub const FILE_INDEX_VERSION: u32 = 1;
+/// Number of events a single index entry describes.
+const FILE_INDEX_BLOCK_SIZE: usize = 1024;
+
+/// Path of the index of an events file: the events file path with an ".idx"
+/// extension appended, e.g. "retis.data.idx".
+pub fn index_path<P>(file: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut path = file.as_ref().as_os_str().to_owned();
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+/// Member holding the checksum of an event, when enabled at capture time. It
+/// is the last member of the event json object and holds the CRC32 of the
+/// object without it, in hexadecimal.
+const CHECKSUM_KEY: &str = "retis_checksum";
+
+/// Append the checksum of a json event (w/o its trailing '\n') to it.
+pub fn append_checksum(event: &mut Vec<u8>) {
+    let crc = crc32fast::hash(event);
+    if event.pop() != Some(b'}') {
+        // Not an object, leave it alone.
+        event.push(b'}');
+        return;
+    }
+    if event.len() > 1 {
+        event.push(b',');
+    }
+    event.extend_from_slice(format!("\"{CHECKSUM_KEY}\":\"{crc:08x}\"}}").as_bytes());
+}
+
+/// Remove the checksum from a json event line, if any. The line is returned
+/// w/o its checksum, along with the result of its verification (`None` if the
+/// event has no checksum).
+fn split_checksum(line: String) -> (String, Option<bool>) {
+    let marker = format!("\"{CHECKSUM_KEY}\":\"");
+    let content = line.trim_end_matches('\n');
+
+    let (pos, crc) = match content
+        .strip_suffix("\"}")
+        .and_then(|s| s.rfind(&marker).map(|pos| (pos, &s[pos + marker.len()..])))
+    {
+        Some(found) => found,
+        None => return (line, None),
+    };
+
+    // Remove the separating ',' unless the checksum is the only member.
+    let start = match content[..pos].ends_with(',') {
+        true => pos - 1,
+        false => pos,
+    };
+    let event = format!("{}}}", &content[..start]);
+    let valid =
+        u32::from_str_radix(crc, 16).is_ok_and(|crc| crc == crc32fast::hash(event.as_bytes()));
+
+    (event, Some(valid))
+}
+
+/// Index entry describing a contiguous block of events in a file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileIndexEntry {
+    /// Offset of the first event of the block in the file.
+    pub offset: u64,
+    /// Lowest event timestamp in the block.
+    pub ts_min: u64,
+    /// Highest event timestamp in the block.
+    pub ts_max: u64,
+    /// CPUs having generated events in the block.
+    pub cpus: Vec<u32>,
+    /// The block contains events w/o a timestamp (e.g. the startup event),
+    /// which must always be read.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub untimed: bool,
+}
+
+impl FileIndexEntry {
+    /// Does the block possibly contain events in the [since, until] range?
+    fn overlaps(&self, since: Option<u64>, until: Option<u64>) -> bool {
+        self.untimed
+            || !(since.is_some_and(|since| self.ts_max < since)
+                || until.is_some_and(|until| self.ts_min > until))
+    }
+}
+
+/// Index of an events file, stored next to it in its own file (see
+/// `index_path`). Events files are left untouched and can still be read by
+/// any json-lines consumer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileIndex {
+    /// Version of the index format, see `FILE_INDEX_VERSION`.
+    pub version: u32,
+    /// Size of the events file when the index was written. An index not
+    /// matching its events file, e.g. because events were appended to it
+    /// afterwards, is not used.
+    pub size: u64,
+    /// Index entries, in file order.
+    pub entries: Vec<FileIndexEntry>,
+}
+
+/// Builds a `FileIndex` while events are written to a file and writes it to
+/// the index file once the capture is done.
+#[derive(Default)]
+pub struct FileIndexBuilder {
+    /// Path of the index file.
+    path: PathBuf,
+    block_size: usize,
+    offset: u64,
+    /// Number of events recorded in the current block.
+    in_block: usize,
+    index: FileIndex,
+}
+
+impl FileIndexBuilder {
+    /// Start building the index of a new events file. An index left at the
+    /// same location by a previous file is removed, as it does not apply.
+    pub fn new<P>(file: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = index_path(file);
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != ErrorKind::NotFound {
+                bail!("Could not remove {}: {e}", path.display());
+            }
+        }
+
+        Ok(Self {
+            path,
+            block_size: FILE_INDEX_BLOCK_SIZE,
+            index: FileIndex {
+                version: FILE_INDEX_VERSION,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    /// Resume building the index of an existing file whose events end at
+    /// `offset`, e.g. when appending a new collection session to it. The next
+    /// recorded event starts a new index entry.
+    fn resume(path: PathBuf, index: FileIndex, offset: u64) -> Self {
+        Self {
+            path,
+            block_size: FILE_INDEX_BLOCK_SIZE,
+            offset,
+            in_block: FILE_INDEX_BLOCK_SIZE,
+            index,
+        }
+    }
+
+    /// Record an event which was written to the file using `len` bytes.
+    pub fn record(&mut self, event: &Event, len: u64) {
+        if self.index.entries.is_empty() || self.in_block == self.block_size {
+            self.index.entries.push(FileIndexEntry {
+                offset: self.offset,
+                ts_min: u64::MAX,
+                ..Default::default()
+            });
+            self.in_block = 0;
+        }
+        self.in_block += 1;
+        self.offset += len;
+
+        // Unwrap as an entry was pushed above if none was there.
+        let entry = self.index.entries.last_mut().unwrap();
+        match event.get_section::<CommonEvent>(SectionId::Common) {
+            Some(common) => {
+                entry.ts_min = entry.ts_min.min(common.timestamp);
+                entry.ts_max = entry.ts_max.max(common.timestamp);
+                if let Some(cpu) = common.smp_id {
+                    if let Err(pos) = entry.cpus.binary_search(&cpu) {
+                        entry.cpus.insert(pos, cpu);
+                    }
+                }
+            }
+            None => entry.untimed = true,
+        }
+    }
+
+    /// Write the index file. This should be called once, after the last event
+    /// was written to the events file and flushed.
+    pub fn write(&self) -> Result<()> {
+        let index = FileIndex {
+            size: self.offset,
+            ..self.index.clone()
+        };
+        fs::write(&self.path, serde_json::to_vec(&index)?)
+            .map_err(|e| anyhow!("Could not write {}: {e}", self.path.display()))
+    }
+}
+
+/// Open an events file for a new collection session to be appended to it. The
+/// file is created if it does not exist. If the file has a valid index, a
+/// builder resuming it is returned; non-empty files w/o an index can be
+/// appended to but won't get one.
+pub fn open_for_append<P>(file: P) -> Result<(File, Option<FileIndexBuilder>)>
+where
+    P: AsRef<Path>,
+{
+    let path = file.as_ref();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| anyhow!("Could not open {}: {e}", path.display()))?;
+
+    let size = file.metadata()?.len();
+    if size == 0 {
+        return Ok((file, Some(FileIndexBuilder::new(path)?)));
+    }
+
+    let mut reader = BufReader::new(file.try_clone()?);
+    if let FileType::Series = FileEventsFactory::detect_type(&mut reader)? {
+        bail!("Cannot append events to a file containing sorted series");
+    }
+
+    let builder = match FileEventsFactory::read_index(path, size) {
+        Ok(Some(index)) => Some(FileIndexBuilder::resume(index_path(path), index, size)),
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Ignoring the index of {}: {e}", path.display());
+            None
+        }
+    };
+
+    file.seek(SeekFrom::End(0))?;
+    Ok((file, builder))
+}
 
 // Type of file that is being processed.
 #[derive(Debug, Clone)]
@@ -24,6 +263,13 @@ pub enum FileType {
 pub struct FileEventsFactory {
     reader: BufReader<File>,
     filetype: FileType,
+    /// Current offset in the file.
+    offset: u64,
+    index: Option<FileIndex>,
+    /// Next index entry to be reached.
+    next_entry: usize,
+    since: Option<u64>,
+    until: Option<u64>,
 }
 
 impl FileEventsFactory {
@@ -31,13 +277,29 @@ impl FileEventsFactory {
     where
         P: AsRef<Path>,
     {
-        let mut reader = BufReader::new(
-            File::open(&file)
-                .map_err(|e| anyhow!("Could not open {}: {e}", file.as_ref().display()))?,
-        );
+        let path = file.as_ref();
+        let file =
+            File::open(path).map_err(|e| anyhow!("Could not open {}: {e}", path.display()))?;
+        let size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
         let filetype = Self::detect_type(&mut reader)?;
+        let index = match Self::read_index(path, size) {
+            Ok(index) => index,
+            Err(e) => {
+                warn!("Ignoring the index of {}: {e}", path.display());
+                None
+            }
+        };
 
-        Ok(FileEventsFactory { reader, filetype })
+        Ok(FileEventsFactory {
+            reader,
+            filetype,
+            offset: 0,
+            index,
+            next_entry: 0,
+            since: None,
+            until: None,
+        })
     }
 }
 
@@ -49,12 +311,32 @@ impl FileEventsFactory {
             FileType::Event => (),
             FileType::Series => bail!("Cannot read event from sorted file"),
         }
-        let mut line = String::new();
 
-        match self.reader.read_line(&mut line) {
-            Err(e) => Err(e.into()),
-            Ok(0) => Ok(None),
-            Ok(_) => Ok(Some(Event::from_json(line)?)),
+        loop {
+            let offset = self.offset;
+            let line = match self.next_line()? {
+                Some(line) => line,
+                None => return Ok(None),
+            };
+            let (line, checksum) = split_checksum(line);
+            if checksum == Some(false) {
+                warn!("Event at offset {offset} failed checksum verification, skipping it");
+                continue;
+            }
+            let event = Event::from_json(line)?;
+
+            if self.since.is_none() && self.until.is_none() {
+                return Ok(Some(event));
+            }
+            match event.get_section::<CommonEvent>(SectionId::Common) {
+                Some(common)
+                    if self.since.is_some_and(|since| common.timestamp < since)
+                        || self.until.is_some_and(|until| common.timestamp > until) =>
+                {
+                    continue
+                }
+                _ => return Ok(Some(event)),
+            }
         }
     }
 
@@ -65,15 +347,92 @@ impl FileEventsFactory {
             FileType::Event => bail!("Cannot read series from unsorted file"),
             FileType::Series => (),
         }
-        let mut line = String::new();
 
+        Ok(match self.next_line()? {
+            Some(line) => Some(EventSeries::from_json(line)?),
+            None => None,
+        })
+    }
+
+    /// Only retrieve events having a timestamp in the [since, until] range.
+    /// Events w/o a timestamp are always retrieved. If the file has an index,
+    /// blocks of events outside the range are not read at all. Only applies
+    /// to files containing events.
+    pub fn set_time_range(&mut self, since: Option<u64>, until: Option<u64>) {
+        self.since = since;
+        self.until = until;
+    }
+
+    /// Get the file index, if any.
+    pub fn index(&self) -> Option<&FileIndex> {
+        self.index.as_ref()
+    }
+
+    /// Read the next line, skipping blocks of events outside of the requested
+    /// time range.
+    fn next_line(&mut self) -> Result<Option<String>> {
+        if let (Some(index), FileType::Event) = (&self.index, &self.filetype) {
+            let mut seek = false;
+            while let Some(entry) = index.entries.get(self.next_entry) {
+                if entry.offset > self.offset {
+                    break;
+                }
+                self.next_entry += 1;
+
+                if !entry.overlaps(self.since, self.until) {
+                    self.offset = index
+                        .entries
+                        .get(self.next_entry)
+                        .map(|next| next.offset)
+                        .unwrap_or(index.size);
+                    seek = true;
+                }
+            }
+
+            if seek {
+                self.reader.seek(SeekFrom::Start(self.offset))?;
+            }
+        }
+
+        let mut line = String::new();
         match self.reader.read_line(&mut line) {
             Err(e) => Err(e.into()),
             Ok(0) => Ok(None),
-            Ok(_) => Ok(Some(EventSeries::from_json(line)?)),
+            Ok(len) => {
+                self.offset += len as u64;
+                Ok(Some(line))
+            }
         }
     }
 
+    /// Look for the index of an events file of size `size`. Files w/o an
+    /// index are still valid and can be read linearly.
+    fn read_index(file: &Path, size: u64) -> Result<Option<FileIndex>> {
+        let path = index_path(file);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => bail!("Could not read {}: {e}", path.display()),
+        };
+        let index: FileIndex = serde_json::from_slice(&data)
+            .map_err(|e| anyhow!("Failed to parse the file index: {e}"))?;
+
+        if index.version != FILE_INDEX_VERSION {
+            bail!(
+                "Unsupported file index version {} (expected {FILE_INDEX_VERSION})",
+                index.version
+            );
+        }
+        if index.size != size {
+            bail!(
+                "Index covers {} bytes but the file has {size}, it was modified after being indexed",
+                index.size
+            );
+        }
+
+        Ok(Some(index))
+    }
+
     fn detect_type<T>(reader: &mut T) -> Result<FileType>
     where
         T: BufRead + Seek,
@@ -102,9 +461,274 @@ impl FileEventsFactory {
     }
 }
 
+/// Collection session found in an events file, see `FileEventsFactory::sessions`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileSession {
+    /// Session id, if the session was given one.
+    pub id: Option<u64>,
+    /// Retis version used to collect the session, if known.
+    pub retis_version: Option<String>,
+    /// Clock used to timestamp the events of the session, if known.
+    pub clock_source: Option<ClockSource>,
+    /// Number of events in the session, including its startup event.
+    pub events: u64,
+}
+
+impl FileEventsFactory {
+    /// List the collection sessions found in an events file, in file order.
+    /// Events are attributed to the session matching their id, or to the
+    /// last session seen if they do not have any.
+    pub fn sessions<P>(file: P) -> Result<Vec<FileSession>>
+    where
+        P: AsRef<Path>,
+    {
+        let mut sessions: Vec<FileSession> = Vec::new();
+        let mut add = |event: &Event| {
+            if let Some(startup) = event.get_section::<StartupEvent>(SectionId::Startup) {
+                sessions.push(FileSession {
+                    id: startup.session_id,
+                    retis_version: Some(startup.retis_version.clone()),
+                    clock_source: Some(startup.clock()),
+                    events: 0,
+                });
+            }
+
+            let id = event
+                .get_section::<CommonEvent>(SectionId::Common)
+                .and_then(|common| common.session_id);
+            let pos = match sessions.iter().rposition(|s| id.is_some() && s.id == id) {
+                Some(pos) => pos,
+                None if id.is_none() && !sessions.is_empty() => sessions.len() - 1,
+                None => {
+                    sessions.push(FileSession {
+                        id,
+                        ..Default::default()
+                    });
+                    sessions.len() - 1
+                }
+            };
+            sessions[pos].events += 1;
+        };
+
+        let mut factory = Self::new(file)?;
+        match factory.file_type() {
+            FileType::Event => {
+                while let Some(event) = factory.next_event()? {
+                    add(&event);
+                }
+            }
+            FileType::Series => {
+                while let Some(series) = factory.next_series()? {
+                    series.events.iter().for_each(&mut add);
+                }
+            }
+        }
+
+        Ok(sessions)
+    }
+}
+
+/// Result of the validation of an events file, see `FileEventsFactory::validate`.
+#[derive(Debug, Default)]
+pub struct FileValidationReport {
+    /// Type of the file, if it could be detected.
+    pub file_type: Option<FileType>,
+    /// Number of events found, including the ones part of series.
+    pub events: u64,
+    /// Number of series found, for sorted files.
+    pub series: u64,
+    /// Lowest and highest event timestamps.
+    pub first_ts: Option<u64>,
+    pub last_ts: Option<u64>,
+    /// The file has a valid index.
+    pub indexed: bool,
+    /// Sections not known by this version, and how many times they were
+    /// found. Those are not errors: they are skipped but kept in the file.
+    pub unknown_sections: BTreeMap<String, u64>,
+    /// Number of events whose checksum was verified.
+    pub checksums: u64,
+    /// Gaps in the series sequences, for sorted files.
+    pub gaps: Vec<String>,
+    /// Structural errors, making the file (partially) unusable.
+    pub errors: Vec<String>,
+}
+
+impl FileValidationReport {
+    /// Is the file free of structural errors?
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn add_event(&mut self, obj: HashMap<String, serde_json::Value>) -> Result<Event> {
+        let (event, unknown) = Event::from_json_obj_with_unknown(obj)?;
+
+        unknown
+            .into_iter()
+            .for_each(|owner| *self.unknown_sections.entry(owner).or_default() += 1);
+        if let Some(common) = event.get_section::<CommonEvent>(SectionId::Common) {
+            self.first_ts = Some(
+                self.first_ts
+                    .map_or(common.timestamp, |ts| ts.min(common.timestamp)),
+            );
+            self.last_ts = Some(
+                self.last_ts
+                    .map_or(common.timestamp, |ts| ts.max(common.timestamp)),
+            );
+        }
+        self.events += 1;
+
+        Ok(event)
+    }
+}
+
+impl FileEventsFactory {
+    /// Walk a whole events file and check its integrity: every line must be a
+    /// complete and valid event (or series), known sections must be valid,
+    /// the index (if any) must match the events and series must not
+    /// have gaps. Only errors preventing the validation to start (e.g. the
+    /// file can't be opened) are returned as such, others are reported.
+    pub fn validate<P>(file: P) -> Result<FileValidationReport>
+    where
+        P: AsRef<Path>,
+    {
+        let mut report = FileValidationReport::default();
+        let path = file.as_ref();
+        let file =
+            File::open(path).map_err(|e| anyhow!("Could not open {}: {e}", path.display()))?;
+        let size = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        report.file_type = match Self::detect_type(&mut reader) {
+            Ok(file_type) => Some(file_type),
+            Err(e) => {
+                report.errors.push(format!("line 1: {e}"));
+                return Ok(report);
+            }
+        };
+        let index = match Self::read_index(path, size) {
+            Ok(index) => index,
+            Err(e) => {
+                report.errors.push(format!("index: {e}"));
+                None
+            }
+        };
+        report.indexed = index.is_some();
+        let entries = index.map(|index| index.entries).unwrap_or_default();
+        let mut next_entry = 0;
+
+        let mut offset = 0;
+        let mut line = String::new();
+        for n in 1.. {
+            line.clear();
+            let len = reader.read_line(&mut line)? as u64;
+            if len == 0 {
+                break;
+            }
+
+            // Check the line is where the index expects it.
+            while let Some(entry) = entries.get(next_entry) {
+                if entry.offset > offset {
+                    break;
+                }
+                if entry.offset < offset {
+                    report.errors.push(format!(
+                        "index: entry {next_entry} does not point to the start of an event"
+                    ));
+                }
+                next_entry += 1;
+            }
+            let block = next_entry.checked_sub(1).and_then(|i| entries.get(i));
+            offset += len;
+
+            if !line.ends_with('\n') {
+                report.errors.push(format!("line {n}: truncated"));
+                break;
+            }
+
+            if let Some(FileType::Event) = report.file_type {
+                let (event, checksum) = split_checksum(std::mem::take(&mut line));
+                match checksum {
+                    Some(true) => report.checksums += 1,
+                    Some(false) => {
+                        report
+                            .errors
+                            .push(format!("line {n}: checksum verification failed"));
+                        continue;
+                    }
+                    None => (),
+                }
+                line = event;
+            }
+
+            let res = match report.file_type {
+                Some(FileType::Event) => serde_json::from_str(&line)
+                    .map_err(|e| anyhow!("invalid json: {e}"))
+                    .and_then(|obj| report.add_event(obj))
+                    .map(|event| vec![event]),
+                _ => serde_json::from_str::<Vec<HashMap<String, serde_json::Value>>>(&line)
+                    .map_err(|e| anyhow!("invalid json: {e}"))
+                    .and_then(|objs| {
+                        report.series += 1;
+                        objs.into_iter().map(|obj| report.add_event(obj)).collect()
+                    }),
+            };
+            let events = match res {
+                Ok(events) => events,
+                Err(e) => {
+                    report.errors.push(format!("line {n}: {e}"));
+                    continue;
+                }
+            };
+
+            if let (Some(block), Some(common)) = (
+                block,
+                events
+                    .first()
+                    .and_then(|e| e.get_section::<CommonEvent>(SectionId::Common)),
+            ) {
+                if common.timestamp < block.ts_min || common.timestamp > block.ts_max {
+                    report.errors.push(format!(
+                        "line {n}: timestamp {} out of its index entry range",
+                        common.timestamp
+                    ));
+                }
+            }
+
+            if let Some(FileType::Series) = report.file_type {
+                let mut expected = 0;
+                for info in events
+                    .iter()
+                    .filter_map(|e| e.get_section::<TrackingInfo>(SectionId::Tracking))
+                {
+                    if info.idx != expected {
+                        report.gaps.push(format!(
+                            "line {n}: series jumps from index {} to {}",
+                            expected as i64 - 1,
+                            info.idx
+                        ));
+                    }
+                    expected = info.idx + 1;
+                }
+            }
+        }
+
+        if next_entry < entries.len() {
+            report.errors.push(format!(
+                "index: {} entries point past the last event",
+                entries.len() - next_entry
+            ));
+        }
+
+        Ok(report)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
     use super::*;
+    use crate::TimeSpec;
     #[test]
     fn read_from_file() {
         let mut fact = FileEventsFactory::new("test_data/test_events.json").unwrap();
@@ -116,4 +740,273 @@ mod tests {
         }
         assert!(events.len() == 4);
     }
+
+    #[test]
+    fn read_indexed_file() {
+        let mut fact = FileEventsFactory::new("test_data/test_events.json").unwrap();
+        assert!(fact.index().is_none());
+
+        let path = std::env::temp_dir().join(format!("retis-index-{}.data", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        let mut builder = FileIndexBuilder::new(&path).unwrap();
+        builder.block_size = 1;
+
+        let mut timestamps = Vec::new();
+        while let Some(event) = fact.next_event().unwrap() {
+            let mut line = serde_json::to_vec(&event.to_json()).unwrap();
+            line.push(b'\n');
+            file.write_all(&line).unwrap();
+            builder.record(&event, line.len() as u64);
+            timestamps.push(
+                event
+                    .get_section::<CommonEvent>(SectionId::Common)
+                    .unwrap()
+                    .timestamp,
+            );
+        }
+        drop(file);
+        builder.write().unwrap();
+
+        let mut fact = FileEventsFactory::new(&path).unwrap();
+        assert_eq!(fact.index().unwrap().entries.len(), 4);
+        assert_eq!(fact.index().unwrap().entries[1].cpus, vec![2]);
+        let mut count = 0;
+        while fact.next_event().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 4);
+
+        // Only events in the range are returned.
+        let mut fact = FileEventsFactory::new(&path).unwrap();
+        fact.set_time_range(Some(timestamps[1]), Some(timestamps[2]));
+        let mut events = Vec::new();
+        while let Some(event) = fact.next_event().unwrap() {
+            events.push(
+                event
+                    .get_section::<CommonEvent>(SectionId::Common)
+                    .unwrap()
+                    .timestamp,
+            );
+        }
+        assert_eq!(events, timestamps[1..3]);
+
+        // The index is not part of the events file: every line is an event
+        // and readers not using the index get all of them.
+        let lines = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(lines.lines().count(), 4);
+        lines
+            .lines()
+            .for_each(|line| assert!(Event::from_json(line.to_string()).is_ok()));
+        std::fs::remove_file(index_path(&path)).unwrap();
+        let mut fact = FileEventsFactory::new(&path).unwrap();
+        assert!(fact.index().is_none());
+        fact.set_time_range(Some(timestamps[1]), Some(timestamps[2]));
+        let mut count = 0;
+        while fact.next_event().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stale_index() {
+        let path = std::env::temp_dir().join(format!("retis-stale-{}.data", std::process::id()));
+        let events = std::fs::read_to_string("test_data/test_events.json").unwrap();
+        let mut lines = events.lines();
+        let mut file = File::create(&path).unwrap();
+        let mut builder = FileIndexBuilder::new(&path).unwrap();
+
+        let line = format!("{}\n", lines.next().unwrap());
+        file.write_all(line.as_bytes()).unwrap();
+        builder.record(&Event::from_json(line.clone()).unwrap(), line.len() as u64);
+        builder.write().unwrap();
+        assert!(FileEventsFactory::new(&path).unwrap().index().is_some());
+
+        // Events appended w/o updating the index: it is not used anymore.
+        writeln!(file, "{}", lines.next().unwrap()).unwrap();
+        drop(file);
+        let mut fact = FileEventsFactory::new(&path).unwrap();
+        assert!(fact.index().is_none());
+        let mut count = 0;
+        while fact.next_event().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        let report = FileEventsFactory::validate(&path).unwrap();
+        assert!(!report.indexed);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("index: Index covers"));
+
+        // Starting a new file removes the index of the previous one.
+        FileIndexBuilder::new(&path).unwrap();
+        assert!(!index_path(&path).exists());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn append_sessions() {
+        let path = std::env::temp_dir().join(format!("retis-append-{}.data", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(index_path(&path));
+
+        let write_session = |id: u64, n: u64, clock_source: Option<ClockSource>| {
+            let (mut file, builder) = open_for_append(&path).unwrap();
+            let mut builder = builder.unwrap();
+
+            let mut startup = Event::new();
+            startup
+                .insert_section(
+                    SectionId::Startup,
+                    Box::new(StartupEvent {
+                        retis_version: "test".to_string(),
+                        clock_monotonic_offset: TimeSpec::new(0, 0),
+                        session_id: Some(id),
+                        clock_source,
+                    }),
+                )
+                .unwrap();
+            let events = (0..n).map(|ts| {
+                let mut event = Event::new();
+                event
+                    .insert_section(
+                        SectionId::Common,
+                        Box::new(CommonEvent {
+                            timestamp: ts,
+                            session_id: Some(id),
+                            ..Default::default()
+                        }),
+                    )
+                    .unwrap();
+                event
+            });
+
+            for event in std::iter::once(startup).chain(events) {
+                let mut line = serde_json::to_vec(&event.to_json()).unwrap();
+                line.push(b'\n');
+                file.write_all(&line).unwrap();
+                builder.record(&event, line.len() as u64);
+            }
+            builder.write().unwrap();
+        };
+        write_session(1, 3, None);
+        write_session(2, 1, Some(ClockSource::Boottime));
+
+        // The index of the first session was resumed, not buried.
+        let report = FileEventsFactory::validate(&path).unwrap();
+        assert!(report.is_valid());
+        assert!(report.indexed);
+        assert_eq!(report.events, 6);
+
+        let sessions = FileEventsFactory::sessions(&path).unwrap();
+        assert_eq!(
+            sessions,
+            vec![
+                FileSession {
+                    id: Some(1),
+                    retis_version: Some("test".to_string()),
+                    clock_source: Some(ClockSource::Monotonic),
+                    events: 4,
+                },
+                FileSession {
+                    id: Some(2),
+                    retis_version: Some("test".to_string()),
+                    clock_source: Some(ClockSource::Boottime),
+                    events: 2,
+                },
+            ]
+        );
+
+        std::fs::remove_file(index_path(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn validate_file() {
+        let report = FileEventsFactory::validate("test_data/test_events.json").unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.events, 4);
+        assert!(report.first_ts.is_some() && report.first_ts <= report.last_ts);
+        assert!(report.unknown_sections.is_empty());
+
+        let report = FileEventsFactory::validate("test_data/test_events_sorted.json").unwrap();
+        assert!(report.is_valid());
+        assert!(report.series > 0);
+        assert!(report.gaps.is_empty());
+
+        let path = std::env::temp_dir().join(format!("retis-validate-{}.data", std::process::id()));
+        let events = std::fs::read_to_string("test_data/test_events.json").unwrap();
+        let mut lines = events.lines();
+        let mut file = File::create(&path).unwrap();
+        // An event with an unknown section.
+        let mut event: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        event["future-module"] = serde_json::json!({"foo": 1});
+        writeln!(file, "{event}").unwrap();
+        // A valid event, an invalid line and a truncated event.
+        writeln!(file, "{}", lines.next().unwrap()).unwrap();
+        writeln!(file, "{{\"common\":").unwrap();
+        let truncated = lines.next().unwrap();
+        write!(file, "{}", &truncated[..truncated.len() / 2]).unwrap();
+        drop(file);
+
+        let report = FileEventsFactory::validate(&path).unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.events, 2);
+        assert_eq!(report.unknown_sections.get("future-module"), Some(&1));
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].starts_with("line 3: invalid json"));
+        assert_eq!(report.errors[1], "line 4: truncated");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn checksums() {
+        let path = std::env::temp_dir().join(format!("retis-checksum-{}.data", std::process::id()));
+        let events = std::fs::read_to_string("test_data/test_events.json").unwrap();
+        let mut file = File::create(&path).unwrap();
+        for (i, event) in events.lines().enumerate() {
+            let mut line = event.as_bytes().to_vec();
+            append_checksum(&mut line);
+            // Tamper with the second event.
+            if i == 1 {
+                line = String::from_utf8(line)
+                    .unwrap()
+                    .replacen("\"timestamp\":", "\"timestamp\":1", 1)
+                    .into_bytes();
+            }
+            line.push(b'\n');
+            file.write_all(&line).unwrap();
+        }
+        drop(file);
+
+        // Events failing verification are skipped, others are unchanged.
+        let mut fact = FileEventsFactory::new(&path).unwrap();
+        let mut read = Vec::new();
+        while let Some(event) = fact.next_event().unwrap() {
+            read.push(event.to_json());
+        }
+        let expected = events
+            .lines()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, e)| Event::from_json(e.to_string()).unwrap().to_json())
+            .collect::<Vec<_>>();
+        assert_eq!(read, expected);
+
+        let report = FileEventsFactory::validate(&path).unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.checksums, 3);
+        assert_eq!(report.events, 3);
+        assert_eq!(report.errors, vec!["line 2: checksum verification failed"]);
+
+        // Events w/o a checksum are left alone.
+        let line = "{\"common\":{}}\n".to_string();
+        assert_eq!(split_checksum(line.clone()), (line, None));
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -54,6 +54,61 @@ pub(crate) fn protocol_str(protocol: u8) -> Option<&'static str> {
     })
 }
 
+/// Returns the name of some Linux errno values. Negative values, as returned by
+/// the kernel, are translated too.
+pub fn errno_str(errno: i32) -> Option<&'static str> {
+    Some(match errno.unsigned_abs() {
+        1 => "EPERM",
+        2 => "ENOENT",
+        3 => "ESRCH",
+        4 => "EINTR",
+        5 => "EIO",
+        6 => "ENXIO",
+        7 => "E2BIG",
+        9 => "EBADF",
+        11 => "EAGAIN",
+        12 => "ENOMEM",
+        13 => "EACCES",
+        14 => "EFAULT",
+        16 => "EBUSY",
+        17 => "EEXIST",
+        19 => "ENODEV",
+        22 => "EINVAL",
+        24 => "EMFILE",
+        27 => "EFBIG",
+        28 => "ENOSPC",
+        32 => "EPIPE",
+        34 => "ERANGE",
+        71 => "EPROTO",
+        75 => "EOVERFLOW",
+        90 => "EMSGSIZE",
+        95 => "EOPNOTSUPP",
+        97 => "EAFNOSUPPORT",
+        105 => "ENOBUFS",
+        110 => "ETIMEDOUT",
+        111 => "ECONNREFUSED",
+        _ => return None,
+    })
+}
+
+/// Returns the name of the errno held by a kernel return code, if it reports
+/// an error (i.e. is negative) and the errno is known.
+pub fn ret_errno_str(ret: i32) -> Option<&'static str> {
+    match ret {
+        ..=-1 => errno_str(ret),
+        _ => None,
+    }
+}
+
+/// Formats an error code, along with the name of its errno if known (e.g.
+/// "ENOBUFS (-105)", or "-42" if not known).
+pub fn errno_fmt(code: i32, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{name} ({code})"),
+        None => code.to_string(),
+    }
+}
+
 /// u128 representation in the events. We can't use the Rust primitive as serde
 /// does not handle the type well.
 #[event_type]
@@ -23,6 +23,8 @@ pub mod common;
 pub use common::*;
 pub mod ct;
 pub use ct::*;
+pub mod extension;
+pub use extension::*;
 pub mod kernel;
 pub use kernel::*;
 pub mod nft;
@@ -1,10 +1,14 @@
 use std::fmt;
 
-use anyhow::{bail, Result};
-use serde::{de::Error as Derror, ser::Error as Serror, Deserialize, Deserializer, Serializer};
+use anyhow::Result;
+use serde::{de::Error as Derror, Deserialize, Deserializer, Serializer};
 
 use super::*;
-use crate::{event_section, event_type, Formatter};
+use crate::{
+    event_section, event_type,
+    helpers::{errno_fmt, errno_str, etype_str},
+    Formatter,
+};
 
 ///The OVS Event
 #[event_section(SectionId::Ovs)]
@@ -15,12 +19,28 @@ pub struct OvsEvent {
     pub event: OvsEventType,
 }
 
+impl OvsEvent {
+    /// Does the event represent a change made to the packet, i.e. the
+    /// execution of a mutating action? See `OvsAction::is_mutating`.
+    pub fn is_mutating(&self) -> bool {
+        match &self.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(action),
+                ..
+            }) => action.is_mutating(),
+            _ => false,
+        }
+    }
+}
+
 impl EventFmt for OvsEvent {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         self.event.event_fmt(f, format)
     }
 }
 
+// Action events are the most common ones, boxing them would not save memory.
+#[allow(clippy::large_enum_variant)]
 #[event_type]
 #[serde(tag = "event_type")]
 #[derive(PartialEq)]
@@ -53,6 +73,21 @@ pub enum OvsEventType {
     Action(ActionEvent),
 }
 
+impl OvsEventType {
+    /// Name of the event type, as used in the JSON representation.
+    pub fn name(&self) -> &'static str {
+        use OvsEventType::*;
+        match self {
+            Upcall(_) => "upcall",
+            UpcallEnqueue(_) => "upcall_enqueue",
+            UpcallReturn(_) => "upcall_return",
+            RecvUpcall(_) => "recv_upcall",
+            Operation(_) => "flow_operation",
+            Action(_) => "action_execute",
+        }
+    }
+}
+
 impl EventFmt for OvsEventType {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         use OvsEventType::*;
@@ -69,13 +104,14 @@ impl EventFmt for OvsEventType {
     }
 }
 
-fn fmt_upcall_cmd(cmd: u8) -> &'static str {
+/// Name of an upcall command (OVS_PACKET_CMD_*).
+pub fn upcall_cmd_str(cmd: u8) -> &'static str {
     match cmd {
-        0 => " (unspec)",
-        1 => " (miss)",
-        2 => " (action)",
-        3 => " (exec)",
-        _ => "",
+        0 => "unspec",
+        1 => "miss",
+        2 => "action",
+        3 => "exec",
+        _ => "unknown",
     }
 }
 
@@ -89,7 +125,8 @@ pub struct UpcallEvent {
     ///   OVS_PACKET_CMD_ACTION   = 2
     ///   OVS_PACKET_CMD_EXECUTE  = 3
     pub cmd: u8,
-    /// Upcall port.
+    /// Netlink port id the upcall is sent to, identifying the ovs-vswitchd
+    /// handler socket (and thread) receiving it.
     pub port: u32,
     /// Cpu ID
     pub cpu: u32,
@@ -99,21 +136,29 @@ impl EventFmt for UpcallEvent {
     fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
         write!(
             f,
-            "upcall{} port {} cpu {}",
-            fmt_upcall_cmd(self.cmd),
+            "upcall ({}) port {} cpu {}",
+            upcall_cmd_str(self.cmd),
             self.port,
             self.cpu
         )
     }
 }
 
+/// Writes an upcall return code, along with the name of its errno if known.
+fn write_ret(f: &mut Formatter, ret: i32, ret_str: Option<&str>) -> fmt::Result {
+    write!(f, "ret {}", errno_fmt(ret, ret_str))
+}
+
 /// Upcall enqueue event.
 #[event_type]
-#[derive(Copy, Default, PartialEq)]
+#[derive(Default, PartialEq)]
 pub struct UpcallEnqueueEvent {
     /// Return code. Any value different from zero indicates the upcall enqueue
     /// failed probably indicating a packet drop.
     pub ret: i32,
+    /// Name of the errno held by the return code, if it reports an error (e.g.
+    /// "ENOBUFS" when the netlink socket buffer is full).
+    pub ret_str: Option<String>,
     /// Upcall command executed.
     pub cmd: u8,
     /// Upcall port id.
@@ -127,44 +172,53 @@ pub struct UpcallEnqueueEvent {
 }
 
 impl EventFmt for UpcallEnqueueEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         write!(
             f,
-            "upcall_enqueue{} ({}/{}) q {} ret {}",
-            fmt_upcall_cmd(self.cmd),
+            "upcall_enqueue ({}) ({}/{}) q {} ",
+            upcall_cmd_str(self.cmd),
             self.upcall_cpu,
             self.upcall_ts,
             self.queue_id,
-            self.ret
-        )
+        )?;
+        write_ret(f, self.ret, self.ret_str.as_deref())?;
+        if format.multiline {
+            write!(f, " port {}", self.port)?;
+        }
+        Ok(())
     }
 }
 
 /// Upcall return event
 #[event_type]
-#[derive(Copy, Default, PartialEq)]
+#[derive(Default, PartialEq)]
 pub struct UpcallReturnEvent {
     pub upcall_ts: u64,
     pub upcall_cpu: u32,
     pub ret: i32,
+    /// Name of the errno held by the return code, if it reports an error.
+    pub ret_str: Option<String>,
+    /// Time between the first enqueue of the upcall and its return, in
+    /// nanoseconds. Only known at post-processing time.
+    pub latency_ns: Option<u64>,
 }
 
 impl EventFmt for UpcallReturnEvent {
     fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
-        write!(
-            f,
-            "upcall_ret ({}/{}) ret {}",
-            self.upcall_cpu, self.upcall_ts, self.ret
-        )
+        write!(f, "upcall_ret ({}/{}) ", self.upcall_cpu, self.upcall_ts)?;
+        write_ret(f, self.ret, self.ret_str.as_deref())?;
+        if let Some(latency) = self.latency_ns {
+            write!(f, " latency {latency}ns")?;
+        }
+        Ok(())
     }
 }
 
 /// Operation event.
 #[event_type]
-#[derive(Copy, Default, PartialEq)]
-#[repr(C)]
+#[derive(Default, PartialEq)]
 pub struct OperationEvent {
-    /// Operation type ("put" or "exec")
+    /// Operation type ("exec", "put", "del" or "get")
     #[serde(
         deserialize_with = "OperationEvent::deserialize_op",
         serialize_with = "OperationEvent::serialize_op"
@@ -176,15 +230,83 @@ pub struct OperationEvent {
     pub batch_ts: u64,
     /// Index within the batch
     pub batch_idx: u8,
+    /// Error returned by the kernel, if the operation failed
+    pub error: Option<i32>,
+    /// Fields matched by the flow, for flow_put operations
+    pub flow: Option<OvsFlowMatch>,
+}
+
+/// Fields matched by a datapath flow, decoded from its key and mask.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsFlowMatch {
+    /// Matched fields, in the order of the flow key. Fully wildcarded fields
+    /// are omitted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<OvsFlowField>,
+    /// The flow has a mask (megaflow). Flows without one are exact matches.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub masked: bool,
+    /// The key or the mask could not be fully retrieved.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// Field matched by a datapath flow.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsFlowField {
+    /// Field name (e.g. "nw_src"), or "key(<type>)" for key attributes not
+    /// known by Retis, whose value and mask are then in hexadecimal.
+    pub name: String,
+    /// Value of the field, masked.
+    pub value: String,
+    /// Mask of the field, if partially wildcarded. Prefix masks of addresses
+    /// are reported as their length.
+    pub mask: Option<String>,
+}
+
+impl fmt::Display for OvsFlowField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(mask) = &self.mask {
+            write!(f, "/{mask}")?;
+        }
+        Ok(())
+    }
 }
 
+impl EventFmt for OvsFlowMatch {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| field.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(f, "match({fields}")?;
+        if self.truncated {
+            write!(f, ",...")?;
+        }
+        write!(f, ")")
+    }
+}
+
+pub const OVS_OP_EXEC: u8 = 0;
+pub const OVS_OP_PUT: u8 = 1;
+pub const OVS_OP_DEL: u8 = 2;
+pub const OVS_OP_GET: u8 = 3;
+
 impl OperationEvent {
-    fn operation_str(op_type: u8) -> Result<&'static str> {
-        Ok(match op_type {
-            0 => "exec",
-            1 => "put",
-            x => bail!("Unknown operation type {x}"),
-        })
+    /// Name of an operation type (OVS_OP_*).
+    pub fn operation_str(op_type: u8) -> String {
+        match op_type {
+            OVS_OP_EXEC => "exec".to_string(),
+            OVS_OP_PUT => "put".to_string(),
+            OVS_OP_DEL => "del".to_string(),
+            OVS_OP_GET => "get".to_string(),
+            x => format!("unknown({x})"),
+        }
     }
 
     fn deserialize_op<'de, D>(deserializer: D) -> Result<u8, D::Error>
@@ -193,11 +315,15 @@ impl OperationEvent {
     {
         let st = String::deserialize(deserializer)?;
         match st.as_str() {
-            "exec" => Ok(0),
-            "put" => Ok(1),
-            other => Err(D::Error::custom(format!(
-                "Unknown operation string {other}"
-            ))),
+            "exec" => Ok(OVS_OP_EXEC),
+            "put" => Ok(OVS_OP_PUT),
+            "del" => Ok(OVS_OP_DEL),
+            "get" => Ok(OVS_OP_GET),
+            other => other
+                .strip_prefix("unknown(")
+                .and_then(|op| op.strip_suffix(')'))
+                .and_then(|op| op.parse().ok())
+                .ok_or_else(|| D::Error::custom(format!("Unknown operation string {other}"))),
         }
     }
 
@@ -205,20 +331,31 @@ impl OperationEvent {
     where
         S: Serializer,
     {
-        serializer.serialize_str(OperationEvent::operation_str(*op_type).map_err(S::Error::custom)?)
+        serializer.serialize_str(&OperationEvent::operation_str(*op_type))
     }
 }
 
 impl EventFmt for OperationEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         write!(
             f,
             "flow_{} q {} ts {} ({})",
-            OperationEvent::operation_str(self.op_type).unwrap_or("?"),
+            OperationEvent::operation_str(self.op_type),
             self.queue_id,
             self.batch_ts,
             self.batch_idx
-        )
+        )?;
+
+        if let Some(error) = self.error {
+            write!(f, " error {}", errno_fmt(error, errno_str(error)))?;
+        }
+
+        if let Some(flow) = &self.flow {
+            write!(f, " ")?;
+            flow.event_fmt(f, format)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -226,7 +363,7 @@ impl EventFmt for OperationEvent {
 #[event_type]
 #[derive(Copy, Default, PartialEq)]
 pub struct RecvUpcallEvent {
-    /// Type of upcall
+    /// Type of upcall, holds the upcall command (OVS_PACKET_CMD_*).
     pub r#type: u32,
     /// Packet size
     pub pkt_size: u32,
@@ -241,13 +378,83 @@ pub struct RecvUpcallEvent {
 }
 
 impl EventFmt for RecvUpcallEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
-        // FIXME: there are more fields.
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         write!(
             f,
-            "upcall_recv q {} pkt_size {}",
-            self.queue_id, self.pkt_size
-        )
+            "upcall_recv ({}) q {} pkt_size {}",
+            u8::try_from(self.r#type).map_or("unknown", upcall_cmd_str),
+            self.queue_id,
+            self.pkt_size
+        )?;
+        if format.multiline {
+            write!(
+                f,
+                " key_size {} ts {} ({})",
+                self.key_size, self.batch_ts, self.batch_idx
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl OvsAction {
+    /// Does the action modify the packet (headers, encapsulation, length or
+    /// conntrack state)? Other actions only forward, observe or redirect it.
+    pub fn is_mutating(&self) -> bool {
+        use OvsAction::*;
+        match self {
+            Set(_) | SetMasked(_) | PushVlan(_) | PopVlan(_) | PushMpls(_) | PopMpls(_)
+            | AddMpls(_) | Ct(_) | CtClear(_) | Trunc(_) | PushEth(_) | PopEth(_) | PushNsh(_)
+            | PopNsh(_) | DecTtl(_) => true,
+            Output(_) | Userspace(_) | Sample(_) | Recirc(_) | Hash(_) | Meter(_) | Clone(_)
+            | CheckPktLen(_) | Drop(_) | Psample(_) | Unknown(_) => false,
+        }
+    }
+
+    /// Does the action report details (e.g. the output port), in addition to
+    /// its type?
+    pub fn has_detail(&self) -> bool {
+        use OvsAction::*;
+        match self {
+            Output(_) | Userspace(_) | Set(_) | SetMasked(_) | PushVlan(_) | PopVlan(_)
+            | Sample(_) | Recirc(_) | Hash(_) | PushMpls(_) | Ct(_) | Trunc(_) | PushEth(_)
+            | PopNsh(_) | Meter(_) | Clone(_) | CheckPktLen(_) | AddMpls(_) | DecTtl(_) => true,
+            PopMpls(_) | PopEth(_) | CtClear(_) | PushNsh(_) | Drop(_) | Psample(_)
+            | Unknown(_) => false,
+        }
+    }
+
+    /// Name of the action, as used in the JSON representation.
+    pub fn name(&self) -> &'static str {
+        use OvsAction::*;
+        match self {
+            Output(_) => "output",
+            Userspace(_) => "userspace",
+            Set(_) => "set",
+            PushVlan(_) => "push_vlan",
+            PopVlan(_) => "pop_vlan",
+            Sample(_) => "sample",
+            Recirc(_) => "recirc",
+            Hash(_) => "hash",
+            PushMpls(_) => "push_mpls",
+            PopMpls(_) => "pop_mpls",
+            SetMasked(_) => "set_masked",
+            Ct(_) => "ct",
+            Trunc(_) => "trunc",
+            PushEth(_) => "push_eth",
+            PopEth(_) => "pop_eth",
+            CtClear(_) => "ct_clear",
+            PushNsh(_) => "push_nsh",
+            PopNsh(_) => "pop_nsh",
+            Meter(_) => "meter",
+            Clone(_) => "clone",
+            CheckPktLen(_) => "check_pkt_len",
+            AddMpls(_) => "add_mpls",
+            DecTtl(_) => "dec_ttl",
+            Drop(_) => "drop",
+            Psample(_) => "psample",
+            Unknown(_) => "unknown",
+        }
     }
 }
 
@@ -264,30 +471,132 @@ pub struct ActionEvent {
     /// an upcall.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub queue_id: Option<u32>,
+    /// Error returned by the execution of the action list, reported against its last executed
+    /// action (the one which failed). None if the execution succeeded or is not finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<i32>,
+    /// Whether the action details were reported: false for actions having no
+    /// detail (e.g. ct_clear), true once the details of the others were
+    /// parsed. None if they are missing (e.g. they failed to be retrieved).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_detail: Option<bool>,
 }
 
 impl EventFmt for ActionEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
-        if self.recirc_id != 0 {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        // The recirculation id is only shown in the compact format when the
+        // packet was recirculated.
+        if self.recirc_id != 0 || format.multiline {
             write!(f, "[recirc_id {:#x}] ", self.recirc_id)?;
         }
 
         write!(f, "exec")?;
 
         match &self.action {
-            Some(OvsAction::Output(a)) => write!(f, " oport {}", a.port)?,
-            Some(OvsAction::Userspace(_)) => write!(f, " userspace")?,
-            Some(OvsAction::Set(_)) => write!(f, " tunnel_set")?,
-            Some(OvsAction::PushVlan(_)) => write!(f, " push_vlan")?,
-            Some(OvsAction::PopVlan(_)) => write!(f, " pop_vlan")?,
-            Some(OvsAction::Sample(_)) => write!(f, " sample")?,
+            Some(OvsAction::Output(a)) => {
+                write!(f, " oport {}", a.port)?;
+                if let Some(name) = &a.out_port_name {
+                    write!(f, " ({name})")?;
+                }
+                match a.max_len {
+                    Some(len) => write!(f, " (truncated to {len})")?,
+                    None if a.truncated => write!(f, " (truncated)")?,
+                    None => (),
+                }
+            }
+            Some(OvsAction::Userspace(userspace)) => {
+                write!(f, " userspace")?;
+                userspace.event_fmt(f, format)?;
+            }
+            Some(OvsAction::Set(set)) => match set.key_type {
+                OVS_KEY_ATTR_TUNNEL => write!(f, " tunnel_set")?,
+                _ => {
+                    write!(f, " set")?;
+                    set.event_fmt(f, format)?;
+                }
+            },
+            Some(OvsAction::PushVlan(a)) => {
+                write!(f, " push_vlan")?;
+                if let Some(vid) = a.vlan_vid {
+                    write!(f, " vid {vid}")?;
+                }
+                if let Some(pcp) = a.vlan_pcp {
+                    write!(f, " pcp {pcp}")?;
+                }
+                if let Some(proto) = a.vlan_proto {
+                    write!(f, " proto")?;
+                    if let Some(name) = etype_str(proto) {
+                        write!(f, " {name}")?;
+                    }
+                    write!(f, " ({:#06x})", proto)?;
+                }
+            }
+            Some(OvsAction::PopVlan(a)) => {
+                write!(f, " pop_vlan")?;
+                if let Some(etype) = a.inner_ethertype {
+                    write!(f, " inner_ethertype")?;
+                    if let Some(name) = etype_str(etype) {
+                        write!(f, " {name}")?;
+                    }
+                    write!(f, " ({:#06x})", etype)?;
+                }
+            }
+            Some(OvsAction::Sample(sample)) => {
+                write!(f, " sample")?;
+                if let Some(probability) = sample.probability {
+                    write!(f, " probability {probability:.2}%")?;
+                }
+                if let Some(n) = sample.nested_actions {
+                    write!(f, " actions {n}")?;
+                }
+            }
             Some(OvsAction::Recirc(a)) => write!(f, " recirc {:#x}", a.id)?,
-            Some(OvsAction::Hash(_)) => write!(f, " hash")?,
-            Some(OvsAction::PushMpls(_)) => write!(f, " push_mpls")?,
+            Some(OvsAction::Hash(hash)) => {
+                write!(f, " hash")?;
+                match (hash.is_l4(), hash.is_symmetric()) {
+                    (true, true) => write!(f, "(l4, sym)")?,
+                    (true, false) => write!(f, "(l4)")?,
+                    _ => write!(f, "({})", hash.alg_str())?,
+                }
+                if let Some(basis) = hash.hash_basis {
+                    write!(f, " basis {basis:#x}")?;
+                }
+            }
+            Some(OvsAction::PushMpls(a)) => {
+                write!(f, " push_mpls")?;
+                if let Some(label) = a.label {
+                    write!(f, " label {label}")?;
+                }
+                if let Some(tc) = a.tc {
+                    write!(f, " tc {tc}")?;
+                }
+                if let Some(ttl) = a.ttl {
+                    write!(f, " ttl {ttl}")?;
+                }
+                if a.bos {
+                    write!(f, " bos")?;
+                }
+                if let Some(etype) = a.ethertype {
+                    write!(f, " ethertype")?;
+                    if let Some(name) = etype_str(etype) {
+                        write!(f, " {name}")?;
+                    }
+                    write!(f, " ({:#06x})", etype)?;
+                }
+            }
             Some(OvsAction::PopMpls(_)) => write!(f, " pop_mpls")?,
-            Some(OvsAction::SetMasked(_)) => write!(f, " set_masked")?,
+            Some(OvsAction::SetMasked(set)) => {
+                write!(f, " set_masked")?;
+                set.event_fmt(f, format)?;
+            }
             Some(OvsAction::Ct(ct)) => {
-                write!(f, " ct zone {}", ct.zone_id)?;
+                write!(f, " ct")?;
+                match (&ct.ct_direction, &ct.ct_state) {
+                    (Some(dir), Some(state)) => write!(f, "({dir}, {state})")?,
+                    (Some(dir), None) => write!(f, "({dir})")?,
+                    _ => (),
+                }
+                write!(f, " zone {}", ct.zone_id)?;
 
                 if let Some(nat) = &ct.nat {
                     write!(f, " nat")?;
@@ -347,18 +656,127 @@ impl EventFmt for ActionEvent {
                     }
                     write!(f, " {}", flags.join(","))?;
                 }
+
+                if let Some(mask) = ct.eventmask {
+                    write!(f, " eventmask({})", OvsActionCt::eventmask_str(mask))?;
+                }
+
+                if let Some(label) = &ct.label {
+                    write!(f, " label={label}")?;
+                    if let Some(mask) = &ct.label_mask {
+                        write!(f, "/{mask}")?;
+                    }
+                }
+
+                if let Some(mark) = ct.mark {
+                    write!(f, " mark={mark:#x}")?;
+                    if let Some(mask) = ct.mark_mask {
+                        write!(f, "/{mask:#x}")?;
+                    }
+                }
+            }
+            Some(OvsAction::Trunc(trunc)) => {
+                write!(f, " trunc")?;
+                if let Some(len) = trunc.max_len {
+                    write!(f, " max_len {len}")?;
+                }
+                if trunc.sampled {
+                    write!(f, " (sampled copy)")?;
+                }
+            }
+            Some(OvsAction::PushEth(a)) => {
+                write!(f, " push_eth")?;
+                if let (Some(src), Some(dst)) = (&a.src, &a.dst) {
+                    write!(f, " {src} > {dst}")?;
+                }
             }
-            Some(OvsAction::Trunc(_)) => write!(f, " trunc")?,
-            Some(OvsAction::PushEth(_)) => write!(f, " push_eth")?,
             Some(OvsAction::PopEth(_)) => write!(f, " pop_eth")?,
             Some(OvsAction::CtClear(_)) => write!(f, " ct_clear")?,
             Some(OvsAction::PushNsh(_)) => write!(f, " push_nsh")?,
-            Some(OvsAction::PopNsh(_)) => write!(f, " pop_nsh")?,
-            Some(OvsAction::Meter(_)) => write!(f, " meter")?,
-            Some(OvsAction::Clone(_)) => write!(f, " clone")?,
-            Some(OvsAction::CheckPktLen(_)) => write!(f, " check_pkt_len")?,
-            Some(OvsAction::AddMpls(_)) => write!(f, " add_mpls")?,
-            Some(OvsAction::DecTtl(_)) => write!(f, " dec_ttl")?,
+            Some(OvsAction::PopNsh(a)) => {
+                write!(f, " pop_nsh")?;
+                if let Some(etype) = a.inner_ethertype {
+                    write!(f, " inner_ethertype")?;
+                    if let Some(name) = etype_str(etype) {
+                        write!(f, " {name}")?;
+                    }
+                    write!(f, " ({:#06x})", etype)?;
+                }
+            }
+            Some(OvsAction::Meter(meter)) => {
+                write!(f, " meter(id={}", meter.id)?;
+                if let Some(color) = meter.color {
+                    match OvsActionMeter::color_str(color) {
+                        Some(name) => write!(f, " color={name}")?,
+                        None => write!(f, " color={color}")?,
+                    }
+                }
+                write!(f, ")")?;
+            }
+            Some(OvsAction::Clone(clone)) => {
+                write!(f, " clone")?;
+                match clone.cloned {
+                    Some(true) => write!(f, " (copy)")?,
+                    Some(false) => write!(f, " (no copy)")?,
+                    None => (),
+                }
+            }
+            Some(OvsAction::CheckPktLen(cpl)) => {
+                let actions = |actions: &Vec<String>| match actions.is_empty() {
+                    true => "drop".to_string(),
+                    false => actions.join(","),
+                };
+                write!(
+                    f,
+                    " check_pkt_len(size={},gt({}),le({})) taken {}",
+                    cpl.pkt_len,
+                    actions(&cpl.actions_if_greater),
+                    actions(&cpl.actions_if_less_equal),
+                    if cpl.greater { "gt" } else { "le" },
+                )?;
+                if cpl.truncated {
+                    write!(f, " (truncated)")?;
+                }
+            }
+            Some(OvsAction::AddMpls(a)) => {
+                write!(f, " add_mpls")?;
+                if let Some(label) = a.label {
+                    write!(f, " label {label}")?;
+                }
+                if let Some(tc) = a.tc {
+                    write!(f, " tc {tc}")?;
+                }
+                if let Some(ttl) = a.ttl {
+                    write!(f, " ttl {ttl}")?;
+                }
+                if a.bos {
+                    write!(f, " bos")?;
+                }
+                if let Some(etype) = a.ethertype {
+                    write!(f, " ethertype")?;
+                    if let Some(name) = etype_str(etype) {
+                        write!(f, " {name}")?;
+                    }
+                    write!(f, " ({:#06x})", etype)?;
+                }
+                if let Some(position) = a.position {
+                    write!(f, " position {position}")?;
+                }
+                if let Some(flags) = a.unknown_flags {
+                    write!(f, " tun_flags {:#x}", flags)?;
+                }
+            }
+            Some(OvsAction::DecTtl(dec_ttl)) => {
+                write!(f, " dec_ttl")?;
+                match dec_ttl.has_action {
+                    Some(true) => write!(f, "(le_1(actions))")?,
+                    Some(false) => write!(f, "(le_1(drop))")?,
+                    None => (),
+                }
+            }
+            Some(OvsAction::Drop(_)) => write!(f, " drop")?,
+            Some(OvsAction::Psample(_)) => write!(f, " psample")?,
+            Some(OvsAction::Unknown(a)) => write!(f, " unknown({})", a.id)?,
             None => write!(f, " unspec")?,
         }
 
@@ -366,6 +784,10 @@ impl EventFmt for ActionEvent {
             write!(f, " q {}", p)?;
         }
 
+        if let Some(error) = self.error {
+            write!(f, " error {}", errno_fmt(error, errno_str(error)))?;
+        }
+
         Ok(())
     }
 }
@@ -383,31 +805,31 @@ pub enum OvsAction {
     #[serde(rename = "output")]
     Output(OvsActionOutput),
     #[serde(rename = "userspace")]
-    Userspace(OvsDummyAction),
+    Userspace(OvsActionUserspace),
     #[serde(rename = "set")]
-    Set(OvsDummyAction),
+    Set(OvsActionSet),
     #[serde(rename = "push_vlan")]
-    PushVlan(OvsDummyAction),
+    PushVlan(OvsActionPushVlan),
     #[serde(rename = "pop_vlan")]
-    PopVlan(OvsDummyAction),
+    PopVlan(OvsActionPopVlan),
     #[serde(rename = "sample")]
-    Sample(OvsDummyAction),
+    Sample(OvsActionSample),
     #[serde(rename = "recirc")]
     Recirc(OvsActionRecirc),
     #[serde(rename = "hash")]
-    Hash(OvsDummyAction),
+    Hash(OvsActionHash),
     #[serde(rename = "push_mpls")]
-    PushMpls(OvsDummyAction),
+    PushMpls(OvsActionPushMpls),
     #[serde(rename = "pop_mpls")]
     PopMpls(OvsDummyAction),
     #[serde(rename = "set_masked")]
-    SetMasked(OvsDummyAction),
+    SetMasked(OvsActionSet),
     #[serde(rename = "ct")]
     Ct(OvsActionCt),
     #[serde(rename = "trunc")]
-    Trunc(OvsDummyAction),
+    Trunc(OvsActionTrunc),
     #[serde(rename = "push_eth")]
-    PushEth(OvsDummyAction),
+    PushEth(OvsActionPushEth),
     #[serde(rename = "pop_eth")]
     PopEth(OvsDummyAction),
     #[serde(rename = "ct_clear")]
@@ -415,25 +837,67 @@ pub enum OvsAction {
     #[serde(rename = "push_nsh")]
     PushNsh(OvsDummyAction),
     #[serde(rename = "pop_nsh")]
-    PopNsh(OvsDummyAction),
+    PopNsh(OvsActionPopNsh),
     #[serde(rename = "meter")]
-    Meter(OvsDummyAction),
+    Meter(OvsActionMeter),
     #[serde(rename = "clone")]
-    Clone(OvsDummyAction),
+    Clone(OvsActionClone),
     #[serde(rename = "check_pkt_len")]
-    CheckPktLen(OvsDummyAction),
+    CheckPktLen(OvsActionCheckPktLen),
     #[serde(rename = "add_mpls")]
-    AddMpls(OvsDummyAction),
+    AddMpls(OvsActionAddMpls),
     #[serde(rename = "dec_ttl")]
-    DecTtl(OvsDummyAction),
+    DecTtl(OvsActionDecTtl),
+    #[serde(rename = "drop")]
+    Drop(OvsDummyAction),
+    #[serde(rename = "psample")]
+    Psample(OvsDummyAction),
+    /// Action not known by this version of Retis.
+    #[serde(rename = "unknown")]
+    Unknown(OvsActionUnknown),
 }
 
-/// OVS output action data.
+/// OVS action not known by this version of Retis.
 #[event_type]
 #[derive(Copy, Default, PartialEq)]
+pub struct OvsActionUnknown {
+    /// Action id (enum ovs_action_attr).
+    pub id: u8,
+}
+
+/// OVS output action data.
+#[event_type]
+#[derive(Default, PartialEq)]
 pub struct OvsActionOutput {
     /// Output port.
     pub port: u32,
+    /// Name of the datapath the output port belongs to, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dp_name: Option<String>,
+    /// Name of the output port, if resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_port_name: Option<String>,
+    /// The packet is truncated by a preceding trunc action. Only known at
+    /// post-processing time.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Effective length the packet is truncated to, i.e. the max_len of the
+    /// preceding trunc action, if known. Only known at post-processing time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<u32>,
+}
+
+/// OVS trunc action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionTrunc {
+    /// The truncation applies to a copy of the packet made by a sample action,
+    /// not to the packet itself. Only known at post-processing time.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sampled: bool,
+    /// Maximum length of the packet sent by the next output action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<u32>,
 }
 
 /// OVS recirc action data.
@@ -444,93 +908,827 @@ pub struct OvsActionRecirc {
     pub id: u32,
 }
 
-/// OVS conntrack flags
-pub const R_OVS_CT_COMMIT: u32 = 1 << 0;
-pub const R_OVS_CT_FORCE: u32 = 1 << 1;
-pub const R_OVS_CT_IP4: u32 = 1 << 2;
-pub const R_OVS_CT_IP6: u32 = 1 << 3;
-pub const R_OVS_CT_NAT: u32 = 1 << 4;
-pub const R_OVS_CT_NAT_SRC: u32 = 1 << 5;
-pub const R_OVS_CT_NAT_DST: u32 = 1 << 6;
-pub const R_OVS_CT_NAT_RANGE_MAP_IPS: u32 = 1 << 7;
-pub const R_OVS_CT_NAT_RANGE_PROTO_SPECIFIED: u32 = 1 << 8;
-pub const R_OVS_CT_NAT_RANGE_PROTO_RANDOM: u32 = 1 << 9;
-pub const R_OVS_CT_NAT_RANGE_PERSISTENT: u32 = 1 << 10;
-pub const R_OVS_CT_NAT_RANGE_PROTO_RANDOM_FULLY: u32 = 1 << 11;
+pub const OVS_HASH_ALG_L4: u32 = 0;
+pub const OVS_HASH_ALG_SYM_L4: u32 = 1;
 
-/// OVS conntrack action data.
+/// OVS hash action data.
 #[event_type]
-#[derive(Default, PartialEq)]
-pub struct OvsActionCt {
-    /// Flags
-    pub flags: u32,
-    /// Conntrack zone
-    pub zone_id: u16,
-    /// NAT
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionHash {
+    /// Hash algorithm (enum ovs_hash_alg), selecting the fields the hash is
+    /// computed over. Unknown values are kept as-is.
+    pub hash_alg: u32,
+    /// Basis the hash is computed with. Not reported by older versions.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub nat: Option<OvsActionCtNat>,
+    pub hash_basis: Option<u32>,
 }
 
-impl OvsActionCt {
-    pub fn is_commit(&self) -> bool {
-        self.flags & R_OVS_CT_COMMIT != 0
-    }
-    pub fn is_force(&self) -> bool {
-        self.flags & R_OVS_CT_FORCE != 0
-    }
-    #[allow(dead_code)]
-    pub fn is_ipv4(&self) -> bool {
-        self.flags & R_OVS_CT_IP4 != 0
+impl OvsActionHash {
+    /// Name of the hash algorithm, "unknown(<id>)" if not known.
+    pub fn alg_str(&self) -> String {
+        match self.hash_alg {
+            OVS_HASH_ALG_L4 => "l4".to_string(),
+            OVS_HASH_ALG_SYM_L4 => "sym_l4".to_string(),
+            x => format!("unknown({x})"),
+        }
     }
-    #[allow(dead_code)]
-    pub fn is_ipv6(&self) -> bool {
-        self.flags & R_OVS_CT_IP6 != 0
+
+    /// The hash includes both l3 and l4 fields.
+    pub fn is_l4(&self) -> bool {
+        matches!(self.hash_alg, OVS_HASH_ALG_L4 | OVS_HASH_ALG_SYM_L4)
     }
-    pub fn is_persistent(&self) -> bool {
-        self.flags & R_OVS_CT_NAT_RANGE_PERSISTENT != 0
+    /// The hash is symmetric, i.e. the same for both directions of a
+    /// connection.
+    pub fn is_symmetric(&self) -> bool {
+        self.hash_alg == OVS_HASH_ALG_SYM_L4
     }
-    pub fn is_hash(&self) -> bool {
-        self.flags & R_OVS_CT_NAT_RANGE_PROTO_RANDOM != 0
+}
+
+/// OVS meter action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionMeter {
+    /// Meter id.
+    pub id: u32,
+    /// Color the meter marked the packet with, if exposed by the datapath.
+    /// Unknown values are kept as-is.
+    pub color: Option<u32>,
+}
+
+impl OvsActionMeter {
+    /// Name of a meter color, if known.
+    pub fn color_str(color: u32) -> Option<&'static str> {
+        Some(match color {
+            0 => "green",
+            1 => "yellow",
+            2 => "red",
+            _ => return None,
+        })
     }
-    pub fn is_random(&self) -> bool {
-        self.flags & R_OVS_CT_NAT_RANGE_PROTO_RANDOM_FULLY != 0
+}
+
+/// Flow key attributes (enum ovs_key_attr) decoded in set actions.
+pub const OVS_KEY_ATTR_IPV4: u16 = 7;
+pub const OVS_KEY_ATTR_IPV6: u16 = 8;
+pub const OVS_KEY_ATTR_TUNNEL: u16 = 16;
+
+/// OVS set and set_masked action data. Only the fields modified by the action
+/// are reported.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionSet {
+    /// Flow key attribute (enum ovs_key_attr) being set.
+    pub key_type: u16,
+    /// Name of the flow key attribute being set (e.g. "ipv4" or "tunnel"),
+    /// "unknown(<key_type>)" if it is not known.
+    pub field: Option<String>,
+    /// IPv4 fields, if the action sets the IPv4 key.
+    pub ipv4: Option<OvsSetIpv4>,
+    /// IPv6 fields, if the action sets the IPv6 key.
+    pub ipv6: Option<OvsSetIpv6>,
+}
+
+impl EventFmt for OvsActionSet {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let mut comma = DelimWriter::new(',');
+
+        if let Some(ipv4) = &self.ipv4 {
+            write!(f, "(ipv4(")?;
+            if let Some(src) = &ipv4.src {
+                comma.write(f)?;
+                write!(f, "src={src}")?;
+            }
+            if let Some(dst) = &ipv4.dst {
+                comma.write(f)?;
+                write!(f, "dst={dst}")?;
+            }
+            if let Some(proto) = ipv4.proto {
+                comma.write(f)?;
+                write!(f, "proto={proto}")?;
+            }
+            if let Some(tos) = ipv4.tos {
+                comma.write(f)?;
+                write!(f, "tos={tos:#x}")?;
+            }
+            if let Some(ttl) = ipv4.ttl {
+                comma.write(f)?;
+                write!(f, "ttl={ttl}")?;
+            }
+            write!(f, "))")
+        } else if let Some(ipv6) = &self.ipv6 {
+            write!(f, "(ipv6(")?;
+            if let Some(src) = &ipv6.src {
+                comma.write(f)?;
+                write!(f, "src={src}")?;
+            }
+            if let Some(dst) = &ipv6.dst {
+                comma.write(f)?;
+                write!(f, "dst={dst}")?;
+            }
+            if let Some(label) = ipv6.label {
+                comma.write(f)?;
+                write!(f, "label={label:#x}")?;
+            }
+            if let Some(tclass) = ipv6.tclass {
+                comma.write(f)?;
+                write!(f, "tclass={tclass:#x}")?;
+            }
+            if let Some(hlimit) = ipv6.hlimit {
+                comma.write(f)?;
+                write!(f, "hlimit={hlimit}")?;
+            }
+            write!(f, "))")
+        } else if let Some(field) = &self.field {
+            write!(f, "({field})")
+        } else {
+            write!(f, "(key {})", self.key_type)
+        }
     }
 }
 
+/// IPv4 fields modified by a set action.
 #[event_type]
-#[derive(Default)]
-pub enum NatDirection {
-    #[default]
-    #[serde(rename = "src")]
-    Src,
-    #[serde(rename = "dst")]
-    Dst,
+#[derive(Default, PartialEq)]
+pub struct OvsSetIpv4 {
+    /// Source address.
+    pub src: Option<String>,
+    /// Destination address.
+    pub dst: Option<String>,
+    /// IP protocol.
+    pub proto: Option<u8>,
+    /// Type of service.
+    pub tos: Option<u8>,
+    /// Time to live.
+    pub ttl: Option<u8>,
 }
-/// OVS NAT action data.
+
+/// IPv6 fields modified by a set action.
 #[event_type]
 #[derive(Default, PartialEq)]
-pub struct OvsActionCtNat {
-    /// NAT direction, if any
-    pub dir: Option<NatDirection>,
-    /// Minimum address in address range, if any
-    pub min_addr: Option<String>,
-    /// Maximum address in address range, if any
-    pub max_addr: Option<String>,
-    /// Minimum port in port range, if any
-    pub min_port: Option<u16>,
-    /// Maximum port in port range, if any
-    pub max_port: Option<u16>,
+pub struct OvsSetIpv6 {
+    /// Source address.
+    pub src: Option<String>,
+    /// Destination address.
+    pub dst: Option<String>,
+    /// Flow label (20 bits).
+    pub label: Option<u32>,
+    /// Traffic class.
+    pub tclass: Option<u8>,
+    /// Hop limit.
+    pub hlimit: Option<u8>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use anyhow::{anyhow, Result};
-    use serde_json::Value;
+/// OVS push_vlan action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionPushVlan {
+    /// VLAN id (12 bits) of the pushed tag.
+    pub vlan_vid: Option<u16>,
+    /// Priority code point (3 bits) of the pushed tag.
+    pub vlan_pcp: Option<u8>,
+    /// Tag protocol identifier (e.g. 0x8100 for 802.1Q) of the pushed tag.
+    pub vlan_proto: Option<u16>,
+}
+
+impl OvsActionPushVlan {
+    /// Build the action data from the tag protocol identifier and tag control
+    /// information of the pushed tag, in host byte order.
+    pub fn from_tci(tpid: u16, tci: u16) -> Self {
+        Self {
+            vlan_vid: Some(tci & 0xfff),
+            vlan_pcp: Some((tci >> 13) as u8),
+            vlan_proto: Some(tpid),
+        }
+    }
+}
+
+/// OVS clone action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionClone {
+    /// The nested actions are executed on a copy of the packet. Clone actions
+    /// being the last of their action list don't copy the packet; analysis
+    /// tools should not account for it twice.
+    pub cloned: Option<bool>,
+}
+
+/// OVS push_eth action data.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionPushEth {
+    /// Source MAC address of the pushed Ethernet header.
+    pub src: Option<String>,
+    /// Destination MAC address of the pushed Ethernet header.
+    pub dst: Option<String>,
+}
+
+/// OVS pop_vlan action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionPopVlan {
+    /// Ethertype revealed by popping the vlan tag, if known. A vlan ethertype
+    /// (e.g. 802.1Q) means another tag is still present (QinQ).
+    pub inner_ethertype: Option<u16>,
+}
+
+/// OVS pop_nsh action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionPopNsh {
+    /// Ethertype of the packet revealed by removing the NSH header, if known.
+    pub inner_ethertype: Option<u16>,
+}
+
+/// Where an add_mpls action inserts the label.
+#[event_type]
+#[serde(rename_all = "snake_case")]
+#[derive(Copy, Eq)]
+pub enum OvsMplsPosition {
+    /// After the L2 header.
+    AfterL2,
+    /// At the start of the packet, which has no L2 header (L3 tunnel).
+    Start,
+}
+
+impl fmt::Display for OvsMplsPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::AfterL2 => "after_l2",
+            Self::Start => "start",
+        })
+    }
+}
+
+/// OVS push_mpls action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionPushMpls {
+    /// Label of the label stack entry being pushed.
+    pub label: Option<u32>,
+    /// Traffic class.
+    pub tc: Option<u8>,
+    /// Time to live.
+    pub ttl: Option<u8>,
+    /// The entry is the bottom of the label stack.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bos: bool,
+    /// Ethertype of the packet once the label is pushed.
+    pub ethertype: Option<u16>,
+}
+
+/// OVS add_mpls action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionAddMpls {
+    /// Label of the label stack entry being added.
+    pub label: Option<u32>,
+    /// Traffic class.
+    pub tc: Option<u8>,
+    /// Time to live.
+    pub ttl: Option<u8>,
+    /// The entry is the bottom of the label stack.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bos: bool,
+    /// Ethertype of the packet once the label is added.
+    pub ethertype: Option<u16>,
+    /// Where the label is inserted, if known.
+    pub position: Option<OvsMplsPosition>,
+    /// Tunnel flags not known by Retis.
+    pub unknown_flags: Option<u16>,
+}
+
+/// OVS check_pkt_len action data.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionCheckPktLen {
+    /// Packet length threshold.
+    pub pkt_len: u16,
+    /// The packet was longer than the threshold, i.e. the "greater" actions
+    /// were executed.
+    pub greater: bool,
+    /// Actions executed on packets longer than the threshold, in datapath
+    /// format. Nested check_pkt_len actions are included. An empty list means
+    /// the packet is dropped.
+    pub actions_if_greater: Vec<String>,
+    /// Actions executed on packets not longer than the threshold, in datapath
+    /// format.
+    pub actions_if_less_equal: Vec<String>,
+    /// The action lists could not be fully retrieved: they are too long or
+    /// too deeply nested.
+    pub truncated: bool,
+}
+
+/// OVS dec_ttl action data.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionDecTtl {
+    /// An action list is executed on packets whose TTL expires. If not, they
+    /// are dropped.
+    pub has_action: Option<bool>,
+}
+
+/// OVS sample action data.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionSample {
+    /// Probability of the packet being sampled, in percent.
+    pub probability: Option<f32>,
+    /// Number of actions executed on the sampled packet, if they could all be
+    /// retrieved. Nested actions (e.g. in a check_pkt_len) are not counted.
+    pub nested_actions: Option<u32>,
+}
+
+/// OVS userspace action data.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionUserspace {
+    /// Netlink port id the upcall is sent to.
+    pub pid: Option<u32>,
+    /// Opaque data passed to userspace (the cookie), in hexadecimal.
+    pub userdata: Option<String>,
+    /// Tunnel port the packet is being sent to, if the upcall was requested
+    /// on tunnel egress (e.g. for sampling).
+    pub egress_tun_port: Option<u32>,
+    /// The list of actions being executed is sent to userspace along with the
+    /// packet.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub actions: bool,
+    /// Attributes not known by Retis, kept raw.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_attrs: Vec<OvsUserspaceAttr>,
+    /// The attributes could not be fully retrieved.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// Raw userspace action attribute.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsUserspaceAttr {
+    /// Attribute type (enum ovs_userspace_attr).
+    pub attr_type: u16,
+    /// Attribute payload, in hexadecimal.
+    pub data: String,
+}
+
+impl EventFmt for OvsActionUserspace {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let mut args = Vec::new();
+        if let Some(pid) = self.pid {
+            args.push(format!("pid={pid}"));
+        }
+        if let Some(userdata) = &self.userdata {
+            args.push(format!("userdata({userdata})"));
+        }
+        if self.actions {
+            args.push("actions".to_string());
+        }
+        if let Some(port) = self.egress_tun_port {
+            args.push(format!("tunnel_out_port={port}"));
+        }
+        self.unknown_attrs
+            .iter()
+            .for_each(|attr| args.push(format!("unknown({}={})", attr.attr_type, attr.data)));
+
+        if !args.is_empty() {
+            write!(f, "({})", args.join(","))?;
+        }
+        if self.truncated {
+            write!(f, " (truncated)")?;
+        }
+        Ok(())
+    }
+}
+
+/// OVS conntrack flags
+pub const R_OVS_CT_COMMIT: u32 = 1 << 0;
+pub const R_OVS_CT_FORCE: u32 = 1 << 1;
+pub const R_OVS_CT_IP4: u32 = 1 << 2;
+pub const R_OVS_CT_IP6: u32 = 1 << 3;
+pub const R_OVS_CT_NAT: u32 = 1 << 4;
+pub const R_OVS_CT_NAT_SRC: u32 = 1 << 5;
+pub const R_OVS_CT_NAT_DST: u32 = 1 << 6;
+pub const R_OVS_CT_NAT_RANGE_MAP_IPS: u32 = 1 << 7;
+pub const R_OVS_CT_NAT_RANGE_PROTO_SPECIFIED: u32 = 1 << 8;
+pub const R_OVS_CT_NAT_RANGE_PROTO_RANDOM: u32 = 1 << 9;
+pub const R_OVS_CT_NAT_RANGE_PERSISTENT: u32 = 1 << 10;
+pub const R_OVS_CT_NAT_RANGE_PROTO_RANDOM_FULLY: u32 = 1 << 11;
+pub const R_OVS_CT_CTINFO: u32 = 1 << 12;
+pub const R_OVS_CT_EVENTMASK: u32 = 1 << 13;
+pub const R_OVS_CT_LABELS: u32 = 1 << 14;
+pub const R_OVS_CT_MARK: u32 = 1 << 15;
+
+/// OVS conntrack action data.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionCt {
+    /// Flags
+    pub flags: u32,
+    /// Conntrack zone
+    pub zone_id: u16,
+    /// NAT
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nat: Option<OvsActionCtNat>,
+    /// Direction of the packet in the connection ("orig" or "reply"), if known.
+    pub ct_direction: Option<String>,
+    /// Conntrack state of the packet ("new", "est" or "rel"), if known.
+    pub ct_state: Option<String>,
+    /// Conntrack events (IPCT_* bits) delivered for the connection, if restricted by the action.
+    /// When not set, all events are delivered.
+    pub eventmask: Option<u32>,
+    /// Conntrack labels set by the action (hex, 128 bits), if any.
+    pub label: Option<String>,
+    /// Mask of the conntrack labels set by the action (hex, 128 bits), if any.
+    pub label_mask: Option<String>,
+    /// Conntrack mark set by the action, if any.
+    pub mark: Option<u32>,
+    /// Mask of the conntrack mark set by the action, if not all ones.
+    pub mark_mask: Option<u32>,
+    /// Conntrack correlation key of the packet, if known. Only known at
+    /// post-processing time.
+    pub ct_key: Option<OvsCtKey>,
+}
+
+impl OvsActionCt {
+    pub fn is_commit(&self) -> bool {
+        self.flags & R_OVS_CT_COMMIT != 0
+    }
+    pub fn is_force(&self) -> bool {
+        self.flags & R_OVS_CT_FORCE != 0
+    }
+    #[allow(dead_code)]
+    pub fn is_ipv4(&self) -> bool {
+        self.flags & R_OVS_CT_IP4 != 0
+    }
+    #[allow(dead_code)]
+    pub fn is_ipv6(&self) -> bool {
+        self.flags & R_OVS_CT_IP6 != 0
+    }
+    pub fn is_persistent(&self) -> bool {
+        self.flags & R_OVS_CT_NAT_RANGE_PERSISTENT != 0
+    }
+    pub fn is_hash(&self) -> bool {
+        self.flags & R_OVS_CT_NAT_RANGE_PROTO_RANDOM != 0
+    }
+    pub fn is_random(&self) -> bool {
+        self.flags & R_OVS_CT_NAT_RANGE_PROTO_RANDOM_FULLY != 0
+    }
+
+    /// Formats a conntrack event mask as a comma separated list of event names. Unknown bits are
+    /// kept, in hexadecimal.
+    pub fn eventmask_str(mask: u32) -> String {
+        const EVENTS: [&str; 12] = [
+            "new",
+            "related",
+            "destroy",
+            "reply",
+            "assured",
+            "protoinfo",
+            "helper",
+            "mark",
+            "seqadj",
+            "secmark",
+            "label",
+            "synproxy",
+        ];
+
+        let mut events: Vec<String> = EVENTS
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| mask & (1 << bit) != 0)
+            .map(|(_, name)| name.to_string())
+            .collect();
+        let unknown = mask & !((1 << EVENTS.len()) - 1);
+        if unknown != 0 {
+            events.push(format!("{unknown:#x}"));
+        }
+        events.join(",")
+    }
+}
+
+/// Conntrack correlation key of a packet going through a ct action: its zone
+/// and 5-tuple, in the direction of the packet. The packet being seen before
+/// the action, this is the pre-NAT tuple in the original direction and the
+/// post-NAT one in the reply direction.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsCtKey {
+    /// Conntrack zone
+    pub zone_id: u16,
+    /// Source IP address
+    pub src: String,
+    /// Destination IP address
+    pub dst: String,
+    /// IP protocol
+    pub proto: u8,
+    /// Source port, 0 if not applicable (e.g. ICMP)
+    pub sport: u16,
+    /// Destination port, 0 if not applicable (e.g. ICMP)
+    pub dport: u16,
+}
+
+impl OvsCtKey {
+    /// Builds the correlation key of a packet in a conntrack zone, from its
+    /// headers.
+    pub fn from_skb(zone_id: u16, skb: &SkbEvent) -> Option<Self> {
+        let ip = skb.ip.as_ref()?;
+        let (sport, dport) = match (&skb.tcp, &skb.udp) {
+            (Some(tcp), _) => (tcp.sport, tcp.dport),
+            (_, Some(udp)) => (udp.sport, udp.dport),
+            _ => (0, 0),
+        };
+        Some(Self {
+            zone_id,
+            src: ip.saddr.clone(),
+            dst: ip.daddr.clone(),
+            proto: ip.protocol,
+            sport,
+            dport,
+        })
+    }
+
+    /// Does the key belong to a conntrack entry? As only the tuple of the
+    /// packet's direction is known, both tuples of the entry are checked.
+    pub fn matches(&self, conn: &CtConnEvent) -> bool {
+        self.zone_id == conn.zone_id
+            && (self.matches_tuple(&conn.orig) || self.matches_tuple(&conn.reply))
+    }
+
+    fn matches_tuple(&self, tuple: &CtTuple) -> bool {
+        let (proto, sport, dport) = match (&tuple.proto, &tuple.ip.version) {
+            (CtProto::Tcp(tcp), _) => (6, tcp.sport, tcp.dport),
+            (CtProto::Udp(udp), _) => (17, udp.sport, udp.dport),
+            (CtProto::Icmp(_), CtIpVersion::V4) => (1, 0, 0),
+            (CtProto::Icmp(_), CtIpVersion::V6) => (58, 0, 0),
+        };
+        self.src == tuple.ip.src
+            && self.dst == tuple.ip.dst
+            && self.proto == proto
+            && self.sport == sport
+            && self.dport == dport
+    }
+}
+
+#[event_type]
+#[derive(Default)]
+pub enum NatDirection {
+    #[default]
+    #[serde(rename = "src")]
+    Src,
+    #[serde(rename = "dst")]
+    Dst,
+}
+/// OVS NAT action data.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionCtNat {
+    /// NAT direction, if any
+    pub dir: Option<NatDirection>,
+    /// Minimum address in address range, if any
+    pub min_addr: Option<String>,
+    /// Maximum address in address range, if any
+    pub max_addr: Option<String>,
+    /// Minimum port in port range, if any
+    pub min_port: Option<u16>,
+    /// Maximum port in port range, if any
+    pub max_port: Option<u16>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use serde_json::Value;
+
+    #[test]
+    fn mutating_actions() {
+        let action = |action| OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(action),
+                recirc_id: 0,
+                queue_id: None,
+                error: None,
+                has_detail: None,
+            }),
+        };
+
+        assert!(action(OvsAction::SetMasked(OvsActionSet::default())).is_mutating());
+        assert!(action(OvsAction::PopVlan(OvsActionPopVlan::default())).is_mutating());
+        assert!(action(OvsAction::Ct(OvsActionCt::default())).is_mutating());
+        assert!(action(OvsAction::DecTtl(OvsActionDecTtl::default())).is_mutating());
+        assert!(!action(OvsAction::Output(OvsActionOutput::default())).is_mutating());
+        assert!(!action(OvsAction::Recirc(OvsActionRecirc::default())).is_mutating());
+        assert!(!OvsEvent {
+            event: OvsEventType::Upcall(UpcallEvent::default()),
+        }
+        .is_mutating());
+    }
+
+    #[test]
+    fn upcall_cmds() {
+        assert_eq!(upcall_cmd_str(0), "unspec");
+        assert_eq!(upcall_cmd_str(1), "miss");
+        assert_eq!(upcall_cmd_str(2), "action");
+        assert_eq!(upcall_cmd_str(3), "exec");
+        assert_eq!(upcall_cmd_str(4), "unknown");
+
+        let display = |event: OvsEventType| {
+            format!(
+                "{}",
+                OvsEvent { event }.display(&DisplayFormat::new(), &FormatterConf::new())
+            )
+        };
+        assert_eq!(
+            display(OvsEventType::Upcall(UpcallEvent {
+                cmd: 2,
+                port: 1,
+                cpu: 0,
+            })),
+            "upcall (action) port 1 cpu 0"
+        );
+        assert_eq!(
+            display(OvsEventType::RecvUpcall(RecvUpcallEvent {
+                r#type: 1,
+                queue_id: 3,
+                pkt_size: 98,
+                ..Default::default()
+            })),
+            "upcall_recv (miss) q 3 pkt_size 98"
+        );
+        assert_eq!(
+            display(OvsEventType::RecvUpcall(RecvUpcallEvent {
+                r#type: 0x100,
+                ..Default::default()
+            })),
+            "upcall_recv (unknown) q 0 pkt_size 0"
+        );
+    }
+
+    #[test]
+    fn display_formats() {
+        let display = |event: OvsEventType, multiline: bool| {
+            format!(
+                "{}",
+                OvsEvent { event }.display(
+                    &DisplayFormat::new().multiline(multiline),
+                    &FormatterConf::new()
+                )
+            )
+        };
+
+        let upcall = OvsEventType::Upcall(UpcallEvent {
+            cmd: 1,
+            port: 3,
+            cpu: 1,
+        });
+        assert_eq!(display(upcall.clone(), false), "upcall (miss) port 3 cpu 1");
+        assert_eq!(display(upcall, true), "upcall (miss) port 3 cpu 1");
+
+        let enqueue = OvsEventType::UpcallEnqueue(UpcallEnqueueEvent {
+            cmd: 1,
+            port: 3,
+            upcall_ts: 1000,
+            upcall_cpu: 1,
+            queue_id: 7,
+            ..Default::default()
+        });
+        assert_eq!(
+            display(enqueue.clone(), false),
+            "upcall_enqueue (miss) (1/1000) q 7 ret 0"
+        );
+        assert_eq!(
+            display(enqueue, true),
+            "upcall_enqueue (miss) (1/1000) q 7 ret 0 port 3"
+        );
+
+        let ret = OvsEventType::UpcallReturn(UpcallReturnEvent {
+            upcall_ts: 1000,
+            upcall_cpu: 1,
+            ret: -105,
+            ret_str: Some("ENOBUFS".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            display(ret, false),
+            "upcall_ret (1/1000) ret ENOBUFS (-105)"
+        );
+
+        let recv = OvsEventType::RecvUpcall(RecvUpcallEvent {
+            r#type: 1,
+            pkt_size: 98,
+            key_size: 120,
+            queue_id: 7,
+            batch_ts: 2000,
+            batch_idx: 2,
+        });
+        assert_eq!(
+            display(recv.clone(), false),
+            "upcall_recv (miss) q 7 pkt_size 98"
+        );
+        assert_eq!(
+            display(recv, true),
+            "upcall_recv (miss) q 7 pkt_size 98 key_size 120 ts 2000 (2)"
+        );
+
+        // Unset optional fields are omitted.
+        let action = OvsEventType::Action(ActionEvent {
+            action: Some(OvsAction::Output(OvsActionOutput {
+                port: 7,
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+        assert_eq!(display(action.clone(), false), "exec oport 7");
+        assert_eq!(display(action, true), "[recirc_id 0x0] exec oport 7");
+
+        // Set keys whose content is not decoded are reported by name.
+        let action = OvsEventType::Action(ActionEvent {
+            action: Some(OvsAction::SetMasked(OvsActionSet {
+                key_type: 4,
+                field: Some("eth".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+        assert_eq!(display(action, false), "exec set_masked(eth)");
+
+        let action = OvsEventType::Action(ActionEvent {
+            action: Some(OvsAction::Output(OvsActionOutput {
+                port: 7,
+                ..Default::default()
+            })),
+            recirc_id: 1,
+            queue_id: Some(3),
+            error: Some(-22),
+            has_detail: None,
+        });
+        assert_eq!(
+            display(action.clone(), false),
+            "[recirc_id 0x1] exec oport 7 q 3 error EINVAL (-22)"
+        );
+        assert_eq!(
+            display(action, true),
+            "[recirc_id 0x1] exec oport 7 q 3 error EINVAL (-22)"
+        );
+
+        // Unknown errnos are reported by number, as return codes.
+        let action = OvsEventType::Action(ActionEvent {
+            action: Some(OvsAction::Output(OvsActionOutput {
+                port: 7,
+                ..Default::default()
+            })),
+            error: Some(-42),
+            ..Default::default()
+        });
+        assert_eq!(display(action, false), "exec oport 7 error -42");
+
+        let op = OvsEventType::Operation(OperationEvent {
+            op_type: OVS_OP_PUT,
+            queue_id: 3,
+            batch_ts: 2000,
+            batch_idx: 1,
+            error: Some(-42),
+            flow: None,
+        });
+        assert_eq!(display(op, false), "flow_put q 3 ts 2000 (1) error -42");
+
+        let ret = OvsEventType::UpcallReturn(UpcallReturnEvent {
+            upcall_ts: 1000,
+            upcall_cpu: 1,
+            ret: -42,
+            ret_str: None,
+            ..Default::default()
+        });
+        assert_eq!(display(ret, false), "upcall_ret (1/1000) ret -42");
+    }
+
+    #[test]
+    fn ct_eventmask() {
+        let ct = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Ct(OvsActionCt {
+                    flags: R_OVS_CT_COMMIT | R_OVS_CT_FORCE | R_OVS_CT_EVENTMASK,
+                    eventmask: Some(1 << 0 | 1 << 2 | 1 << 20),
+                    label: Some(String::from("0x2a")),
+                    label_mask: Some(String::from("0xff")),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(
+            format!(
+                "{}",
+                ct.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec ct zone 0 commit,force eventmask(new,destroy,0x100000) label=0x2a/0xff"
+        );
+        assert_eq!(OvsActionCt::eventmask_str(0), "");
+    }
 
     #[test]
     fn test_event_to_from_json() -> Result<()> {
-        let events: [(&'static str, OvsEvent); 7] = [
+        let events: [(&'static str, OvsEvent); 39] = [
             // Upcall event
             (
                 r#"{"cmd":1,"cpu":0,"event_type":"upcall","port":4195744766}"#,
@@ -547,9 +1745,382 @@ mod tests {
                 r#"{"action":"output","event_type":"action_execute","port":2,"queue_id":1361394472,"recirc_id":0}"#,
                 OvsEvent {
                     event: OvsEventType::Action(ActionEvent {
-                        action: Some(OvsAction::Output(OvsActionOutput { port: 2 })),
+                        action: Some(OvsAction::Output(OvsActionOutput {
+                            port: 2,
+                            dp_name: None,
+                            out_port_name: None,
+                            truncated: false,
+                            max_len: None,
+                        })),
                         recirc_id: 0,
                         queue_id: Some(1361394472),
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Output action event with a resolved port name
+            (
+                r#"{"action":"output","event_type":"action_execute","out_port_name":"eth0","port":2,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Output(OvsActionOutput {
+                            port: 2,
+                            dp_name: None,
+                            out_port_name: Some("eth0".to_string()),
+                            truncated: false,
+                            max_len: None,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Trunc action event
+            (
+                r#"{"action":"trunc","event_type":"action_execute","max_len":100,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Trunc(OvsActionTrunc {
+                            sampled: false,
+                            max_len: Some(100),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Output action event after a trunc
+            (
+                r#"{"action":"output","event_type":"action_execute","max_len":100,"port":2,"recirc_id":0,"truncated":true}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Output(OvsActionOutput {
+                            port: 2,
+                            dp_name: None,
+                            out_port_name: None,
+                            truncated: true,
+                            max_len: Some(100),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Userspace action event, as reported by older versions
+            (
+                r#"{"action":"userspace","event_type":"action_execute","recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Userspace(OvsActionUserspace::default())),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Userspace action event
+            (
+                r#"{"action":"userspace","actions":true,"egress_tun_port":4,"event_type":"action_execute","pid":3,"recirc_id":0,"unknown_attrs":[{"attr_type":9,"data":"0a0b"}],"userdata":"01020304"}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Userspace(OvsActionUserspace {
+                            pid: Some(3),
+                            userdata: Some("01020304".to_string()),
+                            egress_tun_port: Some(4),
+                            actions: true,
+                            unknown_attrs: vec![OvsUserspaceAttr {
+                                attr_type: 9,
+                                data: "0a0b".to_string(),
+                            }],
+                            truncated: false,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Push vlan action event
+            (
+                r#"{"action":"push_vlan","event_type":"action_execute","recirc_id":0,"vlan_pcp":3,"vlan_proto":33024,"vlan_vid":100}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::PushVlan(OvsActionPushVlan {
+                            vlan_vid: Some(100),
+                            vlan_pcp: Some(3),
+                            vlan_proto: Some(0x8100),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Pop vlan action event
+            (
+                r#"{"action":"pop_vlan","event_type":"action_execute","inner_ethertype":33024,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::PopVlan(OvsActionPopVlan {
+                            inner_ethertype: Some(0x8100),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Hash action event
+            (
+                r#"{"action":"hash","event_type":"action_execute","hash_alg":1,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Hash(OvsActionHash {
+                            hash_alg: OVS_HASH_ALG_SYM_L4,
+                            hash_basis: None,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Hash action event with its basis
+            (
+                r#"{"action":"hash","event_type":"action_execute","hash_alg":1,"hash_basis":42,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Hash(OvsActionHash {
+                            hash_alg: OVS_HASH_ALG_SYM_L4,
+                            hash_basis: Some(42),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Pop nsh action event
+            (
+                r#"{"action":"pop_nsh","event_type":"action_execute","inner_ethertype":2048,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::PopNsh(OvsActionPopNsh {
+                            inner_ethertype: Some(0x0800),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Push MPLS action event
+            (
+                r#"{"action":"push_mpls","ethertype":34887,"event_type":"action_execute","label":100,"recirc_id":0,"tc":1,"ttl":64}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::PushMpls(OvsActionPushMpls {
+                            label: Some(100),
+                            tc: Some(1),
+                            ttl: Some(64),
+                            bos: false,
+                            ethertype: Some(0x8847),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Add MPLS action event
+            (
+                r#"{"action":"add_mpls","bos":true,"ethertype":34887,"event_type":"action_execute","label":100,"position":"start","recirc_id":0,"tc":1,"ttl":64,"unknown_flags":2}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::AddMpls(OvsActionAddMpls {
+                            label: Some(100),
+                            tc: Some(1),
+                            ttl: Some(64),
+                            bos: true,
+                            ethertype: Some(0x8847),
+                            position: Some(OvsMplsPosition::Start),
+                            unknown_flags: Some(2),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Check pkt len action event
+            (
+                r#"{"action":"check_pkt_len","actions_if_greater":["userspace"],"actions_if_less_equal":["2","recirc(0x5)"],"event_type":"action_execute","greater":false,"pkt_len":1500,"recirc_id":0,"truncated":false}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::CheckPktLen(OvsActionCheckPktLen {
+                            pkt_len: 1500,
+                            greater: false,
+                            actions_if_greater: vec!["userspace".to_string()],
+                            actions_if_less_equal: vec!["2".to_string(), "recirc(0x5)".to_string()],
+                            truncated: false,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Push Ethernet action event
+            (
+                r#"{"action":"push_eth","dst":"ff:ff:ff:ff:ff:ff","event_type":"action_execute","has_detail":true,"recirc_id":0,"src":"0a:58:0a:f4:00:01"}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::PushEth(OvsActionPushEth {
+                            src: Some("0a:58:0a:f4:00:01".to_string()),
+                            dst: Some("ff:ff:ff:ff:ff:ff".to_string()),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: Some(true),
+                    }),
+                },
+            ),
+            // Clone action event
+            (
+                r#"{"action":"clone","cloned":false,"event_type":"action_execute","has_detail":true,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Clone(OvsActionClone {
+                            cloned: Some(false),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: Some(true),
+                    }),
+                },
+            ),
+            // Conntrack clear action event
+            (
+                r#"{"action":"ct_clear","event_type":"action_execute","has_detail":false,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::CtClear(OvsDummyAction)),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: Some(false),
+                    }),
+                },
+            ),
+            // Dec TTL action event
+            (
+                r#"{"action":"dec_ttl","event_type":"action_execute","has_action":false,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::DecTtl(OvsActionDecTtl {
+                            has_action: Some(false),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Sample action event
+            (
+                r#"{"action":"sample","event_type":"action_execute","nested_actions":2,"probability":50.0,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Sample(OvsActionSample {
+                            probability: Some(50.0),
+                            nested_actions: Some(2),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Meter action event
+            (
+                r#"{"action":"meter","color":1,"event_type":"action_execute","id":2,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Meter(OvsActionMeter {
+                            id: 2,
+                            color: Some(1),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Set masked action event (IPv6)
+            (
+                r#"{"action":"set_masked","event_type":"action_execute","ipv6":{"dst":"2001:db8::1","hlimit":63,"label":74565},"field":"ipv6","key_type":8,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::SetMasked(OvsActionSet {
+                            key_type: OVS_KEY_ATTR_IPV6,
+                            field: Some("ipv6".to_string()),
+                            ipv4: None,
+                            ipv6: Some(OvsSetIpv6 {
+                                src: None,
+                                dst: Some("2001:db8::1".to_string()),
+                                label: Some(0x12345),
+                                tclass: None,
+                                hlimit: Some(63),
+                            }),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Set masked action event (IPv4)
+            (
+                r#"{"action":"set_masked","event_type":"action_execute","ipv4":{"src":"10.0.0.1","ttl":63},"field":"ipv4","key_type":7,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::SetMasked(OvsActionSet {
+                            key_type: OVS_KEY_ATTR_IPV4,
+                            field: Some("ipv4".to_string()),
+                            ipv4: Some(OvsSetIpv4 {
+                                src: Some("10.0.0.1".to_string()),
+                                ttl: Some(63),
+                                ..Default::default()
+                            }),
+                            ipv6: None,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
                     }),
                 },
             ),
@@ -559,6 +2130,7 @@ mod tests {
                 OvsEvent {
                     event: OvsEventType::UpcallEnqueue(UpcallEnqueueEvent {
                         ret: 0,
+                        ret_str: None,
                         cmd: 1,
                         port: 4195744766,
                         upcall_ts: 61096236973661,
@@ -573,8 +2145,63 @@ mod tests {
                 OvsEvent {
                     event: OvsEventType::UpcallReturn(UpcallReturnEvent {
                         ret: 0,
+                        ret_str: None,
+                        upcall_ts: 61096236973661,
+                        upcall_cpu: 0,
+                        latency_ns: None,
+                    }),
+                },
+            ),
+            // Upcall return event, with an error
+            (
+                r#"{"event_type":"upcall_return","ret":-105,"ret_str":"ENOBUFS","upcall_cpu":0,"upcall_ts":61096236973661}"#,
+                OvsEvent {
+                    event: OvsEventType::UpcallReturn(UpcallReturnEvent {
+                        ret: -105,
+                        ret_str: Some("ENOBUFS".to_string()),
+                        upcall_ts: 61096236973661,
+                        upcall_cpu: 0,
+                        latency_ns: None,
+                    }),
+                },
+            ),
+            // Upcall return event, with its latency
+            (
+                r#"{"event_type":"upcall_return","latency_ns":1500,"ret":0,"upcall_cpu":0,"upcall_ts":61096236973661}"#,
+                OvsEvent {
+                    event: OvsEventType::UpcallReturn(UpcallReturnEvent {
+                        ret: 0,
+                        ret_str: None,
                         upcall_ts: 61096236973661,
                         upcall_cpu: 0,
+                        latency_ns: Some(1500),
+                    }),
+                },
+            ),
+            // Operation event of an unknown type
+            (
+                r#"{"batch_idx":0,"batch_ts":61096237019698,"event_type":"flow_operation","op_type":"unknown(99)","queue_id":3316322986}"#,
+                OvsEvent {
+                    event: OvsEventType::Operation(OperationEvent {
+                        op_type: 99,
+                        queue_id: 3316322986,
+                        batch_ts: 61096237019698,
+                        batch_idx: 0,
+                        error: None,
+                        flow: None,
+                    }),
+                },
+            ),
+            // Unknown action event
+            (
+                r#"{"action":"unknown","event_type":"action_execute","id":99,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Unknown(OvsActionUnknown { id: 99 })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
                     }),
                 },
             ),
@@ -587,6 +2214,8 @@ mod tests {
                         queue_id: 3316322986,
                         batch_ts: 61096237019698,
                         batch_idx: 0,
+                        error: None,
+                        flow: None,
                     }),
                 },
             ),
@@ -599,6 +2228,51 @@ mod tests {
                         queue_id: 3316322986,
                         batch_ts: 61096237019698,
                         batch_idx: 0,
+                        error: None,
+                        flow: None,
+                    }),
+                },
+            ),
+            // Operation event put, failed
+            (
+                r#"{"batch_idx":1,"batch_ts":61096237019698,"error":-17,"event_type":"flow_operation","op_type":"put","queue_id":3316322986}"#,
+                OvsEvent {
+                    event: OvsEventType::Operation(OperationEvent {
+                        op_type: 1,
+                        queue_id: 3316322986,
+                        batch_ts: 61096237019698,
+                        batch_idx: 1,
+                        error: Some(-17),
+                        flow: None,
+                    }),
+                },
+            ),
+            // Operation event put, with the flow match
+            (
+                r#"{"batch_idx":0,"batch_ts":61096237019698,"event_type":"flow_operation","flow":{"fields":[{"name":"in_port","value":"2"},{"mask":"24","name":"nw_src","value":"10.0.0.0"}],"masked":true},"op_type":"put","queue_id":3316322986}"#,
+                OvsEvent {
+                    event: OvsEventType::Operation(OperationEvent {
+                        op_type: 1,
+                        queue_id: 3316322986,
+                        batch_ts: 61096237019698,
+                        batch_idx: 0,
+                        error: None,
+                        flow: Some(OvsFlowMatch {
+                            fields: vec![
+                                OvsFlowField {
+                                    name: "in_port".to_string(),
+                                    value: "2".to_string(),
+                                    mask: None,
+                                },
+                                OvsFlowField {
+                                    name: "nw_src".to_string(),
+                                    value: "10.0.0.0".to_string(),
+                                    mask: Some("24".to_string()),
+                                },
+                            ],
+                            masked: true,
+                            truncated: false,
+                        }),
                     }),
                 },
             ),
@@ -617,9 +2291,145 @@ mod tests {
                                 min_port: Some(36895),
                                 max_port: Some(36900),
                             }),
+                            ct_direction: None,
+                            ct_state: None,
+                            eventmask: None,
+                            label: None,
+                            label_mask: None,
+                            mark: None,
+                            mark_mask: None,
+                            ct_key: None,
                         })),
                         recirc_id: 34,
                         queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Conntrack action event, with the packet conntrack info
+            (
+                r#"{"action":"ct","ct_direction":"reply","ct_state":"est","event_type":"action_execute","flags":4100,"recirc_id":0,"zone_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(OvsActionCt {
+                            zone_id: 0,
+                            flags: R_OVS_CT_IP4 | R_OVS_CT_CTINFO,
+                            nat: None,
+                            ct_direction: Some(String::from("reply")),
+                            ct_state: Some(String::from("est")),
+                            eventmask: None,
+                            label: None,
+                            label_mask: None,
+                            mark: None,
+                            mark_mask: None,
+                            ct_key: None,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Output action event, failed
+            (
+                r#"{"action":"output","error":-19,"event_type":"action_execute","port":3,"recirc_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Output(OvsActionOutput {
+                            port: 3,
+                            ..Default::default()
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: Some(-19),
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Conntrack action event, setting labels
+            (
+                r#"{"action":"ct","event_type":"action_execute","flags":16389,"label":"0x10000000000000000000000002a","label_mask":"0xff0000000000000000000000000ff","recirc_id":0,"zone_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(OvsActionCt {
+                            zone_id: 0,
+                            flags: R_OVS_CT_COMMIT | R_OVS_CT_IP4 | R_OVS_CT_LABELS,
+                            nat: None,
+                            ct_direction: None,
+                            ct_state: None,
+                            eventmask: None,
+                            label: Some(String::from("0x10000000000000000000000002a")),
+                            label_mask: Some(String::from("0xff0000000000000000000000000ff")),
+                            mark: None,
+                            mark_mask: None,
+                            ct_key: None,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Conntrack action event, with force commit and an event mask
+            (
+                r#"{"action":"ct","event_type":"action_execute","eventmask":1048581,"flags":8199,"recirc_id":0,"zone_id":0}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(OvsActionCt {
+                            zone_id: 0,
+                            flags: R_OVS_CT_COMMIT
+                                | R_OVS_CT_FORCE
+                                | R_OVS_CT_IP4
+                                | R_OVS_CT_EVENTMASK,
+                            nat: None,
+                            ct_direction: None,
+                            ct_state: None,
+                            eventmask: Some(0x100005),
+                            label: None,
+                            label_mask: None,
+                            mark: None,
+                            mark_mask: None,
+                            ct_key: None,
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
+                    }),
+                },
+            ),
+            // Conntrack action event, with a correlation key
+            (
+                r#"{"action":"ct","ct_key":{"dport":80,"dst":"10.0.0.2","proto":6,"sport":1234,"src":"10.0.0.1","zone_id":3},"event_type":"action_execute","flags":4,"recirc_id":0,"zone_id":3}"#,
+                OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(OvsActionCt {
+                            zone_id: 3,
+                            flags: R_OVS_CT_IP4,
+                            nat: None,
+                            ct_direction: None,
+                            ct_state: None,
+                            eventmask: None,
+                            label: None,
+                            label_mask: None,
+                            mark: None,
+                            mark_mask: None,
+                            ct_key: Some(OvsCtKey {
+                                zone_id: 3,
+                                src: String::from("10.0.0.1"),
+                                dst: String::from("10.0.0.2"),
+                                proto: 6,
+                                sport: 1234,
+                                dport: 80,
+                            }),
+                        })),
+                        recirc_id: 0,
+                        queue_id: None,
+                        error: None,
+                        has_detail: None,
                     }),
                 },
             ),
@@ -1,7 +1,7 @@
 use std::fmt;
 
 use super::*;
-use crate::{event_section, Formatter};
+use crate::{event_section, event_type, Formatter};
 
 /// Skb drop event section.
 #[event_section(SectionId::SkbDrop)]
@@ -11,13 +11,166 @@ pub struct SkbDropEvent {
     /// Reason why a packet was freed/dropped. Only reported from specific
     /// functions. See `enum skb_drop_reason` in the kernel.
     pub drop_reason: String,
+    /// The drop reason was resolved to its name. If not, `drop_reason` holds
+    /// its numerical value (within its sub-system, if any).
+    #[serde(
+        default = "reason_resolved_default",
+        skip_serializing_if = "is_resolved"
+    )]
+    pub reason_resolved: bool,
+    /// Kernel function which dropped the packet, if known. Only reported from
+    /// the skb:kfree_skb tracepoint.
+    pub drop_location: Option<String>,
+    /// The packet was freed normally (consumed), e.g. events generated by the
+    /// skb:consume_skb tracepoint, and not dropped.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub consumed: bool,
+    /// Conntrack state of the connection the packet belonged to, for
+    /// netfilter drops. Filled in by post-processing from the last conntrack
+    /// event matching the packet 5-tuple, if any.
+    pub ct: Option<SkbDropCt>,
 }
 
-impl EventFmt for SkbDropEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+// Only unresolved reasons are reported; events not reporting it had their
+// reason resolved.
+fn reason_resolved_default() -> bool {
+    true
+}
+
+fn is_resolved(resolved: &bool) -> bool {
+    *resolved
+}
+
+/// Conntrack information explaining a netfilter drop.
+#[event_type]
+pub struct SkbDropCt {
+    /// Conntrack state of the packet.
+    pub state: CtState,
+    /// Conntrack zone.
+    pub zone_id: u16,
+    /// TCP state, if any.
+    pub tcp_state: Option<String>,
+}
+
+impl SkbDropEvent {
+    /// Drop reason, prefixed by its sub-system if any. Unresolved reasons are
+    /// reported as `reason#<value>` so they can't be mistaken for a name.
+    fn reason(&self) -> String {
+        let reason = match self.reason_resolved {
+            true => self.drop_reason.clone(),
+            false => format!("reason#{}", self.drop_reason),
+        };
         match &self.subsys {
-            None => write!(f, "drop (reason {})", self.drop_reason),
-            Some(name) => write!(f, "drop (reason {name}/{})", self.drop_reason),
+            None => reason,
+            Some(name) => format!("{name}/{reason}"),
+        }
+    }
+}
+
+impl EventFmt for SkbDropEvent {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        match format.verbosity {
+            Verbosity::Compact => {
+                return match self.consumed {
+                    true => write!(f, "free"),
+                    false => write!(f, "{}", self.reason()),
+                };
+            }
+            Verbosity::Verbose => write!(f, "skb_drop: ")?,
+            Verbosity::Normal => (),
+        }
+
+        match self.consumed {
+            true => write!(f, "free")?,
+            false if self.reason_resolved => write!(f, "drop (reason {})", self.reason())?,
+            false => write!(f, "drop ({})", self.reason())?,
         }
+
+        if let Some(location) = &self.drop_location {
+            write!(f, " in {location}")?;
+        }
+
+        if let Some(ct) = &self.ct {
+            use CtState::*;
+            let state = match ct.state {
+                Established => "ESTABLISHED",
+                Related => "RELATED",
+                New => "NEW",
+                Reply => "REPLY",
+                RelatedReply => "RELATED_REPLY",
+                Untracked => "UNTRACKED",
+            };
+            write!(f, " [ct_state {state} zone {}", ct.zone_id)?;
+            if let Some(tcp_state) = &ct.tcp_state {
+                write!(f, " tcp_state {tcp_state}")?;
+            }
+            write!(f, "]")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity() {
+        let display = |event: &SkbDropEvent, verbosity| {
+            format!(
+                "{}",
+                event.display(
+                    &DisplayFormat::new().verbosity(verbosity),
+                    &FormatterConf::new()
+                )
+            )
+        };
+
+        let mut event = SkbDropEvent {
+            subsys: None,
+            drop_reason: "NO_SOCKET".to_string(),
+            reason_resolved: true,
+            drop_location: Some("tcp_v4_rcv".to_string()),
+            consumed: false,
+            ct: None,
+        };
+        assert_eq!(
+            display(&event, Verbosity::Normal),
+            "drop (reason NO_SOCKET) in tcp_v4_rcv"
+        );
+        assert_eq!(display(&event, Verbosity::Compact), "NO_SOCKET");
+        assert_eq!(
+            display(&event, Verbosity::Verbose),
+            "skb_drop: drop (reason NO_SOCKET) in tcp_v4_rcv"
+        );
+
+        event.subsys = Some("openvswitch".to_string());
+        event.drop_reason = "OVS_DROP_LAST_ACTION".to_string();
+        assert_eq!(
+            display(&event, Verbosity::Compact),
+            "openvswitch/OVS_DROP_LAST_ACTION"
+        );
+
+        // Unresolved reasons.
+        event.reason_resolved = false;
+        event.drop_reason = "42".to_string();
+        assert_eq!(display(&event, Verbosity::Compact), "openvswitch/reason#42");
+        assert_eq!(
+            display(&event, Verbosity::Normal),
+            "drop (openvswitch/reason#42) in tcp_v4_rcv"
+        );
+        event.subsys = None;
+        assert_eq!(
+            display(&event, Verbosity::Normal),
+            "drop (reason#42) in tcp_v4_rcv"
+        );
+
+        event.consumed = true;
+        assert_eq!(display(&event, Verbosity::Compact), "free");
+        assert_eq!(
+            display(&event, Verbosity::Verbose),
+            "skb_drop: free in tcp_v4_rcv"
+        );
     }
 }
@@ -61,6 +61,9 @@ pub struct TrackingInfo {
     pub skb: SkbTrackingEvent,
     /// The index in the event series.
     pub idx: u32,
+    /// OvS recirculation ids the packet went through so far, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ovs_recirc: Vec<u32>,
 }
 
 impl Eq for TrackingInfo {}
@@ -88,7 +91,19 @@ impl Ord for TrackingInfo {
 impl EventFmt for TrackingInfo {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         self.skb.event_fmt(f, format)?;
-        write!(f, " n {}", self.idx)
+        write!(f, " n {}", self.idx)?;
+
+        // Only display the recirculation chain if the packet was recirculated.
+        if self.ovs_recirc.len() > 1 {
+            let chain: Vec<_> = self
+                .ovs_recirc
+                .iter()
+                .map(|id| format!("{id:#x}"))
+                .collect();
+            write!(f, " recirc_chain {}", chain.join(","))?;
+        }
+
+        Ok(())
     }
 }
 
@@ -97,6 +112,7 @@ impl TrackingInfo {
         Ok(TrackingInfo {
             skb: *track,
             idx: 0,
+            ovs_recirc: Vec::new(),
         })
     }
 }
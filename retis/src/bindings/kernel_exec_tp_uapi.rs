@@ -21,6 +21,7 @@ pub struct exec_track_event {
 #[derive(Debug, Default, Copy, Clone)]
 pub struct exec_output {
     pub port: u32_,
+    pub dp_name: [::std::os::raw::c_char; 16usize],
 }
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
@@ -28,6 +29,131 @@ pub struct exec_recirc {
     pub id: u32_,
 }
 #[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_hash {
+    pub hash_alg: u32_,
+    pub hash_basis: u32_,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct exec_check_pkt_len {
+    pub pkt_len: u16_,
+    pub len: u16_,
+    pub greater: u8_,
+    pub attrs: [u8_; 128usize],
+}
+impl Default for exec_check_pkt_len {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_dec_ttl {
+    pub has_action: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct exec_sample {
+    pub len: u16_,
+    pub attrs: [u8_; 128usize],
+}
+impl Default for exec_sample {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct exec_userspace {
+    pub len: u16_,
+    pub attrs: [u8_; 96usize],
+}
+impl Default for exec_userspace {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_push_mpls {
+    pub lse: u32_,
+    pub ethertype: u16_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_add_mpls {
+    pub lse: u32_,
+    pub ethertype: u16_,
+    pub tun_flags: u16_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_pop_nsh {
+    pub next_proto: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct exec_set {
+    pub key_type: u16_,
+    pub masked: u8_,
+    pub len: u8_,
+    pub data: [u8_; 80usize],
+}
+impl Default for exec_set {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_meter {
+    pub id: u32_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_trunc {
+    pub max_len: u32_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_clone {
+    pub cloned: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_push_eth {
+    pub eth_src: [u8_; 6usize],
+    pub eth_dst: [u8_; 6usize],
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_push_vlan {
+    pub vlan_tpid: u16_,
+    pub vlan_tci: u16_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_pop_vlan {
+    pub inner_ethertype: u16_,
+}
+#[repr(C)]
 #[derive(Copy, Clone)]
 pub union exec_ip {
     pub addr4: u32_,
@@ -51,6 +177,12 @@ pub struct exec_ct {
     pub zone_id: u16_,
     pub min_port: u16_,
     pub max_port: u16_,
+    pub ctinfo: u8_,
+    pub eventmask: u32_,
+    pub labels: [u8_; 16usize],
+    pub labels_mask: [u8_; 16usize],
+    pub mark: u32_,
+    pub mark_mask: u32_,
 }
 impl Default for exec_ct {
     fn default() -> Self {
@@ -8,6 +8,18 @@
     non_camel_case_types,
     non_snake_case
 )]
+
+/// Checks at build time the size of bindings matches the one of their BPF
+/// counterpart, as computed from its C definition. Bindings are committed
+/// separately from the BPF headers they are generated from; this catches they
+/// drifted apart (e.g. a field was added on one side only) before events are
+/// silently misparsed.
+macro_rules! assert_size {
+    ($($binding:ty => $size:expr),* $(,)?) => {
+        $(const _: () = assert!(::std::mem::size_of::<$binding>() == $size);)*
+    };
+}
+
 pub(crate) mod common_uapi;
 use common_uapi::{retis_probe_config, retis_probe_offsets};
 
@@ -47,6 +59,9 @@ impl Default for nft_offsets {
 }
 
 pub(crate) mod skb_drop_hook_uapi;
+use skb_drop_hook_uapi::skb_drop_config;
+
+unsafe impl plain::Plain for skb_drop_config {}
 
 pub(crate) mod skb_tracking_uapi;
 use skb_tracking_uapi::*;
@@ -67,6 +82,40 @@ pub(crate) mod ovs_common_uapi;
 pub(crate) mod ovs_operation_uapi;
 pub(crate) mod user_recv_upcall_uapi;
 
+assert_size!(
+    kernel_enqueue_uapi::upcall_enqueue_event => 32,
+    kernel_upcall_ret_uapi::upcall_ret_event => 16,
+    kernel_upcall_tp_uapi::upcall_event => 12,
+    kernel_exec_tp_uapi::exec_event => 8,
+    kernel_exec_tp_uapi::exec_track_event => 4,
+    kernel_exec_tp_uapi::exec_output => 20,
+    kernel_exec_tp_uapi::exec_recirc => 4,
+    kernel_exec_tp_uapi::exec_hash => 8,
+    kernel_exec_tp_uapi::exec_check_pkt_len => 134,
+    kernel_exec_tp_uapi::exec_dec_ttl => 1,
+    kernel_exec_tp_uapi::exec_sample => 130,
+    kernel_exec_tp_uapi::exec_userspace => 98,
+    kernel_exec_tp_uapi::exec_push_mpls => 8,
+    kernel_exec_tp_uapi::exec_add_mpls => 8,
+    kernel_exec_tp_uapi::exec_pop_nsh => 1,
+    kernel_exec_tp_uapi::exec_push_eth => 12,
+    kernel_exec_tp_uapi::exec_clone => 1,
+    kernel_exec_tp_uapi::exec_set => 84,
+    kernel_exec_tp_uapi::exec_meter => 4,
+    kernel_exec_tp_uapi::exec_trunc => 4,
+    kernel_exec_tp_uapi::exec_push_vlan => 4,
+    kernel_exec_tp_uapi::exec_pop_vlan => 2,
+    kernel_exec_tp_uapi::exec_ct => 88,
+    ovs_common_uapi::upcall_context => 16,
+    ovs_common_uapi::execute_actions_ctx => 24,
+    ovs_common_uapi::exec_error => 16,
+    ovs_operation_uapi::ovs_operation_event => 24,
+    ovs_operation_uapi::ovs_operation_flow => 324,
+    ovs_operation_uapi::user_upcall_info => 8,
+    ovs_operation_uapi::upcall_batch => 528,
+    user_recv_upcall_uapi::recv_upcall_event => 32,
+);
+
 pub(crate) mod events_uapi;
 use events_uapi::retis_log_event;
 
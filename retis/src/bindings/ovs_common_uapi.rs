@@ -1,7 +1,9 @@
 /* automatically generated by rust-bindgen 0.70.1 */
 
+pub type __u8 = ::std::os::raw::c_uchar;
 pub type __u32 = ::std::os::raw::c_uint;
 pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u8_ = __u8;
 pub type u32_ = __u32;
 pub type u64_ = __u64;
 pub type bool_ = bool;
@@ -17,6 +19,8 @@ pub struct execute_actions_ctx {
     pub skb: *mut ::std::os::raw::c_void,
     pub queue_id: u32_,
     pub command: bool_,
+    pub last_action: u8_,
+    pub last_recirc_id: u32_,
 }
 impl Default for execute_actions_ctx {
     fn default() -> Self {
@@ -27,3 +31,12 @@ impl Default for execute_actions_ctx {
         }
     }
 }
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_error {
+    pub error: ::std::os::raw::c_int,
+    pub recirc_id: u32_,
+    pub queue_id: u32_,
+    pub action: u8_,
+    pub command: bool_,
+}
@@ -1,9 +1,13 @@
 /* automatically generated by rust-bindgen 0.70.1 */
 
 pub type __u8 = ::std::os::raw::c_uchar;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type __s32 = ::std::os::raw::c_int;
 pub type __u32 = ::std::os::raw::c_uint;
 pub type __u64 = ::std::os::raw::c_ulonglong;
 pub type u8_ = __u8;
+pub type u16_ = __u16;
+pub type s32 = __s32;
 pub type u32_ = __u32;
 pub type u64_ = __u64;
 pub type bool_ = bool;
@@ -14,6 +18,24 @@ pub struct ovs_operation_event {
     pub queue_id: u32_,
     pub batch_idx: u8_,
     pub type_: u8_,
+    pub error: s32,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ovs_operation_flow {
+    pub key_len: u16_,
+    pub mask_len: u16_,
+    pub key: [u8_; 160usize],
+    pub mask: [u8_; 160usize],
+}
+impl Default for ovs_operation_flow {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
 }
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
@@ -1,9 +1,21 @@
 /* automatically generated by rust-bindgen 0.70.1 */
 
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
 pub type __s32 = ::std::os::raw::c_int;
 pub type s32 = __s32;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct skb_drop_config {
+    pub kfree_skb_ksym: u64_,
+    pub consume_skb_ksym: u64_,
+}
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
 pub struct skb_drop_event {
+    pub location: u64_,
     pub drop_reason: s32,
+    pub consumed: u8_,
 }
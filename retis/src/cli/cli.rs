@@ -19,7 +19,7 @@ use super::dynamic::DynamicCommand;
 use crate::benchmark::cli::Benchmark;
 use crate::{
     collect::cli::Collect,
-    events::SectionId,
+    events::{SectionId, TimeFormat, Verbosity},
     generate::Complete,
     inspect::Inspect,
     module::Modules,
@@ -421,12 +421,66 @@ pub(crate) enum CliDisplayFormat {
     MultiLine,
 }
 
+/// Type of the "time-format" argument.
+// It is an enum that maps 1:1 with the time formats defined in events library.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, ValueEnum)]
+pub(crate) enum CliTimeFormat {
+    /// Monotonic timestamp, in nanoseconds.
+    #[default]
+    Monotonic,
+    /// Wall-clock date in UTC (same as --utc).
+    Utc,
+    /// Wall-clock date in UTC, following ISO 8601.
+    Iso8601,
+    /// Wall-clock time, in nanoseconds since the Unix epoch.
+    EpochNs,
+    /// Time elapsed since the first event, in seconds.
+    Relative,
+}
+
+impl From<CliTimeFormat> for TimeFormat {
+    fn from(format: CliTimeFormat) -> Self {
+        match format {
+            CliTimeFormat::Monotonic => TimeFormat::MonotonicTimestamp,
+            CliTimeFormat::Utc => TimeFormat::UtcDate,
+            CliTimeFormat::Iso8601 => TimeFormat::Iso8601,
+            CliTimeFormat::EpochNs => TimeFormat::EpochNs,
+            CliTimeFormat::Relative => TimeFormat::Relative,
+        }
+    }
+}
+
+/// Type of the "verbosity" argument.
+// It is an enum that maps 1:1 with the verbosity levels defined in events library.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, ValueEnum)]
+pub(crate) enum CliVerbosity {
+    /// Usual rendering.
+    #[default]
+    Normal,
+    /// As terse as possible, e.g. only the drop reason.
+    Compact,
+    /// Sections are labeled with the module which generated them.
+    Verbose,
+}
+
+impl From<CliVerbosity> for Verbosity {
+    fn from(verbosity: CliVerbosity) -> Self {
+        match verbosity {
+            CliVerbosity::Normal => Verbosity::Normal,
+            CliVerbosity::Compact => Verbosity::Compact,
+            CliVerbosity::Verbose => Verbosity::Verbose,
+        }
+    }
+}
+
 /// Create and register a ThinCli
 pub(crate) fn get_cli() -> Result<ThinCli> {
     let mut cli = ThinCli::new()?;
     cli.add_subcommand(Box::new(Collect::new()?))?;
     cli.add_subcommand(Box::new(Print::new()?))?;
     cli.add_subcommand(Box::new(Sort::new()?))?;
+    cli.add_subcommand(Box::new(Summary::new()?))?;
+    cli.add_subcommand(Box::new(Validate::new()?))?;
     #[cfg(feature = "python")]
     cli.add_subcommand(Box::new(PythonCli::new()?))?;
     cli.add_subcommand(Box::new(Pcap::new()?))?;
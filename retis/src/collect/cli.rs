@@ -11,7 +11,11 @@ use clap::{
 };
 
 use super::CollectRunner;
-use crate::cli::{dynamic::DynamicCommand, SubCommand, *};
+use crate::{
+    cli::{dynamic::DynamicCommand, SubCommand, *},
+    core::events::OverflowPolicy,
+    helpers::time::CliClockSource,
+};
 
 #[derive(Args, Debug, Default)]
 pub(crate) struct CollectArgs {
@@ -64,6 +68,20 @@ Examples:
 defaults to \"retis.data\"."
     )]
     pub(super) out: Option<PathBuf>,
+    #[arg(
+        long,
+        requires = "out",
+        help = "Append the events to the --out file as a new collection session, instead of
+overwriting it. Events of different sessions are never correlated."
+    )]
+    pub(super) append: bool,
+    #[arg(
+        long,
+        requires = "out",
+        help = "Store a checksum (CRC32) along with each event written to the --out file, to detect
+corrupted or tampered events when reading it."
+    )]
+    pub(super) checksum: bool,
     #[arg(
         long,
         help = "Write the events to stdout even if --out is used.",
@@ -73,8 +91,71 @@ defaults to \"retis.data\"."
     #[arg(long, help = "Format used when printing an event.")]
     #[clap(value_enum, default_value_t=CliDisplayFormat::MultiLine)]
     pub(super) format: CliDisplayFormat,
-    #[arg(long, help = "Print the time as UTC")]
+    #[arg(long, help = "Print the time as UTC", conflicts_with = "time_format")]
     pub(super) utc: bool,
+    #[arg(long, help = "Format used when printing the time of an event.")]
+    #[clap(value_enum, default_value_t=CliTimeFormat::Monotonic)]
+    pub(super) time_format: CliTimeFormat,
+    #[arg(long, help = "How much details are displayed for each event.")]
+    #[clap(value_enum, default_value_t=CliVerbosity::Normal)]
+    pub(super) verbosity: CliVerbosity,
+    #[arg(
+        long,
+        help = "Prefix fields with the name of the module that generated them (e.g. ovs.action=output),
+for events composed by multiple modules."
+    )]
+    pub(super) module_prefix: bool,
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of events printed on the console per second, the excess being dropped and
+periodically reported. This is only meant for readability: events written to a file (--out) are not
+throttled."
+    )]
+    pub(super) max_events_per_sec: Option<u32>,
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Every N seconds, print on stderr the OvS flow install latency percentiles (from a miss upcall to its
+flow put) computed since the beginning of the collection."
+    )]
+    pub(super) stats_interval: Option<u64>,
+    #[arg(
+        long,
+        help = "Do not print, on stderr, a summary line (number of events, per module, duration and events lost)
+when the collection stops."
+    )]
+    pub(super) no_summary: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma separated list of drop reasons expected on the system (e.g. TCP_INVALID, or
+openvswitch/OVS_DROP_LAST_ACTION to also match the subsystem), whose events are not printed. Only applies to the console output:
+they are still written to the file (--out) and accounted for by summaries; the number of events not printed is reported at the end."
+    )]
+    pub(super) quiet_known: Vec<String>,
+    #[arg(
+        long,
+        help = "Maximum number of events waiting to be processed. The queue is unbounded by default."
+    )]
+    pub(super) queue_size: Option<usize>,
+    #[arg(
+        long,
+        value_enum,
+        requires = "queue_size",
+        help = "Policy applied when the queue of events waiting to be processed is full (requires --queue-size).
+\"block\" (the default) does not drop events in the queue but can lead to events being lost in the kernel;
+\"drop-oldest\" favors latency and \"drop-newest\" keeps the events already queued."
+    )]
+    pub(super) queue_overflow: Option<OverflowPolicy>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CliClockSource::Monotonic,
+        help = "Clock used to timestamp events. \"boottime\" keeps counting while the system is suspended, which
+helps correlating events with other sources using it."
+    )]
+    pub(super) clock: CliClockSource,
     #[arg(
         id = "filter-packet",
         short,
@@ -1,13 +1,14 @@
 #[cfg(not(test))]
 use std::os::fd::{AsFd, AsRawFd};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashMap, HashSet},
     fs::OpenOptions,
-    io::{self, BufWriter},
+    hash::{BuildHasher, Hasher, RandomState},
+    io::{self, BufWriter, Write},
     process::{Command, Stdio},
     str::FromStr,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, bail, Result};
@@ -36,7 +37,7 @@ use crate::{
     events::*,
     helpers::{signals::Running, time::*},
     module::{ModuleId, Modules},
-    process::display::*,
+    process::{display::*, summary::FlowInstallLatency},
 };
 
 #[cfg(not(test))]
@@ -111,6 +112,8 @@ pub(crate) struct Collectors {
     events_factory: Arc<RetisEventsFactory>,
     // Did we mount debugfs ourselves?
     mounted_debugfs: bool,
+    // Id of the collection session, stamped on all events.
+    session_id: u64,
 }
 
 impl Collectors {
@@ -129,6 +132,7 @@ impl Collectors {
             loaded: Vec::new(),
             events_factory: Arc::new(RetisEventsFactory::default()),
             mounted_debugfs: false,
+            session_id: new_session_id()?,
         })
     }
 
@@ -235,6 +239,15 @@ impl Collectors {
             .downcast_ref::<Collect>()
             .ok_or_else(|| anyhow!("wrong subcommand"))?;
 
+        self.factory.set_queue(
+            collect.args()?.queue_size,
+            collect.args()?.queue_overflow.unwrap_or_default(),
+        );
+
+        // Select the clock used to timestamp events before any probe is
+        // loaded, so all modules use the same one.
+        set_clock_source(collect.args()?.clock.into());
+
         // Check if we need to report stack traces in the events.
         if collect.args()?.stack || collect.args()?.probe_stack {
             self.probes
@@ -251,6 +264,8 @@ impl Collectors {
                         .unwrap_or("unspec")
                         .to_string(),
                     clock_monotonic_offset: monotonic_clock_offset()?,
+                    session_id: Some(self.session_id),
+                    clock_source: Some(clock_source()),
                 }),
             )
         })?;
@@ -420,10 +435,11 @@ impl Collectors {
 
     /// Stop the event retrieval for all collectors in the group by calling
     /// their `stop()` function. All the collectors are in charge to clean-up
-    /// their temporary side effects and exit gracefully.
-    fn stop(&mut self) -> Result<()> {
+    /// their temporary side effects and exit gracefully. Returns the number of
+    /// events lost in the kernel and of events dropped from the events queue.
+    fn stop(&mut self) -> Result<(u64, u64)> {
         self.probes.runtime_mut()?.detach()?;
-        self.probes.runtime_mut()?.report_counters()?;
+        let lost = self.probes.runtime_mut()?.report_counters()?;
 
         for id in &self.loaded {
             let c = self
@@ -448,13 +464,18 @@ impl Collectors {
         debug!("Stopping events");
         self.factory.stop()?;
 
+        let dropped = self.factory.dropped_events();
+        if dropped > 0 {
+            warn!("{dropped} event(s) dropped as the events queue was full");
+        }
+
         // If we mounted debugfs, unmount it.
         if self.mounted_debugfs {
             debug!("Unmounting debugfs");
             umount("/sys/kernel/debug")?;
         }
 
-        Ok(())
+        Ok((lost, dropped))
     }
 
     /// Starts the processing loop and block until we get a single SIGINT
@@ -468,7 +489,7 @@ impl Collectors {
             .ok_or_else(|| anyhow!("wrong subcommand"))?
             .args()?;
 
-        let mut printers = Vec::new();
+        let mut printers = PrintEventFanOut::new();
 
         // Write events to stdout if we don't write to a file (--out) or if
         // explicitly asked to (--print).
@@ -478,29 +499,75 @@ impl Collectors {
                 .time_format(if collect.utc {
                     TimeFormat::UtcDate
                 } else {
-                    TimeFormat::MonotonicTimestamp
+                    collect.time_format.into()
                 })
-                .monotonic_offset(monotonic_clock_offset()?);
-
-            printers.push(PrintEvent::new(
-                Box::new(io::stdout()),
-                PrintEventFormat::Text(format),
-            ));
+                .monotonic_offset(monotonic_clock_offset()?)
+                .module_prefix(collect.module_prefix)
+                .verbosity(collect.verbosity.into());
+
+            let mut printer =
+                PrintEvent::new(Box::new(io::stdout()), PrintEventFormat::Text(format))
+                    .with_quiet_drops(&collect.quiet_known);
+            if let Some(max) = collect.max_events_per_sec {
+                printer = printer.with_throttle(max);
+            }
+            printers.push("the console", printer);
         }
 
         // Write the events to a file if asked to.
         if let Some(out) = collect.out.as_ref() {
-            printers.push(PrintEvent::new(
-                Box::new(BufWriter::new(
-                    OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .truncate(true)
-                        .open(out)
-                        .or_else(|_| bail!("Could not create or open '{}'", out.display()))?,
-                )),
-                PrintEventFormat::Json,
-            ));
+            let printer = if collect.append {
+                let (file, index) = file::open_for_append(out)?;
+
+                // Timestamps of sessions using different clocks can't be
+                // compared, let the user know.
+                if file.metadata()?.len() > 0 {
+                    if let Some(prev) = file::FileEventsFactory::sessions(out)?
+                        .last()
+                        .and_then(|session| session.clock_source)
+                    {
+                        if prev != clock_source() {
+                            warn!(
+                                "'{}' was collected using the {prev} clock but events are timestamped using the {} clock",
+                                out.display(),
+                                clock_source()
+                            );
+                        }
+                    }
+                }
+                let printer =
+                    PrintEvent::new(Box::new(BufWriter::new(file)), PrintEventFormat::Json);
+                match index {
+                    Some(index) => printer.with_index(index),
+                    None => {
+                        warn!(
+                            "'{}' has no index, appended events won't be indexed",
+                            out.display()
+                        );
+                        printer
+                    }
+                }
+            } else {
+                PrintEvent::new(
+                    Box::new(BufWriter::new(
+                        OpenOptions::new()
+                            .create(true)
+                            .write(true)
+                            .truncate(true)
+                            .open(out)
+                            .or_else(|_| bail!("Could not create or open '{}'", out.display()))?,
+                    )),
+                    PrintEventFormat::Json,
+                )
+                .with_index(file::FileIndexBuilder::new(out)?)
+            };
+            printers.push(
+                &format!("'{}'", out.display()),
+                match collect.checksum {
+                    true => printer.with_checksums(),
+                    false => printer,
+                },
+            );
         }
 
         if let Some(cmd) = collect.cmd.to_owned() {
@@ -529,33 +596,190 @@ impl Collectors {
             self.known_kernel_types.clone(),
         );
 
+        let mut stamper = EventStamper::new(self.session_id);
+        let mut summary = CaptureSummary::new();
+        let mut stats = collect
+            .stats_interval
+            .map(|secs| PeriodicStats::new(Duration::from_secs(secs)));
+
         use EventResult::*;
         while self.run.running() {
+            if let Some(stats) = stats.as_mut() {
+                stats.tick()?;
+            }
+
             // First always try to dequeue all Retis events. This is not a
             // blocking call.
-            while let Some(event) = self.events_factory.next_event() {
-                printers
-                    .iter_mut()
-                    .try_for_each(|p| p.process_one(&event))?;
+            while let Some(mut event) = self.events_factory.next_event() {
+                stamper.stamp(&mut event);
+                if let Some(stats) = stats.as_mut() {
+                    stats.process_one(&event);
+                }
+                summary.process_one(&event);
+                printers.process_one(&event)?;
             }
 
             // Then get raw events, if any.
             match self.factory.next_event(Some(Duration::from_secs(1)))? {
                 Event(mut event) => {
+                    stamper.stamp(&mut event);
                     if collect.probe_stack {
                         probe_stack.process_event(self.probes.runtime_mut()?, &mut event)?;
                     }
+                    if let Some(stats) = stats.as_mut() {
+                        stats.process_one(&event);
+                    }
+                    summary.process_one(&event);
 
-                    printers
-                        .iter_mut()
-                        .try_for_each(|p| p.process_one(&event))?;
+                    printers.process_one(&event)?;
                 }
                 Timeout => continue,
             }
         }
 
-        printers.iter_mut().try_for_each(|p| p.flush())?;
-        self.stop()
+        printers.flush()?;
+        let (lost, dropped) = self.stop()?;
+
+        if !collect.no_summary {
+            writeln!(
+                io::stderr(),
+                "{}",
+                summary.line(
+                    lost,
+                    dropped,
+                    self.factory.filtered_events(),
+                    &self.factory.factories_stats()
+                )
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compact summary of a collection, reported when it stops.
+struct CaptureSummary {
+    start: Instant,
+    events: u64,
+    /// Number of events per module.
+    modules: BTreeMap<&'static str, u64>,
+}
+
+impl CaptureSummary {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: 0,
+            modules: BTreeMap::new(),
+        }
+    }
+
+    fn process_one(&mut self, event: &Event) {
+        self.events += 1;
+        event
+            .sections()
+            .filter(|id| id.is_module())
+            .for_each(|id| *self.modules.entry(id.to_str()).or_default() += 1);
+    }
+
+    /// Single line summary, also reporting the number of events lost in the
+    /// kernel, dropped from the events queue and filtered out by the section
+    /// factories (if any), and the statistics of the section factories.
+    fn line(&self, lost: u64, dropped: u64, filtered: u64, factories: &[String]) -> String {
+        let mut line = format!(
+            "{} event(s) in {:.1}s",
+            self.events,
+            self.start.elapsed().as_secs_f64()
+        );
+        if !self.modules.is_empty() {
+            let modules: Vec<_> = self
+                .modules
+                .iter()
+                .map(|(module, n)| format!("{module} {n}"))
+                .collect();
+            line.push_str(&format!(" ({})", modules.join(", ")));
+        }
+        line.push_str(&format!(", {lost} lost, {dropped} dropped"));
+        if filtered > 0 {
+            line.push_str(&format!(", {filtered} filtered"));
+        }
+        factories
+            .iter()
+            .for_each(|stats| line.push_str(&format!("; {stats}")));
+        line
+    }
+}
+
+/// Statistics periodically reported during a collection. They are cumulative, each report
+/// covering all the events seen since the collection started.
+struct PeriodicStats {
+    interval: Duration,
+    last: Instant,
+    flow_install: FlowInstallLatency,
+}
+
+impl PeriodicStats {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Instant::now(),
+            flow_install: FlowInstallLatency::new(),
+        }
+    }
+
+    fn process_one(&mut self, event: &Event) {
+        self.flow_install.process_one(event);
+    }
+
+    /// Report the statistics on stderr if the interval elapsed.
+    fn tick(&mut self) -> Result<()> {
+        if self.last.elapsed() < self.interval {
+            return Ok(());
+        }
+        self.last = Instant::now();
+
+        let mut report = Vec::new();
+        self.flow_install.write(&mut report)?;
+        if !report.is_empty() {
+            let mut stderr = io::stderr().lock();
+            writeln!(stderr, "stats since the collection started:")?;
+            stderr.write_all(&report)?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates an id for the collection session, unique enough to tell apart
+/// sessions appended to the same file.
+fn new_session_id() -> Result<u64> {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(monotonic_timestamp()?);
+    hasher.write_u32(std::process::id());
+    Ok(hasher.finish())
+}
+
+/// Stamps events with their collection session and their identifier (see
+/// `CommonEvent::event_id`).
+struct EventStamper {
+    session_id: u64,
+    /// Next sequence number, per CPU.
+    seqs: HashMap<Option<u32>, u64>,
+}
+
+impl EventStamper {
+    fn new(session_id: u64) -> Self {
+        Self {
+            session_id,
+            seqs: HashMap::new(),
+        }
+    }
+
+    fn stamp(&mut self, event: &mut Event) {
+        if let Some(common) = event.get_section_mut::<CommonEvent>(SectionId::Common) {
+            let seq = self.seqs.entry(common.smp_id).or_default();
+            common.session_id = Some(self.session_id);
+            common.event_id = Some(CommonEvent::event_id(self.session_id, common.smp_id, *seq));
+            *seq += 1;
+        }
     }
 }
 
@@ -734,4 +958,58 @@ mod tests {
         assert!(collectors.start().is_ok());
         Ok(())
     }
+
+    #[test]
+    fn capture_summary() -> Result<()> {
+        let mut summary = CaptureSummary::new();
+
+        let mut event = Event::new();
+        event.insert_section(SectionId::Common, Box::new(CommonEvent::default()))?;
+        summary.process_one(&event);
+        event.insert_section(SectionId::Skb, Box::new(SkbEvent::default()))?;
+        summary.process_one(&event);
+        event.insert_section(
+            SectionId::Ovs,
+            Box::new(OvsEvent {
+                event: OvsEventType::Upcall(UpcallEvent::default()),
+            }),
+        )?;
+        summary.process_one(&event);
+
+        let line = summary.line(2, 0, 0, &[]);
+        assert!(line.starts_with("3 event(s) in "));
+        assert!(line.ends_with("s (ovs 1, skb 2), 2 lost, 0 dropped"));
+        let line = summary.line(2, 0, 0, &["ovs: upcall 1".to_string()]);
+        assert!(line.ends_with(", 2 lost, 0 dropped; ovs: upcall 1"));
+        let line = summary.line(2, 0, 5, &["ovs: upcall 1".to_string()]);
+        assert!(line.ends_with(", 2 lost, 0 dropped, 5 filtered; ovs: upcall 1"));
+        Ok(())
+    }
+
+    #[test]
+    fn event_stamper() -> Result<()> {
+        let mut stamper = EventStamper::new(0x1234_5678_9abc_def0);
+        let mut event_id = |smp_id| -> Result<Option<String>> {
+            let mut event = Event::new();
+            event.insert_section(
+                SectionId::Common,
+                Box::new(CommonEvent {
+                    smp_id,
+                    ..Default::default()
+                }),
+            )?;
+            stamper.stamp(&mut event);
+            Ok(event
+                .get_section::<CommonEvent>(SectionId::Common)
+                .unwrap()
+                .event_id
+                .clone())
+        };
+
+        assert_eq!(event_id(Some(2))?.as_deref(), Some("9abcdef0-2-0"));
+        assert_eq!(event_id(Some(0))?.as_deref(), Some("9abcdef0-0-0"));
+        assert_eq!(event_id(Some(2))?.as_deref(), Some("9abcdef0-2-1"));
+        assert_eq!(event_id(None)?.as_deref(), Some("9abcdef0-x-0"));
+        Ok(())
+    }
 }
@@ -8,7 +8,10 @@ use std::{
     collections::HashMap,
     mem,
     os::fd::{AsFd, AsRawFd, RawFd},
-    sync::mpsc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     thread,
     time::Duration,
 };
@@ -17,6 +20,7 @@ use anyhow::{anyhow, bail, Result};
 use log::{error, log, Level};
 use plain::Plain;
 
+use super::queue::*;
 use crate::{
     bindings::events_uapi::*, event_section_factory, events::*, helpers::signals::Running,
 };
@@ -87,8 +91,17 @@ pub(crate) enum EventResult {
 pub(crate) struct BpfEventsFactory {
     map: libbpf_rs::MapHandle,
     log_map: libbpf_rs::MapHandle,
-    /// Receiver channel to retrieve events from the processing loop.
-    rxc: Option<mpsc::Receiver<Event>>,
+    /// Queue to retrieve events from the processing loop.
+    queue: Option<EventQueue<Event>>,
+    /// Size and overflow policy of the above queue. Unbounded if no size is
+    /// set.
+    queue_size: Option<usize>,
+    queue_policy: OverflowPolicy,
+    /// Statistics of the section factories, saved once the polling thread
+    /// stops.
+    factories_stats: Arc<Mutex<Vec<String>>>,
+    /// Number of events filtered out by the section factories.
+    filtered: Arc<AtomicU64>,
     /// Polling thread handle.
     handle: Option<thread::JoinHandle<()>>,
     log_handle: Option<thread::JoinHandle<()>>,
@@ -129,13 +142,44 @@ impl BpfEventsFactory {
         Ok(BpfEventsFactory {
             map,
             log_map,
-            rxc: None,
+            queue: None,
+            queue_size: None,
+            queue_policy: OverflowPolicy::default(),
+            factories_stats: Arc::new(Mutex::new(Vec::new())),
+            filtered: Arc::new(AtomicU64::new(0)),
             handle: None,
             log_handle: None,
             run_state: Running::new(),
         })
     }
 
+    /// Set the size and the overflow policy of the queue holding the events
+    /// waiting to be processed; `None` keeps it unbounded. Must be called
+    /// before `start()`.
+    pub(crate) fn set_queue(&mut self, size: Option<usize>, policy: OverflowPolicy) {
+        self.queue_size = size;
+        self.queue_policy = policy;
+    }
+
+    /// Number of events dropped because the events queue was full.
+    pub(crate) fn dropped_events(&self) -> u64 {
+        self.queue.as_ref().map(|q| q.dropped()).unwrap_or_default()
+    }
+
+    /// Number of events filtered out by the section factories (see
+    /// `RawEventSectionFactory::create_filtered`).
+    pub(crate) fn filtered_events(&self) -> u64 {
+        self.filtered.load(Ordering::Relaxed)
+    }
+
+    /// Statistics reported by the section factories (see
+    /// `RawEventSectionFactory::stats_summary`), ordered by factory id. Only
+    /// available once stopped.
+    pub(crate) fn factories_stats(&self) -> Vec<String> {
+        // Unwrap as we never panic while holding the lock.
+        self.factories_stats.lock().unwrap().clone()
+    }
+
     /// Get the events map fd for reuse.
     pub(crate) fn map_fd(&self) -> RawFd {
         self.map.as_fd().as_raw_fd()
@@ -181,16 +225,21 @@ impl BpfEventsFactory {
 impl BpfEventsFactory {
     /// This starts the event polling mechanism. A dedicated thread is started
     /// for events to be retrieved and processed.
-    pub(crate) fn start(&mut self, mut section_factories: SectionFactories) -> Result<()> {
+    pub(crate) fn start(&mut self, section_factories: SectionFactories) -> Result<()> {
         if section_factories.is_empty() {
             bail!("No section factory, can't parse events, aborting");
         }
+        let mut section_factories = PollingFactories {
+            factories: section_factories,
+            stats: Arc::clone(&self.factories_stats),
+        };
 
-        // Create the sending and receiving channels.
-        let (txc, rxc) = mpsc::channel();
-        self.rxc = Some(rxc);
+        // Create the events queue.
+        let queue = EventQueue::new(self.queue_size, self.queue_policy);
+        self.queue = Some(queue.clone());
 
         let run_state = self.run_state.clone();
+        let filtered = Arc::clone(&self.filtered);
         // Closure to handle the raw events coming from the BPF part.
         let process_event = move |data: &[u8]| -> i32 {
             // If a termination signal got received, return (EINTR)
@@ -202,18 +251,21 @@ impl BpfEventsFactory {
                 return -4;
             }
             // Parse the raw event.
-            let event = match parse_raw_event(data, &mut section_factories) {
-                Ok(event) => event,
+            let event = match parse_raw_event(data, &mut section_factories.factories) {
+                Ok(Some(event)) => event,
+                // The event was filtered out.
+                Ok(None) => {
+                    filtered.fetch_add(1, Ordering::Relaxed);
+                    return 0;
+                }
                 Err(e) => {
                     error!("Could not parse raw event: {}", e);
                     return 0;
                 }
             };
 
-            // Send the event into the events channel for future retrieval.
-            if let Err(e) = txc.send(event) {
-                error!("Could not send event: {}", e);
-            }
+            // Send the event into the events queue for future retrieval.
+            queue.push(event, &run_state);
 
             0
         };
@@ -285,26 +337,58 @@ impl BpfEventsFactory {
 
     /// Retrieve the next event. This is a blocking call and never returns EOF.
     pub(crate) fn next_event(&mut self, timeout: Option<Duration>) -> Result<EventResult> {
-        let rxc = match &self.rxc {
-            Some(rxc) => rxc,
-            None => bail!("Can't get event, no rx channel found."),
+        let queue = match &self.queue {
+            Some(queue) => queue,
+            None => bail!("Can't get event, no events queue found."),
         };
 
         Ok(match timeout {
-            Some(timeout) => match rxc.recv_timeout(timeout) {
-                Ok(event) => EventResult::Event(event),
-                Err(mpsc::RecvTimeoutError::Timeout) => EventResult::Timeout,
-                Err(e) => return Err(anyhow!(e)),
+            Some(timeout) => match queue.pop(timeout) {
+                Some(event) => EventResult::Event(event),
+                None => EventResult::Timeout,
+            },
+            None => loop {
+                if let Some(event) = queue.pop(Duration::from_millis(BPF_EVENTS_POLL_TIMEOUT_MS)) {
+                    break EventResult::Event(event);
+                }
             },
-            None => EventResult::Event(rxc.recv()?),
         })
     }
 }
 
+/// Section factories owned by the polling thread. Their statistics are saved
+/// when the thread stops and drops them, as they can't be shared.
+#[cfg(not(test))]
+struct PollingFactories {
+    factories: SectionFactories,
+    stats: Arc<Mutex<Vec<String>>>,
+}
+
+#[cfg(not(test))]
+impl Drop for PollingFactories {
+    fn drop(&mut self) {
+        let mut stats = self
+            .factories
+            .iter()
+            .filter_map(|(id, factory)| factory.stats_summary().map(|stats| (*id as u8, stats)))
+            .collect::<Vec<_>>();
+        stats.sort();
+
+        // Unwrap as we never panic while holding the lock.
+        *self.stats.lock().unwrap() = stats.into_iter().map(|(_, stats)| stats).collect();
+    }
+}
+
+/// Parses a raw event into an `Event`. Returns `Ok(None)` if one of the factories filtered the
+/// event out.
+///
+/// Sections are handled by factory id, factories which can filter events being run first (see
+/// `RawEventSectionFactory::filters`): filtered out events are not seen by the other factories,
+/// whatever the order of the sections in the raw event.
 pub(crate) fn parse_raw_event<'a>(
     data: &'a [u8],
     factories: &'a mut SectionFactories,
-) -> Result<Event> {
+) -> Result<Option<Event>> {
     // First retrieve the buffer length.
     let data_size = data.len();
     if data_size < 2 {
@@ -376,29 +460,59 @@ pub(crate) fn parse_raw_event<'a>(
             .push(raw_section);
     }
 
+    let mut raw_sections = raw_sections.into_iter().collect::<Vec<_>>();
+    raw_sections.sort_by_key(|(owner, _)| {
+        let filters = factories.get(owner).is_some_and(|f| f.filters());
+        (!filters, *owner as u8)
+    });
+
     let mut event = Event::new();
-    raw_sections.drain().try_for_each(|(owner, sections)| {
+    for (owner, sections) in raw_sections.into_iter() {
         let factory = factories
             .get_mut(&owner)
             .ok_or_else(|| anyhow!("Unknown factory {}", owner as u8))?;
 
-        let section = factory
-            .create(sections)
-            .map_err(|e| anyhow!("Factory {} failed to parse section: {e}", owner as u8))?;
-        event.insert_section(SectionId::from_u8(section.id())?, section)
-    })?;
+        let section = match factory
+            .create_filtered(sections)
+            .map_err(|e| anyhow!("Factory {} failed to parse section: {e}", owner as u8))?
+        {
+            Some(section) => section,
+            None => return Ok(None),
+        };
+        event.insert_section(SectionId::from_u8(section.id())?, section)?;
+    }
 
-    Ok(event)
+    Ok(Some(event))
+}
+
+/// Describes the type a raw section is parsed into and the section itself, for
+/// error reporting, e.g. "exec_output (Ovs, data type 7)".
+fn raw_section_context<T>(header: Option<&BpfRawSectionHeader>) -> String {
+    // Only keep the type name, without its path.
+    let name = any::type_name::<T>();
+    let name = name.rsplit("::").next().unwrap_or(name);
+
+    match header {
+        Some(header) => {
+            // Copy the fields out of the packed header.
+            let (owner, data_type) = (header.owner, header.data_type);
+            match FactoryId::from_u8(owner) {
+                Ok(id) => format!("{name} ({id:?}, data type {data_type})"),
+                Err(_) => format!("{name} (owner {owner}, data type {data_type})"),
+            }
+        }
+        None => name.to_string(),
+    }
 }
 
 /// Helper to check a raw section validity and parse it into a structured type.
 pub(crate) fn parse_raw_section<'a, T>(raw_section: &'a BpfRawSection) -> Result<&'a T> {
     if raw_section.data.len() != mem::size_of::<T>() {
         bail!(
-            "Section data {} is not the expected size ({} != {})",
-            any::type_name::<T>(),
+            "Failed to parse {}: expected {} bytes, got {}",
+            raw_section_context::<T>(Some(&raw_section.header)),
+            mem::size_of::<T>(),
             raw_section.data.len(),
-            mem::size_of::<T>()
         );
     }
 
@@ -409,7 +523,11 @@ pub(crate) fn parse_raw_section<'a, T>(raw_section: &'a BpfRawSection) -> Result
 /// section validity and parsing it into a structured type.
 pub(crate) fn parse_single_raw_section<'a, T>(raw_sections: &'a [BpfRawSection]) -> Result<&'a T> {
     if raw_sections.len() != 1 {
-        bail!("Raw event must be a single section");
+        bail!(
+            "Failed to parse {}: expected a single section, got {}",
+            raw_section_context::<T>(raw_sections.first().map(|s| &s.header)),
+            raw_sections.len(),
+        );
     }
 
     // We can access the first element safely as we just checked the vector
@@ -464,6 +582,16 @@ impl BpfEventsFactory {
     pub(crate) fn map_fd(&self) -> i32 {
         0
     }
+    pub(crate) fn set_queue(&mut self, _: Option<usize>, _: OverflowPolicy) {}
+    pub(crate) fn dropped_events(&self) -> u64 {
+        0
+    }
+    pub(crate) fn filtered_events(&self) -> u64 {
+        0
+    }
+    pub(crate) fn factories_stats(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 #[cfg(test)]
 impl BpfEventsFactory {
@@ -530,6 +658,30 @@ pub(crate) trait EventSectionFactory: RawEventSectionFactory {
 /// per-object implementation.
 pub(crate) trait RawEventSectionFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>) -> Result<Box<dyn EventSection>>;
+
+    /// Same as `create`, but the factory can also filter the event out after
+    /// decoding its section, in which case `Ok(None)` is returned and the whole
+    /// event is discarded. Factories do not filter events by default.
+    fn create_filtered(
+        &mut self,
+        raw_sections: Vec<BpfRawSection>,
+    ) -> Result<Option<Box<dyn EventSection>>> {
+        self.create(raw_sections).map(Some)
+    }
+
+    /// Whether `create_filtered` can filter events out. Factories which do are
+    /// run before the others, so that filtered out events are not accounted
+    /// for elsewhere.
+    fn filters(&self) -> bool {
+        false
+    }
+
+    /// One line summary of the statistics of the factory, if any, reported
+    /// when the collection stops (e.g. "ovs: upcall 3"). Factories do not
+    /// report statistics by default.
+    fn stats_summary(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Identifier for factories. Should match their counterparts in the BPF side.
@@ -626,11 +778,15 @@ mod tests {
 
     #[event_section_factory(FactoryId::Common)]
     #[derive(Default)]
-    struct TestEventFactory {}
+    struct TestEventFactory {
+        /// Number of sections created.
+        created: u64,
+    }
 
     impl RawEventSectionFactory for TestEventFactory {
         fn create(&mut self, raw_sections: Vec<BpfRawSection>) -> Result<Box<dyn EventSection>> {
             let mut event = TestEvent::default();
+            self.created += 1;
 
             for raw in raw_sections.iter() {
                 let len = raw.data.len();
@@ -659,6 +815,35 @@ mod tests {
         }
     }
 
+    /// Filters out events whose section data starts with 0.
+    #[event_section_factory(FactoryId::SkbTracking)]
+    #[derive(Default)]
+    struct TestFilterFactory {}
+
+    impl RawEventSectionFactory for TestFilterFactory {
+        fn create(&mut self, _: Vec<BpfRawSection>) -> Result<Box<dyn EventSection>> {
+            Ok(Box::new(SkbTrackingEvent {
+                orig_head: 0,
+                timestamp: 0,
+                skb: 0,
+            }))
+        }
+
+        fn create_filtered(
+            &mut self,
+            raw_sections: Vec<BpfRawSection>,
+        ) -> Result<Option<Box<dyn EventSection>>> {
+            if raw_sections.first().is_some_and(|raw| raw.data[0] == 0) {
+                return Ok(None);
+            }
+            self.create(raw_sections).map(Some)
+        }
+
+        fn filters(&self) -> bool {
+            true
+        }
+    }
+
     #[test]
     fn parse_raw_event() {
         let mut factories: SectionFactories = HashMap::new();
@@ -725,7 +910,9 @@ mod tests {
             0,
             0,
         ];
-        let event = super::parse_raw_event(&data, &mut factories).unwrap();
+        let event = super::parse_raw_event(&data, &mut factories)
+            .unwrap()
+            .unwrap();
         let section = event.get_section::<TestEvent>(SectionId::Common).unwrap();
         assert!(section.field0 == Some(42));
 
@@ -781,9 +968,85 @@ mod tests {
             0,
             0,
         ];
-        let event = super::parse_raw_event(&data, &mut factories).unwrap();
+        let event = super::parse_raw_event(&data, &mut factories)
+            .unwrap()
+            .unwrap();
         let section = event.get_section::<TestEvent>(SectionId::Common).unwrap();
         assert!(section.field1 == Some(42));
         assert!(section.field2 == Some(1337));
     }
+
+    #[test]
+    fn parse_raw_event_filtered() -> Result<()> {
+        let mut factories: SectionFactories = HashMap::new();
+        factories.insert(FactoryId::Common, Box::<TestEventFactory>::default());
+        factories.insert(FactoryId::SkbTracking, Box::<TestFilterFactory>::default());
+
+        #[rustfmt::skip]
+        let data = |keep: u8| [
+            17, 0,
+            // Section 1
+            FactoryId::Common as u8, DATA_TYPE_U64, 8, 0, 42, 0, 0, 0, 0, 0, 0, 0,
+            // Section 2
+            FactoryId::SkbTracking as u8, 0, 1, 0, keep,
+        ];
+        let created = |factories: &mut SectionFactories| {
+            factories
+                .get_mut(&FactoryId::Common)
+                .unwrap()
+                .as_any_mut()
+                .downcast_mut::<TestEventFactory>()
+                .unwrap()
+                .created
+        };
+
+        // The filter runs first, whatever the order sections are processed
+        // in: the other factories do not see filtered out events.
+        for _ in 0..16 {
+            assert!(super::parse_raw_event(&data(0), &mut factories)?.is_none());
+        }
+        assert_eq!(created(&mut factories), 0);
+
+        let event = super::parse_raw_event(&data(1), &mut factories)?.unwrap();
+        assert!(event.get_section::<TestEvent>(SectionId::Common).is_some());
+        assert!(event
+            .get_section::<SkbTrackingEvent>(SectionId::SkbTracking)
+            .is_some());
+        assert_eq!(created(&mut factories), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_raw_section_errors() {
+        #[allow(dead_code)]
+        struct TestRawSection {
+            val: u64,
+            flag: u8,
+        }
+
+        let data = [0u8; 13];
+        let section = BpfRawSection {
+            header: BpfRawSectionHeader {
+                owner: FactoryId::Ovs as u8,
+                data_type: 3,
+                size: 13,
+            },
+            data: &data,
+        };
+        let err = parse_raw_section::<TestRawSection>(&section)
+            .err()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            err,
+            "Failed to parse TestRawSection (Ovs, data type 3): expected 16 bytes, got 13"
+        );
+
+        let err = parse_single_raw_section::<TestRawSection>(&[section.clone(), section])
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("TestRawSection"));
+        assert!(err.contains("expected a single section, got 2"));
+    }
 }
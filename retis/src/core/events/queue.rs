@@ -0,0 +1,212 @@
+//! # Event queue
+//!
+//! Queue used to pass events from the thread reading the BPF ring buffer to the
+//! processing and formatting stage. It is unbounded unless a capacity is set,
+//! in which case the configured overflow policy decides whether the producer
+//! waits when it is full, or which event is dropped.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::Duration,
+};
+
+use clap::ValueEnum;
+
+use crate::helpers::signals::Running;
+
+/// Interval at which a blocked producer checks for termination.
+const EVENTS_QUEUE_BLOCK_POLL_MS: u64 = 200;
+
+/// Policy applied when an event is pushed to a full queue.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum OverflowPolicy {
+    /// Wait for space to be available. No event is dropped by the queue, but
+    /// events can be lost in the BPF ring buffer instead.
+    #[default]
+    Block,
+    /// Drop the oldest queued event to make room for the new one.
+    DropOldest,
+    /// Drop the new event.
+    DropNewest,
+}
+
+struct EventQueueInner<T> {
+    queue: Mutex<VecDeque<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    /// Maximum number of events in the queue, if bounded.
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+/// Multi-producer, multi-consumer event queue. Cloning it gives a new handle on
+/// the same queue.
+pub(crate) struct EventQueue<T> {
+    inner: Arc<EventQueueInner<T>>,
+}
+
+// Not derived, as it would require T to implement Clone.
+impl<T> Clone for EventQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> EventQueue<T> {
+    /// Creates a new queue holding at most `capacity` events (at least one),
+    /// or an unbounded one if `capacity` is `None`. The overflow policy only
+    /// applies to bounded queues.
+    pub(crate) fn new(capacity: Option<usize>, policy: OverflowPolicy) -> Self {
+        let capacity = capacity.map(|capacity| capacity.max(1));
+
+        Self {
+            inner: Arc::new(EventQueueInner {
+                queue: Mutex::new(match capacity {
+                    Some(capacity) => VecDeque::with_capacity(capacity),
+                    None => VecDeque::new(),
+                }),
+                not_empty: Condvar::new(),
+                not_full: Condvar::new(),
+                capacity,
+                policy,
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Pushes an event to the queue, applying the overflow policy if it is
+    /// full. When blocking, gives up (and drops the event) once `run` is
+    /// terminated.
+    pub(crate) fn push(&self, event: T, run: &Running) {
+        let inner = &self.inner;
+        // Unwrap as we never panic while holding the lock.
+        let mut queue = inner.queue.lock().unwrap();
+        let full = |queue: &VecDeque<T>| inner.capacity.is_some_and(|cap| queue.len() >= cap);
+
+        if full(&queue) {
+            match inner.policy {
+                OverflowPolicy::Block => {
+                    while full(&queue) {
+                        if !run.running() {
+                            inner.dropped.fetch_add(1, Ordering::Relaxed);
+                            return;
+                        }
+                        queue = inner
+                            .not_full
+                            .wait_timeout(queue, Duration::from_millis(EVENTS_QUEUE_BLOCK_POLL_MS))
+                            .unwrap()
+                            .0;
+                    }
+                }
+                OverflowPolicy::DropOldest => {
+                    queue.pop_front();
+                    inner.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropNewest => {
+                    inner.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+
+        queue.push_back(event);
+        inner.not_empty.notify_one();
+    }
+
+    /// Retrieves the next event, waiting at most `timeout` for one to be
+    /// available. Returns `None` on timeout.
+    pub(crate) fn pop(&self, timeout: Duration) -> Option<T> {
+        let inner = &self.inner;
+        let (mut queue, _) = inner
+            .not_empty
+            .wait_timeout_while(inner.queue.lock().unwrap(), timeout, |q| q.is_empty())
+            .unwrap();
+
+        let event = queue.pop_front();
+        if event.is_some() {
+            inner.not_full.notify_one();
+        }
+        event
+    }
+
+    /// Number of events dropped because the queue was full.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(10);
+
+    fn fill(queue: &EventQueue<u32>, n: u32) {
+        let run = Running::new();
+        (0..n).for_each(|i| queue.push(i, &run));
+    }
+
+    #[test]
+    fn unbounded() {
+        let queue = EventQueue::new(None, OverflowPolicy::DropNewest);
+        fill(&queue, 16 * 1024);
+
+        assert_eq!(queue.dropped(), 0);
+        assert_eq!(queue.pop(TIMEOUT), Some(0));
+    }
+
+    #[test]
+    fn drop_newest() {
+        let queue = EventQueue::new(Some(2), OverflowPolicy::DropNewest);
+        fill(&queue, 4);
+
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(queue.pop(TIMEOUT), Some(0));
+        assert_eq!(queue.pop(TIMEOUT), Some(1));
+        assert_eq!(queue.pop(TIMEOUT), None);
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let queue = EventQueue::new(Some(2), OverflowPolicy::DropOldest);
+        fill(&queue, 4);
+
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(queue.pop(TIMEOUT), Some(2));
+        assert_eq!(queue.pop(TIMEOUT), Some(3));
+        assert_eq!(queue.pop(TIMEOUT), None);
+    }
+
+    #[test]
+    fn block() {
+        let queue = EventQueue::new(Some(1), OverflowPolicy::Block);
+        let run = Running::new();
+
+        let producer = {
+            let (queue, run) = (queue.clone(), run.clone());
+            thread::spawn(move || (0..3).for_each(|i| queue.push(i, &run)))
+        };
+
+        for i in 0..3 {
+            assert_eq!(queue.pop(Duration::from_secs(5)), Some(i));
+        }
+        producer.join().unwrap();
+        assert_eq!(queue.dropped(), 0);
+
+        // A blocked producer gives up once terminated.
+        queue.push(0, &run);
+        run.terminate();
+        queue.push(1, &run);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pop(TIMEOUT), Some(0));
+    }
+}
@@ -1,4 +1,4 @@
-use std::fs;
+use std::{collections::HashMap, fs};
 
 use anyhow::{anyhow, bail, Result};
 use btf_rs::{Btf, Type};
@@ -116,6 +116,37 @@ impl BtfInfo {
         Ok(types)
     }
 
+    /// Resolve the members of an enum, given its name, as a map of their
+    /// values to their names. The given prefixes are trimmed from the names.
+    /// An empty map is returned if the enum is not found, and members whose
+    /// name can't be resolved are skipped.
+    pub(crate) fn resolve_enum(
+        &self,
+        name: &str,
+        trim_start: &[&str],
+    ) -> Result<HashMap<u32, String>> {
+        let mut values = HashMap::new();
+
+        if let Ok(types) = self.resolve_types_by_name(name) {
+            if let Some((btf, Type::Enum(r#enum))) =
+                types.iter().find(|(_, t)| matches!(t, Type::Enum(_)))
+            {
+                for member in r#enum.members.iter() {
+                    let mut val = match btf.resolve_name(member) {
+                        Ok(val) => val,
+                        Err(_) => continue,
+                    };
+                    trim_start
+                        .iter()
+                        .for_each(|p| val = val.trim_start_matches(p).to_string());
+                    values.insert(member.val(), val);
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
     /// Look for a function symbol and return a Vec of matching Type objects as well as
     /// the Btf object where it was found.
     ///
@@ -319,4 +350,34 @@ mod tests {
             .unwrap()
             .is_none());
     }
+
+    #[test]
+    fn resolve_enum() {
+        let btf = BtfInfo::new().unwrap();
+
+        let actions = btf.resolve_enum("xdp_action", &["XDP_"]).unwrap();
+        assert_eq!(
+            actions,
+            HashMap::from([
+                (0, "ABORTED".to_string()),
+                (1, "DROP".to_string()),
+                (2, "PASS".to_string()),
+                (3, "TX".to_string()),
+                (4, "REDIRECT".to_string()),
+            ])
+        );
+
+        // Found in a module.
+        let cmds = btf
+            .resolve_enum("ovs_packet_cmd", &["OVS_PACKET_CMD_"])
+            .unwrap();
+        assert_eq!(cmds.get(&1).map(String::as_str), Some("MISS"));
+        assert_eq!(cmds.get(&3).map(String::as_str), Some("EXECUTE"));
+        let actions = btf
+            .resolve_enum("ovs_action_attr", &["OVS_ACTION_ATTR_"])
+            .unwrap();
+        assert!(actions.values().any(|name| name == "SET_TO_MASKED"));
+
+        assert!(btf.resolve_enum("not_an_enum", &[]).unwrap().is_empty());
+    }
 }
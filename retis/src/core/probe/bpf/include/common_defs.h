@@ -55,6 +55,22 @@ enum {
 /* Current log level. Actually set by user-space. */
 const volatile u8 log_level = LOG_INFO;
 
+/* Please keep in sync with its Rust counterpart in helpers::time. */
+enum {
+	CLOCK_SRC_MONOTONIC = 0,
+	CLOCK_SRC_BOOTTIME = 1,
+};
+
+/* Clock used to timestamp events. Actually set by user-space. */
+const volatile u8 clock_source = CLOCK_SRC_MONOTONIC;
+
+static __always_inline u64 retis_ktime_get_ns(void)
+{
+	if (clock_source == CLOCK_SRC_BOOTTIME)
+		return bpf_ktime_get_boot_ns();
+	return bpf_ktime_get_ns();
+}
+
 /* Log macros must be used carefully and preferrably in the
  * {error,slow} path.
  * Useful exceptions must use a high log level (ideally LOG_TRACE).
@@ -52,7 +52,7 @@ int probe_kretprobe_kretprobe(struct pt_regs *ctx)
 	}
 	bpf_map_delete_elem(&kretprobe_context, &tid);
 
-	context.timestamp = bpf_ktime_get_ns();
+	context.timestamp = retis_ktime_get_ns();
 	context.ksym = kprobe_ctx->ksym;
 	context.probe_type = KERNEL_PROBE_KRETPROBE;
 	context.orig_ctx = ctx;
@@ -68,7 +68,7 @@ int probe_kretprobe_kprobe(struct pt_regs *ctx)
 	struct retis_context context = {};
 	u64 tid = bpf_get_current_pid_tgid();
 
-	context.timestamp = bpf_ktime_get_ns();
+	context.timestamp = retis_ktime_get_ns();
 	context.ksym = kprobe_get_func_ip(ctx);
 	kprobe_get_regs(&context.regs, ctx);
 
@@ -46,7 +46,7 @@ int probe_raw_tracepoint(struct bpf_raw_tracepoint_args *ctx)
 {
 	struct retis_context context = {};
 
-	context.timestamp = bpf_ktime_get_ns();
+	context.timestamp = retis_ktime_get_ns();
 	context.ksym = ksym;
 	context.probe_type = KERNEL_PROBE_TRACEPOINT;
 	context.orig_ctx = ctx;
@@ -9,7 +9,10 @@ use std::os::fd::{AsFd, AsRawFd, RawFd};
 use anyhow::{anyhow, bail, Result};
 use libbpf_rs::skel::SkelBuilder;
 
-use crate::core::{filters::Filter, probe::builder::*, probe::*};
+use crate::{
+    core::{filters::Filter, probe::builder::*, probe::*},
+    helpers::time::bpf_clock_source,
+};
 
 mod kprobe_bpf {
     include!("bpf/.out/kprobe.skel.rs");
@@ -40,6 +43,7 @@ impl ProbeBuilder for KprobeBuilder {
         let mut skel = KprobeSkelBuilder::default().open()?;
         skel.rodata_mut().nhooks = hooks.len() as u32;
         skel.rodata_mut().log_level = log::max_level() as u8;
+        skel.rodata_mut().clock_source = bpf_clock_source();
 
         filters.iter().for_each(|f| {
             if let Filter::Meta(m) = f {
@@ -13,7 +13,10 @@ use std::os::fd::{AsFd, AsRawFd, RawFd};
 use anyhow::{anyhow, bail, Result};
 use libbpf_rs::skel::SkelBuilder;
 
-use crate::core::{filters::Filter, probe::builder::*, probe::*};
+use crate::{
+    core::{filters::Filter, probe::builder::*, probe::*},
+    helpers::time::bpf_clock_source,
+};
 
 mod kretprobe_bpf {
     include!("bpf/.out/kretprobe.skel.rs");
@@ -44,6 +47,7 @@ impl ProbeBuilder for KretprobeBuilder {
         let mut skel = KretprobeSkelBuilder::default().open()?;
         skel.rodata_mut().nhooks = hooks.len() as u32;
         skel.rodata_mut().log_level = log::max_level() as u8;
+        skel.rodata_mut().clock_source = bpf_clock_source();
 
         filters.iter().for_each(|f| {
             if let Filter::Meta(m) = f {
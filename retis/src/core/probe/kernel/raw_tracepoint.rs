@@ -10,7 +10,10 @@ use std::os::fd::{AsFd, AsRawFd, RawFd};
 use anyhow::{anyhow, bail, Result};
 use libbpf_rs::skel::SkelBuilder;
 
-use crate::core::{filters::Filter, probe::builder::*, probe::*};
+use crate::{
+    core::{filters::Filter, probe::builder::*, probe::*},
+    helpers::time::bpf_clock_source,
+};
 
 mod raw_tracepoint_bpf {
     include!("bpf/.out/raw_tracepoint.skel.rs");
@@ -56,6 +59,7 @@ impl ProbeBuilder for RawTracepointBuilder {
         skel.rodata_mut().nargs = probe.symbol.nargs()?;
         skel.rodata_mut().nhooks = self.hooks.len() as u32;
         skel.rodata_mut().log_level = log::max_level() as u8;
+        skel.rodata_mut().clock_source = bpf_clock_source();
 
         self.filters.iter().for_each(|f| {
             if let Filter::Meta(m) = f {
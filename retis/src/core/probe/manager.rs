@@ -554,12 +554,13 @@ impl ProbeRuntimeManager {
     }
 
     #[cfg(test)]
-    pub(crate) fn report_counters(&self) -> Result<()> {
-        Ok(())
+    pub(crate) fn report_counters(&self) -> Result<u64> {
+        Ok(0)
     }
 
+    /// Report the events lost by the probes. Returns the total number of events lost.
     #[cfg(not(test))]
-    pub(crate) fn report_counters(&self) -> Result<()> {
+    pub(crate) fn report_counters(&self) -> Result<u64> {
         let mut counters_key = CountersKey::default();
         let mut counters = Counters::default();
         let mut total_lost: u64 = 0;
@@ -606,7 +607,7 @@ impl ProbeRuntimeManager {
             warn!("total events lost: {total_lost}");
         }
 
-        Ok(())
+        Ok(total_lost)
     }
 }
 
@@ -82,7 +82,7 @@ int probe_usdt(struct pt_regs *ctx)
 	if (!e)
 		goto discard_event;
 
-	uctx.timestamp = bpf_ktime_get_ns();
+	uctx.timestamp = retis_ktime_get_ns();
 	e->timestamp = uctx.timestamp;
 	e->smp_id = bpf_get_smp_processor_id();
 
@@ -6,6 +6,7 @@ use libbpf_rs::skel::SkelBuilder;
 use crate::core::filters::Filter;
 use crate::core::probe::builder::*;
 use crate::core::probe::{Hook, Probe, ProbeType};
+use crate::helpers::time::bpf_clock_source;
 
 mod usdt_bpf {
     include!("bpf/.out/usdt.skel.rs");
@@ -47,6 +48,7 @@ impl ProbeBuilder for UsdtBuilder {
 
         let mut skel = UsdtSkelBuilder::default().open()?;
         skel.rodata_mut().log_level = log::max_level() as u8;
+        skel.rodata_mut().clock_source = bpf_clock_source();
         let open_obj = skel.obj;
         reuse_map_fds(&open_obj, &self.map_fds)?;
 
@@ -3,4 +3,6 @@ pub(crate) mod logger;
 pub(crate) mod net;
 pub(crate) mod pager;
 pub(crate) mod signals;
+#[cfg(test)]
+pub(crate) mod testing;
 pub(crate) mod time;
@@ -0,0 +1,27 @@
+//! # Testing
+//!
+//! Helpers shared by unit tests.
+
+use crate::events::*;
+
+/// Raw section holding a copy of `data`.
+pub(crate) fn raw_bytes<T>(data: &T) -> Vec<u8> {
+    unsafe { std::slice::from_raw_parts((data as *const T) as *const u8, std::mem::size_of::<T>()) }
+        .to_vec()
+}
+
+/// Event made of a common section, holding `timestamp`, and an OvS one.
+pub(crate) fn ovs_event(timestamp: u64, event: OvsEventType) -> Event {
+    let mut e = Event::new();
+    e.insert_section(
+        SectionId::Common,
+        Box::new(CommonEvent {
+            timestamp,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+    e.insert_section(SectionId::Ovs, Box::new(OvsEvent { event }))
+        .unwrap();
+    e
+}
@@ -1,25 +1,148 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
 use anyhow::{bail, Result};
+use clap::ValueEnum;
+use log::warn;
 use nix::time::{clock_gettime, ClockId};
 
-use crate::events::TimeSpec;
+use crate::events::{ClockSource, StartupEvent, TimeSpec};
+
+/// Type of the "clock" argument.
+// It is an enum that maps 1:1 with the clock sources defined in events library.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum CliClockSource {
+    #[default]
+    Monotonic,
+    Boottime,
+}
+
+impl From<CliClockSource> for ClockSource {
+    fn from(clock: CliClockSource) -> Self {
+        match clock {
+            CliClockSource::Monotonic => ClockSource::Monotonic,
+            CliClockSource::Boottime => ClockSource::Boottime,
+        }
+    }
+}
+
+// Please keep in sync with its BPF counterpart in common_defs.h.
+const CLOCK_SRC_MONOTONIC: u8 = 0;
+const CLOCK_SRC_BOOTTIME: u8 = 1;
+
+/// Clock used to timestamp events, both by the BPF probes and in user space.
+static CLOCK_SOURCE: AtomicU8 = AtomicU8::new(CLOCK_SRC_MONOTONIC);
+
+/// Sets the clock used to timestamp events. Must be called before probes are
+/// loaded.
+pub(crate) fn set_clock_source(clock: ClockSource) {
+    CLOCK_SOURCE.store(
+        match clock {
+            ClockSource::Monotonic => CLOCK_SRC_MONOTONIC,
+            ClockSource::Boottime => CLOCK_SRC_BOOTTIME,
+        },
+        Ordering::Relaxed,
+    );
+}
+
+/// Returns the clock used to timestamp events.
+pub(crate) fn clock_source() -> ClockSource {
+    match CLOCK_SOURCE.load(Ordering::Relaxed) {
+        CLOCK_SRC_BOOTTIME => ClockSource::Boottime,
+        _ => ClockSource::Monotonic,
+    }
+}
+
+/// Returns the value to set in the BPF `clock_source` configuration.
+pub(crate) fn bpf_clock_source() -> u8 {
+    CLOCK_SOURCE.load(Ordering::Relaxed)
+}
+
+fn clock_id(clock: ClockSource) -> ClockId {
+    match clock {
+        ClockSource::Monotonic => ClockId::CLOCK_MONOTONIC,
+        ClockSource::Boottime => ClockId::CLOCK_BOOTTIME,
+    }
+}
 
-/// Returns the monotonic timestamp in nanoseconds.
+/// Returns the timestamp of the clock used to timestamp events (see
+/// `set_clock_source`), in nanoseconds.
 pub(crate) fn monotonic_timestamp() -> Result<u64> {
-    let monotonic = clock_gettime(ClockId::CLOCK_MONOTONIC)?;
+    let clock = clock_source();
+    let now = clock_gettime(clock_id(clock))?;
 
-    let ts = monotonic.tv_sec() * 1000000000 + monotonic.tv_nsec();
+    let ts = now.tv_sec() * 1000000000 + now.tv_nsec();
     if ts < 0 {
-        bail!("Monotonic timestamp is negative: {ts}");
+        bail!("{clock} timestamp is negative: {ts}");
     }
 
     Ok(ts as u64)
 }
 
-/// Computes and returns the offset of CLOCK_MONOTONIC to the wall-clock time.
+/// Computes and returns the offset of the clock used to timestamp events (see
+/// `set_clock_source`) to the wall-clock time.
 pub(crate) fn monotonic_clock_offset() -> Result<TimeSpec> {
     let realtime = clock_gettime(ClockId::CLOCK_REALTIME)?;
-    let monotonic = clock_gettime(ClockId::CLOCK_MONOTONIC)?;
-    let offset = realtime - monotonic;
+    let now = clock_gettime(clock_id(clock_source()))?;
+    let offset = realtime - now;
 
     Ok(TimeSpec::new(offset.tv_sec(), offset.tv_nsec()))
 }
+
+/// Detects events timestamped using different clocks, whose timestamps can't
+/// be compared (e.g. sessions appended to the same file).
+#[derive(Default)]
+pub(crate) struct ClockCheck {
+    clock: Option<ClockSource>,
+    warned: bool,
+}
+
+impl ClockCheck {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the clock of a startup event against the ones seen so far.
+    /// Returns true if clocks are mixed, in which case a warning is emitted the
+    /// first time.
+    pub(crate) fn check(&mut self, startup: &StartupEvent) -> bool {
+        let clock = startup.clock();
+        match self.clock {
+            Some(prev) if prev != clock => {
+                if !self.warned {
+                    warn!("Events were timestamped using different clocks ({prev} and {clock}), timestamps can't be compared");
+                    self.warned = true;
+                }
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.clock = Some(clock);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn startup(clock_source: Option<ClockSource>) -> StartupEvent {
+        StartupEvent {
+            retis_version: "test".to_string(),
+            clock_monotonic_offset: TimeSpec::new(0, 0),
+            session_id: None,
+            clock_source,
+        }
+    }
+
+    #[test]
+    fn clock_check() {
+        let mut check = ClockCheck::new();
+
+        // Older versions did not report the clock, which was monotonic.
+        assert!(!check.check(&startup(None)));
+        assert!(!check.check(&startup(Some(ClockSource::Monotonic))));
+        assert!(check.check(&startup(Some(ClockSource::Boottime))));
+    }
+}
@@ -2,29 +2,45 @@
 //! Many parts of the OvsEvent (defined in retis-events) are used directly to parse
 //! the bpf raw event. Please keep them in sync.
 
-use std::net::Ipv6Addr;
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
 
 use anyhow::{anyhow, bail, Result};
+use once_cell::sync::OnceCell;
 
+use super::port_names::PortNames;
 use crate::{
     bindings::{
         kernel_enqueue_uapi::upcall_enqueue_event,
-        kernel_exec_tp_uapi::{exec_ct, exec_event, exec_output, exec_recirc, exec_track_event},
+        kernel_exec_tp_uapi::{
+            exec_add_mpls, exec_check_pkt_len, exec_clone, exec_ct, exec_dec_ttl, exec_event,
+            exec_hash, exec_meter, exec_output, exec_pop_nsh, exec_pop_vlan, exec_push_eth,
+            exec_push_mpls, exec_push_vlan, exec_recirc, exec_sample, exec_set, exec_track_event,
+            exec_trunc, exec_userspace,
+        },
         kernel_upcall_ret_uapi::upcall_ret_event,
         kernel_upcall_tp_uapi::upcall_event,
-        ovs_operation_uapi::ovs_operation_event,
+        ovs_common_uapi::exec_error,
+        ovs_operation_uapi::{ovs_operation_event, ovs_operation_flow},
         user_recv_upcall_uapi::recv_upcall_event,
     },
-    core::events::{
-        parse_raw_section, BpfRawSection, EventSectionFactory, FactoryId, RawEventSectionFactory,
+    core::{
+        events::{
+            parse_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+            RawEventSectionFactory,
+        },
+        inspect::{inspector, kernel_version::KernelVersionReq},
     },
     event_section_factory,
-    events::*,
-    helpers,
+    events::{helpers::ret_errno_str, *},
+    helpers, raw_to_string, raw_to_string_opt,
 };
 
 /// Event data types supported by the ovs module.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum OvsDataType {
     /// Upcall tracepoint.
     Upcall = 0,
@@ -46,6 +62,40 @@ pub(crate) enum OvsDataType {
     RecircAction = 8,
     /// Conntrack action.
     ConntrackAction = 9,
+    /// Pop vlan action.
+    PopVlanAction = 10,
+    /// Hash action.
+    HashAction = 11,
+    /// Meter action.
+    MeterAction = 12,
+    /// Set and set_masked actions.
+    SetAction = 13,
+    /// Pop nsh action.
+    PopNshAction = 14,
+    /// Check pkt len action.
+    CheckPktLenAction = 15,
+    /// Userspace action.
+    UserspaceAction = 16,
+    /// Flow key and mask of a flow operation.
+    OperationFlow = 17,
+    /// Add MPLS action.
+    AddMplsAction = 18,
+    /// Failure of an action list execution.
+    ActionError = 19,
+    /// Trunc action.
+    TruncAction = 20,
+    /// Push vlan action.
+    PushVlanAction = 21,
+    /// Push MPLS action.
+    PushMplsAction = 22,
+    /// Sample action.
+    SampleAction = 23,
+    /// Dec TTL action.
+    DecTtlAction = 24,
+    /// Push Ethernet action.
+    PushEthAction = 25,
+    /// Clone action.
+    CloneAction = 26,
 }
 
 impl OvsDataType {
@@ -62,9 +112,68 @@ impl OvsDataType {
             7 => OutputAction,
             8 => RecircAction,
             9 => ConntrackAction,
+            10 => PopVlanAction,
+            11 => HashAction,
+            12 => MeterAction,
+            13 => SetAction,
+            14 => PopNshAction,
+            15 => CheckPktLenAction,
+            16 => UserspaceAction,
+            17 => OperationFlow,
+            18 => AddMplsAction,
+            19 => ActionError,
+            20 => TruncAction,
+            21 => PushVlanAction,
+            22 => PushMplsAction,
+            23 => SampleAction,
+            24 => DecTtlAction,
+            25 => PushEthAction,
+            26 => CloneAction,
             x => bail!("Can't construct a OvsDataType from {}", x),
         })
     }
+
+    /// Inverse of `from_u8`, used when building raw sections.
+    #[cfg(any(test, feature = "benchmark"))]
+    pub(super) fn to_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for OvsDataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use OvsDataType::*;
+        let name = match self {
+            Upcall => "upcall",
+            UpcallEnqueue => "upcall_enqueue",
+            UpcallReturn => "upcall_return",
+            RecvUpcall => "recv_upcall",
+            Operation => "operation",
+            ActionExec => "action_exec",
+            ActionExecTrack => "action_exec_track",
+            OutputAction => "output_action",
+            RecircAction => "recirc_action",
+            ConntrackAction => "conntrack_action",
+            PopVlanAction => "pop_vlan_action",
+            HashAction => "hash_action",
+            MeterAction => "meter_action",
+            SetAction => "set_action",
+            PopNshAction => "pop_nsh_action",
+            CheckPktLenAction => "check_pkt_len_action",
+            UserspaceAction => "userspace_action",
+            OperationFlow => "operation_flow",
+            AddMplsAction => "add_mpls_action",
+            ActionError => "action_error",
+            TruncAction => "trunc_action",
+            PushVlanAction => "push_vlan_action",
+            PushMplsAction => "push_mpls_action",
+            SampleAction => "sample_action",
+            DecTtlAction => "dec_ttl_action",
+            PushEthAction => "push_eth_action",
+            CloneAction => "clone_action",
+        };
+        write!(f, "{name}")
+    }
 }
 
 pub(super) fn unmarshall_upcall(raw_section: &BpfRawSection) -> Result<OvsEvent> {
@@ -81,49 +190,121 @@ pub(super) fn unmarshall_upcall(raw_section: &BpfRawSection) -> Result<OvsEvent>
 pub(super) fn unmarshall_exec(raw_section: &BpfRawSection) -> Result<OvsEvent> {
     let raw = parse_raw_section::<exec_event>(raw_section)?;
 
-    // When we implement event data types for every action we will be able to create the
-    // specific action variant when unmarshaling its event data type. Until then, we need to
-    // initialize the Action here based on the action_id (which corresponds to ovs_action_attr
-    // defined in uapi/linux/openvswitch.h).
+    let action = action_from_id(raw.action);
+
     Ok(OvsEvent {
         event: OvsEventType::Action(ActionEvent {
-            action: match raw.action {
-                0 => None,
-                1 => Some(OvsAction::Output(OvsActionOutput::default())),
-                2 => Some(OvsAction::Userspace(OvsDummyAction)),
-                3 => Some(OvsAction::Set(OvsDummyAction)),
-                4 => Some(OvsAction::PushVlan(OvsDummyAction)),
-                5 => Some(OvsAction::PopVlan(OvsDummyAction)),
-                6 => Some(OvsAction::Sample(OvsDummyAction)),
-                7 => Some(OvsAction::Recirc(OvsActionRecirc::default())),
-                8 => Some(OvsAction::Hash(OvsDummyAction)),
-                9 => Some(OvsAction::PushMpls(OvsDummyAction)),
-                10 => Some(OvsAction::PopMpls(OvsDummyAction)),
-                11 => Some(OvsAction::SetMasked(OvsDummyAction)),
-                12 => Some(OvsAction::Ct(OvsActionCt::default())),
-                13 => Some(OvsAction::Trunc(OvsDummyAction)),
-                14 => Some(OvsAction::PushEth(OvsDummyAction)),
-                15 => Some(OvsAction::PopEth(OvsDummyAction)),
-                16 => Some(OvsAction::CtClear(OvsDummyAction)),
-                17 => Some(OvsAction::PushNsh(OvsDummyAction)),
-                18 => Some(OvsAction::PopNsh(OvsDummyAction)),
-                19 => Some(OvsAction::Meter(OvsDummyAction)),
-                20 => Some(OvsAction::Clone(OvsDummyAction)),
-                21 => Some(OvsAction::CheckPktLen(OvsDummyAction)),
-                22 => Some(OvsAction::AddMpls(OvsDummyAction)),
-                23 => Some(OvsAction::DecTtl(OvsDummyAction)),
-                // The private OVS_ACTION_ATTR_SET_TO_MASKED action is used
-                // in the same way as OVS_ACTION_ATTR_SET_MASKED. Use only
-                // one action to avoid confusion
-                25 => Some(OvsAction::SetMasked(OvsDummyAction)),
-                val => bail!("Unsupported action id {val}"),
-            },
+            has_detail: action_no_detail(&action),
+            action,
             recirc_id: raw.recirc_id,
             ..ActionEvent::default()
         }),
     })
 }
 
+/// Initial `has_detail` value of an action event: false if the action has no detail, unknown
+/// otherwise until its data is parsed.
+fn action_no_detail(action: &Option<OvsAction>) -> Option<bool> {
+    action
+        .as_ref()
+        .is_some_and(|a| !a.has_detail())
+        .then_some(false)
+}
+
+/// Failure of an action list execution, reported against the last action executed.
+pub(super) fn unmarshall_action_error(raw_section: &BpfRawSection) -> Result<OvsEvent> {
+    let raw = parse_raw_section::<exec_error>(raw_section)?;
+
+    let action = action_from_id(raw.action);
+
+    Ok(OvsEvent {
+        event: OvsEventType::Action(ActionEvent {
+            has_detail: action_no_detail(&action),
+            action,
+            recirc_id: raw.recirc_id,
+            queue_id: raw.command.then_some(raw.queue_id),
+            error: Some(raw.error),
+        }),
+    })
+}
+
+fn action_from_id(id: u8) -> Option<OvsAction> {
+    // When we implement event data types for every action we will be able to create the
+    // specific action variant when unmarshaling its event data type. Until then, we need to
+    // initialize the Action here based on the action_id (which corresponds to ovs_action_attr
+    // defined in uapi/linux/openvswitch.h). Actions not known by this version are reported as
+    // such instead of failing, as newer kernels can add actions.
+    match id {
+        0 => None,
+        1 => Some(OvsAction::Output(OvsActionOutput::default())),
+        2 => Some(OvsAction::Userspace(OvsActionUserspace::default())),
+        3 => Some(OvsAction::Set(OvsActionSet::default())),
+        4 => Some(OvsAction::PushVlan(OvsActionPushVlan::default())),
+        5 => Some(OvsAction::PopVlan(OvsActionPopVlan::default())),
+        6 => Some(OvsAction::Sample(OvsActionSample::default())),
+        7 => Some(OvsAction::Recirc(OvsActionRecirc::default())),
+        8 => Some(OvsAction::Hash(OvsActionHash::default())),
+        9 => Some(OvsAction::PushMpls(OvsActionPushMpls::default())),
+        10 => Some(OvsAction::PopMpls(OvsDummyAction)),
+        11 => Some(OvsAction::SetMasked(OvsActionSet::default())),
+        12 => Some(OvsAction::Ct(OvsActionCt::default())),
+        13 => Some(OvsAction::Trunc(OvsActionTrunc::default())),
+        14 => Some(OvsAction::PushEth(OvsActionPushEth::default())),
+        15 => Some(OvsAction::PopEth(OvsDummyAction)),
+        16 => Some(OvsAction::CtClear(OvsDummyAction)),
+        17 => Some(OvsAction::PushNsh(OvsDummyAction)),
+        18 => Some(OvsAction::PopNsh(OvsActionPopNsh::default())),
+        19 => Some(OvsAction::Meter(OvsActionMeter::default())),
+        20 => Some(OvsAction::Clone(OvsActionClone::default())),
+        21 => Some(OvsAction::CheckPktLen(OvsActionCheckPktLen::default())),
+        22 => Some(OvsAction::AddMpls(OvsActionAddMpls::default())),
+        23 => Some(OvsAction::DecTtl(OvsActionDecTtl::default())),
+        // The private OVS_ACTION_ATTR_SET_TO_MASKED action is used
+        // in the same way as OVS_ACTION_ATTR_SET_MASKED. Use only
+        // one action to avoid confusion
+        val if Some(val) == set_to_masked_id() => {
+            Some(OvsAction::SetMasked(OvsActionSet::default()))
+        }
+        24 => Some(OvsAction::Drop(OvsDummyAction)),
+        25 => Some(OvsAction::Psample(OvsDummyAction)),
+        val => Some(OvsAction::Unknown(OvsActionUnknown { id: val })),
+    }
+}
+
+/// Id of the private OVS_ACTION_ATTR_SET_TO_MASKED action.
+static SET_TO_MASKED_ID: OnceCell<Option<u8>> = OnceCell::new();
+
+/// Id of the private OVS_ACTION_ATTR_SET_TO_MASKED action, if known. It comes
+/// right after the last uapi action, so its value depends on the kernel.
+fn set_to_masked_id() -> Option<u8> {
+    *SET_TO_MASKED_ID.get_or_init(|| {
+        let inspector = inspector().ok()?;
+
+        if let Some(val) = inspector
+            .kernel
+            .btf
+            .resolve_enum("ovs_action_attr", &["OVS_ACTION_ATTR_"])
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|(val, name)| (name == "SET_TO_MASKED").then_some(val))
+        {
+            return u8::try_from(val).ok();
+        }
+
+        // The openvswitch BTF is not available, e.g. the module is not loaded
+        // yet. Guess from the actions added by each kernel version: drop
+        // (v6.4) and psample (v6.11).
+        let kver = inspector.kernel.version();
+        Some(if KernelVersionReq::parse(">= 6.11").ok()?.matches(kver) {
+            27
+        } else if KernelVersionReq::parse(">= 6.4").ok()?.matches(kver) {
+            26
+        } else {
+            25
+        })
+    })
+}
+
 pub(super) fn unmarshall_exec_track(
     raw_section: &BpfRawSection,
     event: &mut OvsEvent,
@@ -134,7 +315,7 @@ pub(super) fn unmarshall_exec_track(
         OvsEventType::Action(ref mut action) => action.queue_id = Some(raw.queue_id),
         other => {
             bail!(
-                "Conflicting OVS event types. Received {:?} data type but event is already {:#?}",
+                "Conflicting OVS event types. Received {} data type but event is already {:#?}",
                 OvsDataType::ActionExecTrack,
                 other
             );
@@ -145,7 +326,10 @@ pub(super) fn unmarshall_exec_track(
 
 fn update_action_event(event: &mut OvsEvent, action: OvsAction) -> Result<()> {
     match &mut event.event {
-        OvsEventType::Action(ref mut event) => event.action = Some(action),
+        OvsEventType::Action(ref mut event) => {
+            event.action = Some(action);
+            event.has_detail = Some(true);
+        }
         other => {
             bail!(
                 "Conflicting OVS event types. Received {:?} data type but event is already {:#?}",
@@ -160,7 +344,14 @@ fn update_action_event(event: &mut OvsEvent, action: OvsAction) -> Result<()> {
 pub(super) fn unmarshall_output(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
     let raw = parse_raw_section::<exec_output>(raw_section)?;
 
-    update_action_event(event, OvsAction::Output(OvsActionOutput { port: raw.port }))
+    update_action_event(
+        event,
+        OvsAction::Output(OvsActionOutput {
+            port: raw.port,
+            dp_name: raw_to_string_opt!(&raw.dp_name)?,
+            ..Default::default()
+        }),
+    )
 }
 
 pub(super) fn unmarshall_recirc(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
@@ -168,6 +359,544 @@ pub(super) fn unmarshall_recirc(raw_section: &BpfRawSection, event: &mut OvsEven
     update_action_event(event, OvsAction::Recirc(OvsActionRecirc { id: raw.id }))
 }
 
+pub(super) fn unmarshall_clone(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_clone>(raw_section)?;
+    update_action_event(
+        event,
+        OvsAction::Clone(OvsActionClone {
+            cloned: Some(raw.cloned != 0),
+        }),
+    )
+}
+
+pub(super) fn unmarshall_push_eth(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_push_eth>(raw_section)?;
+    update_action_event(
+        event,
+        OvsAction::PushEth(OvsActionPushEth {
+            src: Some(helpers::net::parse_eth_addr(&raw.eth_src)?),
+            dst: Some(helpers::net::parse_eth_addr(&raw.eth_dst)?),
+        }),
+    )
+}
+
+pub(super) fn unmarshall_push_vlan(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<exec_push_vlan>(raw_section)?;
+    update_action_event(
+        event,
+        OvsAction::PushVlan(OvsActionPushVlan::from_tci(
+            u16::from_be(raw.vlan_tpid),
+            u16::from_be(raw.vlan_tci),
+        )),
+    )
+}
+
+pub(super) fn unmarshall_pop_vlan(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_pop_vlan>(raw_section)?;
+    let inner_ethertype = match u16::from_be(raw.inner_ethertype) {
+        0 => None,
+        etype => Some(etype),
+    };
+
+    update_action_event(
+        event,
+        OvsAction::PopVlan(OvsActionPopVlan { inner_ethertype }),
+    )
+}
+
+pub(super) fn unmarshall_pop_nsh(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_pop_nsh>(raw_section)?;
+    // Same conversion as the kernel's tun_p_to_eth_p().
+    let inner_ethertype = match raw.next_proto {
+        1 => Some(0x0800), // IPv4
+        2 => Some(0x86dd), // IPv6
+        3 => Some(0x6558), // Ethernet (transparent bridging)
+        4 => Some(0x894f), // NSH
+        5 => Some(0x8847), // MPLS
+        _ => None,
+    };
+
+    update_action_event(
+        event,
+        OvsAction::PopNsh(OvsActionPopNsh { inner_ethertype }),
+    )
+}
+
+/// Packet is L3, the MPLS label is added at its start (OVS_MPLS_L3_TUNNEL_FLAG_MASK).
+const OVS_MPLS_L3_TUNNEL_FLAG: u16 = 1 << 0;
+
+/// Fields of an MPLS label stack entry.
+#[derive(Debug, PartialEq)]
+struct MplsLse {
+    label: u32,
+    tc: u8,
+    bos: bool,
+    ttl: u8,
+}
+
+/// Decodes an MPLS label stack entry, in host byte order.
+fn parse_mpls_lse(lse: u32) -> MplsLse {
+    MplsLse {
+        label: lse >> 12,
+        tc: (lse >> 9 & 0x7) as u8,
+        bos: lse & (1 << 8) != 0,
+        ttl: (lse & 0xff) as u8,
+    }
+}
+
+pub(super) fn unmarshall_push_mpls(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<exec_push_mpls>(raw_section)?;
+    let lse = parse_mpls_lse(u32::from_be(raw.lse));
+
+    update_action_event(
+        event,
+        OvsAction::PushMpls(OvsActionPushMpls {
+            label: Some(lse.label),
+            tc: Some(lse.tc),
+            ttl: Some(lse.ttl),
+            bos: lse.bos,
+            ethertype: Some(u16::from_be(raw.ethertype)),
+        }),
+    )
+}
+
+pub(super) fn unmarshall_add_mpls(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_add_mpls>(raw_section)?;
+    update_action_event(event, OvsAction::AddMpls(parse_add_mpls(raw)))
+}
+
+/// Decodes the label stack entry and the tunnel flags of an add_mpls action.
+fn parse_add_mpls(raw: &exec_add_mpls) -> OvsActionAddMpls {
+    let lse = parse_mpls_lse(u32::from_be(raw.lse));
+    let unknown_flags = raw.tun_flags & !OVS_MPLS_L3_TUNNEL_FLAG;
+
+    OvsActionAddMpls {
+        label: Some(lse.label),
+        tc: Some(lse.tc),
+        ttl: Some(lse.ttl),
+        bos: lse.bos,
+        ethertype: Some(u16::from_be(raw.ethertype)),
+        position: Some(match raw.tun_flags & OVS_MPLS_L3_TUNNEL_FLAG {
+            0 => OvsMplsPosition::AfterL2,
+            _ => OvsMplsPosition::Start,
+        }),
+        unknown_flags: (unknown_flags != 0).then_some(unknown_flags),
+    }
+}
+
+pub(super) fn unmarshall_check_pkt_len(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<exec_check_pkt_len>(raw_section)?;
+    let len = (raw.len as usize).min(raw.attrs.len());
+
+    let mut truncated = len < raw.len as usize;
+    let (_, actions_if_greater, actions_if_less_equal) =
+        parse_check_pkt_len(&raw.attrs[..len], 0, &mut truncated);
+
+    update_action_event(
+        event,
+        OvsAction::CheckPktLen(OvsActionCheckPktLen {
+            pkt_len: raw.pkt_len,
+            greater: raw.greater != 0,
+            actions_if_greater,
+            actions_if_less_equal,
+            truncated,
+        }),
+    )
+}
+
+pub(super) fn unmarshall_userspace(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<exec_userspace>(raw_section)?;
+    let len = (raw.len as usize).min(raw.attrs.len());
+
+    let mut userspace = OvsActionUserspace {
+        truncated: len < raw.len as usize,
+        ..Default::default()
+    };
+    parse_userspace(&raw.attrs[..len], &mut userspace);
+
+    update_action_event(event, OvsAction::Userspace(userspace))
+}
+
+/// Decodes the nested attributes of a userspace action (enum ovs_userspace_attr). Unknown ones
+/// are kept raw.
+fn parse_userspace(data: &[u8], userspace: &mut OvsActionUserspace) {
+    let hex = |payload: &[u8]| {
+        payload
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>()
+    };
+    let u32_attr = |payload: &[u8]| {
+        payload
+            .get(..4)
+            .map(|v| u32::from_ne_bytes(v.try_into().unwrap()))
+    };
+
+    for (id, payload) in parse_nlattrs(data, &mut userspace.truncated) {
+        match id {
+            1 => userspace.pid = u32_attr(payload),
+            2 => userspace.userdata = Some(hex(payload)),
+            3 => userspace.egress_tun_port = u32_attr(payload),
+            4 => userspace.actions = true,
+            attr_type => userspace.unknown_attrs.push(OvsUserspaceAttr {
+                attr_type,
+                data: hex(payload),
+            }),
+        }
+    }
+}
+
+/// Size of a netlink attribute header.
+const NLA_HDRLEN: usize = 4;
+/// Mask removing the nested and byte order flags from netlink attribute types.
+const NLA_TYPE_MASK: u16 = !(1 << 15 | 1 << 14);
+/// Maximum nesting of check_pkt_len actions being decoded.
+const CHECK_PKT_LEN_MAX_DEPTH: usize = 4;
+
+/// Splits `data` into netlink attributes, returning their type and payload. Incomplete
+/// attributes, e.g. because `data` was truncated, set `truncated`.
+fn parse_nlattrs<'a>(mut data: &'a [u8], truncated: &mut bool) -> Vec<(u16, &'a [u8])> {
+    let mut attrs = Vec::new();
+
+    while !data.is_empty() {
+        if data.len() < NLA_HDRLEN {
+            *truncated = true;
+            break;
+        }
+        let len = u16::from_ne_bytes([data[0], data[1]]) as usize;
+        let nla_type = u16::from_ne_bytes([data[2], data[3]]) & NLA_TYPE_MASK;
+        if len < NLA_HDRLEN || len > data.len() {
+            *truncated = true;
+            break;
+        }
+
+        attrs.push((nla_type, &data[NLA_HDRLEN..len]));
+        // Attributes are aligned on 4 bytes.
+        data = &data[len.next_multiple_of(4).min(data.len())..];
+    }
+
+    attrs
+}
+
+/// Name of an action (enum ovs_action_attr) in datapath format, if known. Derived from
+/// `action_from_id`, which holds the known action ids.
+fn action_name(id: u16) -> Option<&'static str> {
+    match action_from_id(u8::try_from(id).ok()?)? {
+        OvsAction::Unknown(_) => None,
+        action => Some(action.name()),
+    }
+}
+
+/// Formats a list of actions in datapath format. Only the arguments of output, recirc and
+/// check_pkt_len actions are decoded, the latter recursively.
+fn format_actions(data: &[u8], depth: usize, truncated: &mut bool) -> Vec<String> {
+    parse_nlattrs(data, truncated)
+        .into_iter()
+        .map(|(id, payload)| match (id, payload.get(..4)) {
+            (1, Some(port)) => u32::from_ne_bytes(port.try_into().unwrap()).to_string(),
+            (7, Some(recirc)) => {
+                format!(
+                    "recirc({:#x})",
+                    u32::from_ne_bytes(recirc.try_into().unwrap())
+                )
+            }
+            (21, _) if depth < CHECK_PKT_LEN_MAX_DEPTH => {
+                let (pkt_len, gt, le) = parse_check_pkt_len(payload, depth + 1, truncated);
+                let list = |actions: Vec<String>| match actions.is_empty() {
+                    true => "drop".to_string(),
+                    false => actions.join(","),
+                };
+                format!(
+                    "check_pkt_len(size={},gt({}),le({}))",
+                    pkt_len.map(|l| l.to_string()).unwrap_or("?".to_string()),
+                    list(gt),
+                    list(le)
+                )
+            }
+            (21, _) => {
+                *truncated = true;
+                "check_pkt_len(...)".to_string()
+            }
+            (id, _) => match action_name(id) {
+                Some(name) => name.to_string(),
+                None => format!("unknown({id})"),
+            },
+        })
+        .collect()
+}
+
+/// Parses the nested attributes of a check_pkt_len action, returning its threshold and its
+/// "greater" and "less or equal" action lists.
+fn parse_check_pkt_len(
+    data: &[u8],
+    depth: usize,
+    truncated: &mut bool,
+) -> (Option<u16>, Vec<String>, Vec<String>) {
+    let (mut pkt_len, mut gt, mut le) = (None, Vec::new(), Vec::new());
+
+    for (id, payload) in parse_nlattrs(data, truncated) {
+        match id {
+            // OVS_CHECK_PKT_LEN_ATTR_PKT_LEN (userspace format) or the private
+            // OVS_CHECK_PKT_LEN_ATTR_ARG; both start with the threshold.
+            1 | 4 => {
+                pkt_len = payload
+                    .get(..2)
+                    .map(|l| u16::from_ne_bytes(l.try_into().unwrap()))
+            }
+            2 => gt = format_actions(payload, depth, truncated),
+            3 => le = format_actions(payload, depth, truncated),
+            _ => (),
+        }
+    }
+
+    (pkt_len, gt, le)
+}
+
+pub(super) fn unmarshall_dec_ttl(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_dec_ttl>(raw_section)?;
+    update_action_event(
+        event,
+        OvsAction::DecTtl(OvsActionDecTtl {
+            has_action: Some(raw.has_action != 0),
+        }),
+    )
+}
+
+pub(super) fn unmarshall_sample(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_sample>(raw_section)?;
+    let len = (raw.len as usize).min(raw.attrs.len());
+
+    let mut truncated = len < raw.len as usize;
+    let mut sample = parse_sample(&raw.attrs[..len], &mut truncated);
+    if truncated {
+        sample.nested_actions = None;
+    }
+
+    update_action_event(event, OvsAction::Sample(sample))
+}
+
+/// Parses the nested attributes of a sample action, returning its probability and the number
+/// of sampled actions.
+fn parse_sample(data: &[u8], truncated: &mut bool) -> OvsActionSample {
+    // The probability is a fraction of UINT32_MAX.
+    let probability = |payload: &[u8]| {
+        payload.get(..4).map(|p| {
+            (u32::from_ne_bytes(p.try_into().unwrap()) as f64 * 100.0 / u32::MAX as f64) as f32
+        })
+    };
+    let mut sample = OvsActionSample::default();
+
+    match parse_nlattrs(data, truncated).split_first() {
+        // The private OVS_SAMPLE_ATTR_ARG (struct sample_arg, the probability following the
+        // exec flag), followed by the actions.
+        Some(((4, arg), actions)) => {
+            sample.probability = arg.get(4..).and_then(probability);
+            sample.nested_actions = Some(actions.len() as u32);
+        }
+        // Userspace format.
+        Some(_) => {
+            for (id, payload) in parse_nlattrs(data, truncated) {
+                match id {
+                    1 => sample.probability = probability(payload),
+                    2 => {
+                        sample.nested_actions = Some(parse_nlattrs(payload, truncated).len() as u32)
+                    }
+                    _ => (),
+                }
+            }
+        }
+        None => (),
+    }
+
+    sample
+}
+
+pub(super) fn unmarshall_hash(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_hash>(raw_section)?;
+    update_action_event(
+        event,
+        OvsAction::Hash(OvsActionHash {
+            hash_alg: raw.hash_alg,
+            hash_basis: Some(raw.hash_basis),
+        }),
+    )
+}
+
+pub(super) fn unmarshall_trunc(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_trunc>(raw_section)?;
+    update_action_event(
+        event,
+        OvsAction::Trunc(OvsActionTrunc {
+            max_len: Some(raw.max_len),
+            ..Default::default()
+        }),
+    )
+}
+
+pub(super) fn unmarshall_meter(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_meter>(raw_section)?;
+    // The kernel datapath meters only drop packets and do not expose a color.
+    update_action_event(
+        event,
+        OvsAction::Meter(OvsActionMeter {
+            id: raw.id,
+            color: None,
+        }),
+    )
+}
+
+pub(super) fn unmarshall_set(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
+    let raw = parse_raw_section::<exec_set>(raw_section)?;
+    let len = (raw.len as usize).min(raw.data.len());
+    let set = unmarshall_set_key(raw.key_type, &raw.data[..len], raw.masked != 0);
+
+    update_action_event(
+        event,
+        match raw.masked {
+            0 => OvsAction::Set(set),
+            _ => OvsAction::SetMasked(set),
+        },
+    )
+}
+
+/// Name of a flow key attribute (enum ovs_key_attr), as used by OVS.
+fn key_attr_str(key_type: u16) -> String {
+    match key_type {
+        0 => "unspec",
+        1 => "encap",
+        2 => "skb_priority",
+        3 => "in_port",
+        4 => "eth",
+        5 => "vlan",
+        6 => "eth_type",
+        OVS_KEY_ATTR_IPV4 => "ipv4",
+        OVS_KEY_ATTR_IPV6 => "ipv6",
+        9 => "tcp",
+        10 => "udp",
+        11 => "icmp",
+        12 => "icmpv6",
+        13 => "arp",
+        14 => "nd",
+        15 => "skb_mark",
+        OVS_KEY_ATTR_TUNNEL => "tunnel",
+        17 => "sctp",
+        18 => "tcp_flags",
+        19 => "dp_hash",
+        20 => "recirc_id",
+        21 => "mpls",
+        22 => "ct_state",
+        23 => "ct_zone",
+        24 => "ct_mark",
+        25 => "ct_label",
+        26 => "ct_tuple4",
+        27 => "ct_tuple6",
+        28 => "nsh",
+        29 => "packet_type",
+        30 => "nd_ext",
+        31 => "tunnel_info",
+        32 => "ipv6_exthdrs",
+        x => return format!("unknown({x})"),
+    }
+    .to_string()
+}
+
+/// Gets the `N` bytes long field at offset `off` of a set action key. If the
+/// action is masked, the field is masked and `None` is returned if the action
+/// does not modify it.
+fn set_key_field<const N: usize>(value: &[u8], mask: Option<&[u8]>, off: usize) -> Option<[u8; N]> {
+    let mut field: [u8; N] = value.get(off..off + N)?.try_into().ok()?;
+    if let Some(mask) = mask {
+        let mask = mask.get(off..off + N)?;
+        if mask.iter().all(|m| *m == 0) {
+            return None;
+        }
+        field.iter_mut().zip(mask).for_each(|(v, m)| *v &= m);
+    }
+    Some(field)
+}
+
+/// Decodes the key attribute of a set action. Masked keys hold the value
+/// followed by the mask, both of the same size. Multi-byte fields are in
+/// network order.
+fn unmarshall_set_key(key_type: u16, data: &[u8], masked: bool) -> OvsActionSet {
+    let (value, mask) = match masked {
+        true => {
+            let (value, mask) = data.split_at(data.len() / 2);
+            (value, Some(mask))
+        }
+        false => (data, None),
+    };
+
+    let mut set = OvsActionSet {
+        key_type,
+        field: Some(key_attr_str(key_type)),
+        ..Default::default()
+    };
+
+    match key_type {
+        // struct ovs_key_ipv4
+        OVS_KEY_ATTR_IPV4 => {
+            set.ipv4 = Some(OvsSetIpv4 {
+                src: set_key_field::<4>(value, mask, 0).map(|a| Ipv4Addr::from(a).to_string()),
+                dst: set_key_field::<4>(value, mask, 4).map(|a| Ipv4Addr::from(a).to_string()),
+                proto: set_key_field::<1>(value, mask, 8).map(|v| v[0]),
+                tos: set_key_field::<1>(value, mask, 9).map(|v| v[0]),
+                ttl: set_key_field::<1>(value, mask, 10).map(|v| v[0]),
+            })
+        }
+        // struct ovs_key_ipv6
+        OVS_KEY_ATTR_IPV6 => {
+            set.ipv6 = Some(OvsSetIpv6 {
+                src: set_key_field::<16>(value, mask, 0).map(|a| Ipv6Addr::from(a).to_string()),
+                dst: set_key_field::<16>(value, mask, 16).map(|a| Ipv6Addr::from(a).to_string()),
+                label: set_key_field::<4>(value, mask, 32).map(|v| u32::from_be_bytes(v) & 0xfffff),
+                tclass: set_key_field::<1>(value, mask, 37).map(|v| v[0]),
+                hlimit: set_key_field::<1>(value, mask, 38).map(|v| v[0]),
+            })
+        }
+        _ => (),
+    }
+
+    set
+}
+
+/// Converts a conntrack info (enum ip_conntrack_info) into the packet direction in the
+/// connection and its conntrack state. Untracked packets have no direction.
+fn unmarshall_ctinfo(ctinfo: u8) -> (Option<String>, Option<String>) {
+    let (dir, state) = match ctinfo {
+        0 => ("orig", "est"),
+        1 => ("orig", "rel"),
+        2 => ("orig", "new"),
+        3 => ("reply", "est"),
+        4 => ("reply", "rel"),
+        _ => return (None, None),
+    };
+    (Some(dir.to_string()), Some(state.to_string()))
+}
+
+/// Formats conntrack labels and their mask, both in network byte order. The mask is omitted if
+/// all the labels are set.
+fn unmarshall_ct_labels(labels: &[u8; 16], mask: &[u8; 16]) -> (String, Option<String>) {
+    let labels = u128::from_be_bytes(*labels);
+    let mask = u128::from_be_bytes(*mask);
+    (
+        format!("{:#x}", labels & mask),
+        (mask != u128::MAX).then(|| format!("{mask:#x}")),
+    )
+}
+
 pub(super) fn unmarshall_ct(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
     let raw = parse_raw_section::<exec_ct>(raw_section)?;
     let nat = if raw.flags & R_OVS_CT_NAT != 0 {
@@ -219,11 +948,30 @@ pub(super) fn unmarshall_ct(raw_section: &BpfRawSection, event: &mut OvsEvent) -
         None
     };
 
-    let ct = OvsActionCt {
+    let (ct_direction, ct_state) = if raw.flags & R_OVS_CT_CTINFO != 0 {
+        unmarshall_ctinfo(raw.ctinfo)
+    } else {
+        (None, None)
+    };
+
+    let mut ct = OvsActionCt {
         flags: raw.flags,
         zone_id: raw.zone_id,
         nat,
+        ct_direction,
+        ct_state,
+        eventmask: (raw.flags & R_OVS_CT_EVENTMASK != 0).then_some(raw.eventmask),
+        ..Default::default()
     };
+    if raw.flags & R_OVS_CT_LABELS != 0 {
+        let (label, mask) = unmarshall_ct_labels(&raw.labels, &raw.labels_mask);
+        ct.label = Some(label);
+        ct.label_mask = mask;
+    }
+    if raw.flags & R_OVS_CT_MARK != 0 {
+        ct.mark = Some(raw.mark);
+        ct.mark_mask = (raw.mark_mask != u32::MAX).then_some(raw.mark_mask);
+    }
     update_action_event(event, OvsAction::Ct(ct))
 }
 
@@ -251,16 +999,281 @@ pub(super) fn unmarshall_operation(raw_section: &BpfRawSection) -> Result<OvsEve
             queue_id: raw.queue_id,
             batch_idx: raw.batch_idx,
             op_type: raw.type_,
+            error: match raw.error {
+                0 => None,
+                e => Some(e),
+            },
+            flow: None,
         }),
     })
 }
 
+pub(super) fn unmarshall_operation_flow(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<ovs_operation_flow>(raw_section)?;
+    let key_len = (raw.key_len as usize).min(raw.key.len());
+    let mask_len = (raw.mask_len as usize).min(raw.mask.len());
+
+    let mut flow = OvsFlowMatch {
+        masked: raw.mask_len != 0,
+        truncated: key_len < raw.key_len as usize || mask_len < raw.mask_len as usize,
+        ..Default::default()
+    };
+    parse_flow_match(
+        &raw.key[..key_len],
+        flow.masked.then_some(&raw.mask[..mask_len]),
+        &mut flow,
+    );
+
+    match &mut event.event {
+        OvsEventType::Operation(ref mut op) => op.flow = Some(flow),
+        other => {
+            bail!(
+                "Conflicting OVS event types. Received {} data type but event is already {:#?}",
+                OvsDataType::OperationFlow,
+                other
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Representation of a flow key field.
+#[derive(Clone, Copy)]
+enum KeyFieldFmt {
+    /// Decimal integer.
+    Dec,
+    /// Hexadecimal integer.
+    Hex,
+    Ipv4,
+    Ipv6,
+    Mac,
+}
+
+/// Field of a flow key attribute.
+struct KeyField {
+    name: &'static str,
+    off: usize,
+    len: usize,
+    fmt: KeyFieldFmt,
+    /// Integers are in network order, otherwise in host order.
+    be: bool,
+}
+
+const fn key_field(
+    name: &'static str,
+    off: usize,
+    len: usize,
+    fmt: KeyFieldFmt,
+    be: bool,
+) -> KeyField {
+    KeyField {
+        name,
+        off,
+        len,
+        fmt,
+        be,
+    }
+}
+
+/// Fields of the flow key attributes (enum ovs_key_attr) known by Retis.
+fn key_fields(key_type: u16) -> Option<&'static [KeyField]> {
+    use KeyFieldFmt::*;
+
+    // Function calls are not promoted to constants, use a const item for the
+    // field lists to be static.
+    macro_rules! fields {
+        ($($field:expr),+ $(,)?) => {{
+            const FIELDS: &[KeyField] = &[$($field),+];
+            FIELDS
+        }};
+    }
+
+    Some(match key_type {
+        2 => fields![key_field("skb_priority", 0, 4, Hex, false)],
+        3 => fields![key_field("in_port", 0, 4, Dec, false)],
+        // struct ovs_key_ethernet
+        4 => fields![
+            key_field("eth_src", 0, 6, Mac, true),
+            key_field("eth_dst", 6, 6, Mac, true),
+        ],
+        5 => fields![key_field("vlan_tci", 0, 2, Hex, true)],
+        6 => fields![key_field("eth_type", 0, 2, Hex, true)],
+        // struct ovs_key_ipv4
+        OVS_KEY_ATTR_IPV4 => fields![
+            key_field("nw_src", 0, 4, Ipv4, true),
+            key_field("nw_dst", 4, 4, Ipv4, true),
+            key_field("nw_proto", 8, 1, Dec, true),
+            key_field("nw_tos", 9, 1, Hex, true),
+            key_field("nw_ttl", 10, 1, Dec, true),
+            key_field("nw_frag", 11, 1, Dec, true),
+        ],
+        // struct ovs_key_ipv6
+        OVS_KEY_ATTR_IPV6 => fields![
+            key_field("ipv6_src", 0, 16, Ipv6, true),
+            key_field("ipv6_dst", 16, 16, Ipv6, true),
+            key_field("ipv6_label", 32, 4, Hex, true),
+            key_field("nw_proto", 36, 1, Dec, true),
+            key_field("nw_tos", 37, 1, Hex, true),
+            key_field("nw_ttl", 38, 1, Dec, true),
+            key_field("nw_frag", 39, 1, Dec, true),
+        ],
+        // TCP, UDP and SCTP.
+        9 | 10 | 17 => fields![
+            key_field("tp_src", 0, 2, Dec, true),
+            key_field("tp_dst", 2, 2, Dec, true),
+        ],
+        // ICMP and ICMPv6.
+        11 | 12 => fields![
+            key_field("icmp_type", 0, 1, Dec, true),
+            key_field("icmp_code", 1, 1, Dec, true),
+        ],
+        15 => fields![key_field("skb_mark", 0, 4, Hex, false)],
+        18 => fields![key_field("tcp_flags", 0, 2, Hex, true)],
+        19 => fields![key_field("dp_hash", 0, 4, Hex, false)],
+        20 => fields![key_field("recirc_id", 0, 4, Hex, false)],
+        22 => fields![key_field("ct_state", 0, 4, Hex, false)],
+        23 => fields![key_field("ct_zone", 0, 2, Dec, false)],
+        24 => fields![key_field("ct_mark", 0, 4, Hex, false)],
+        _ => return None,
+    })
+}
+
+/// Length of an address prefix mask (at most 16 bytes), if `mask` is one.
+fn prefix_len(mask: &[u8]) -> Option<u32> {
+    let mask = mask.iter().fold(0u128, |acc, b| acc << 8 | *b as u128) << (128 - mask.len() * 8);
+    (mask.count_ones() == mask.leading_ones()).then_some(mask.leading_ones())
+}
+
+/// Formats a flow key field, or its mask.
+fn format_key_field(field: &KeyField, bytes: &[u8]) -> String {
+    let int = || {
+        let mut bytes = bytes.to_vec();
+        if !field.be && cfg!(target_endian = "little") {
+            bytes.reverse();
+        }
+        bytes.iter().fold(0u64, |acc, b| acc << 8 | *b as u64)
+    };
+
+    match field.fmt {
+        KeyFieldFmt::Dec => int().to_string(),
+        KeyFieldFmt::Hex => format!("{:#x}", int()),
+        // Unwraps are safe as the field lengths are known.
+        KeyFieldFmt::Ipv4 => Ipv4Addr::from(<[u8; 4]>::try_from(bytes).unwrap()).to_string(),
+        KeyFieldFmt::Ipv6 => Ipv6Addr::from(<[u8; 16]>::try_from(bytes).unwrap()).to_string(),
+        KeyFieldFmt::Mac => bytes
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+    }
+}
+
+/// Decodes the key and mask (enum ovs_key_attr netlink attributes) of a flow
+/// into the fields it matches. Without a mask all fields are matched exactly,
+/// otherwise fields missing from the mask or whose mask is null are wildcarded
+/// and not reported. Attributes not known by Retis are kept raw.
+fn parse_flow_match(key: &[u8], mask: Option<&[u8]>, flow: &mut OvsFlowMatch) {
+    let hex = |payload: &[u8]| {
+        payload
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>()
+    };
+
+    let masks = mask.map(|mask| parse_nlattrs(mask, &mut flow.truncated));
+    for (key_type, value) in parse_nlattrs(key, &mut flow.truncated) {
+        let mask = match &masks {
+            Some(masks) => match masks.iter().find(|(t, _)| *t == key_type) {
+                Some((_, mask)) => Some(*mask),
+                None => continue,
+            },
+            None => None,
+        };
+
+        // Nested encapsulated key (e.g. after a VLAN header).
+        if key_type == 1 {
+            parse_flow_match(value, mask, flow);
+            continue;
+        }
+
+        let fields = match key_fields(key_type) {
+            Some(fields) => fields,
+            None => {
+                let mask = mask.filter(|m| m.iter().any(|b| *b != 0xff));
+                if mask.is_some_and(|m| m.iter().all(|b| *b == 0)) {
+                    continue;
+                }
+                let mut value = value.to_vec();
+                if let Some(mask) = mask {
+                    value.iter_mut().zip(mask).for_each(|(v, m)| *v &= m);
+                }
+                flow.fields.push(OvsFlowField {
+                    name: format!("key({key_type})"),
+                    value: hex(&value),
+                    mask: mask.map(hex),
+                });
+                continue;
+            }
+        };
+
+        for field in fields {
+            let range = field.off..field.off + field.len;
+            let mut bytes = match value.get(range.clone()) {
+                Some(bytes) => bytes.to_vec(),
+                None => continue,
+            };
+
+            let mask = match mask {
+                Some(mask) => match mask.get(range) {
+                    Some(mask) => mask,
+                    None => continue,
+                },
+                None => &[],
+            };
+            if !mask.is_empty() {
+                if mask.iter().all(|b| *b == 0) {
+                    continue;
+                }
+                bytes.iter_mut().zip(mask).for_each(|(v, m)| *v &= m);
+            }
+
+            let mask = match field.fmt {
+                _ if mask.iter().all(|b| *b == 0xff) => None,
+                KeyFieldFmt::Ipv4 | KeyFieldFmt::Ipv6 => Some(
+                    prefix_len(mask)
+                        .map(|len| len.to_string())
+                        .unwrap_or_else(|| format_key_field(field, mask)),
+                ),
+                // Masks of decimal fields are easier to read in hexadecimal.
+                KeyFieldFmt::Dec => Some(format_key_field(
+                    &KeyField {
+                        fmt: KeyFieldFmt::Hex,
+                        ..*field
+                    },
+                    mask,
+                )),
+                _ => Some(format_key_field(field, mask)),
+            };
+
+            flow.fields.push(OvsFlowField {
+                name: field.name.to_string(),
+                value: format_key_field(field, &bytes),
+                mask,
+            });
+        }
+    }
+}
+
 pub(super) fn unmarshall_upcall_enqueue(raw_section: &BpfRawSection) -> Result<OvsEvent> {
     let raw = parse_raw_section::<upcall_enqueue_event>(raw_section)?;
 
     Ok(OvsEvent {
         event: OvsEventType::UpcallEnqueue(UpcallEnqueueEvent {
             ret: raw.ret,
+            ret_str: ret_errno_str(raw.ret).map(String::from),
             cmd: raw.cmd,
             port: raw.port,
             upcall_ts: raw.upcall_ts,
@@ -278,20 +1291,56 @@ pub(super) fn unmarshall_upcall_return(raw_section: &BpfRawSection) -> Result<Ov
             upcall_ts: raw.upcall_ts,
             upcall_cpu: raw.upcall_cpu,
             ret: raw.ret,
+            ret_str: ret_errno_str(raw.ret).map(String::from),
+            latency_ns: None,
         }),
     })
 }
 
 #[event_section_factory(FactoryId::Ovs)]
 #[derive(Default)]
-pub(crate) struct OvsEventFactory {}
+pub(crate) struct OvsEventFactory {
+    /// Resolves the name of output ports, if enabled.
+    pub(super) port_names: Option<PortNames>,
+    /// Fail on action ids and operation types not known by Retis, instead of
+    /// reporting them as unknown.
+    pub(super) strict_unknown: bool,
+    /// Number of events decoded, by event type (see `OvsEventType::name`).
+    pub(super) stats: HashMap<&'static str, u64>,
+}
+
+impl OvsEventFactory {
+    /// Number of events decoded so far, by event type (see `OvsEventType::name`).
+    pub(crate) fn stats(&self) -> &HashMap<&'static str, u64> {
+        &self.stats
+    }
+
+    /// Fails if the event holds an action id or an operation type not known by
+    /// Retis.
+    fn check_unknown(event: &OvsEvent) -> Result<()> {
+        match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Unknown(a)),
+                ..
+            }) => bail!("Unknown action id ({})", a.id),
+            OvsEventType::Operation(op) if op.op_type > OVS_OP_GET => {
+                bail!("Unknown flow operation type ({})", op.op_type)
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
 impl RawEventSectionFactory for OvsEventFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>) -> Result<Box<dyn EventSection>> {
         let mut event = None; // = OvsEvent::default();
+        #[cfg(any(debug_assertions, feature = "validate_events"))]
+        let mut exec_action = None;
 
         for section in raw_sections.iter() {
-            match OvsDataType::from_u8(section.header.data_type)? {
+            let data_type = OvsDataType::from_u8(section.header.data_type)?;
+            let no_event = |parent| anyhow!("received {data_type} data without {parent}");
+            match data_type {
                 OvsDataType::Upcall => {
                     event = Some(unmarshall_upcall(section)?);
                 }
@@ -307,8 +1356,25 @@ impl RawEventSectionFactory for OvsEventFactory {
                 OvsDataType::Operation => {
                     event = Some(unmarshall_operation(section)?);
                 }
+                OvsDataType::OperationFlow => unmarshall_operation_flow(
+                    section,
+                    event.as_mut().ok_or_else(|| no_event("operation"))?,
+                )?,
                 OvsDataType::ActionExec => {
-                    event = Some(unmarshall_exec(section)?);
+                    let exec = unmarshall_exec(section)?;
+                    #[cfg(any(debug_assertions, feature = "validate_events"))]
+                    {
+                        exec_action = super::validate::action_kind(&exec);
+                    }
+                    event = Some(exec);
+                }
+                OvsDataType::ActionError => {
+                    let error = unmarshall_action_error(section)?;
+                    #[cfg(any(debug_assertions, feature = "validate_events"))]
+                    {
+                        exec_action = super::validate::action_kind(&error);
+                    }
+                    event = Some(error);
                 }
                 OvsDataType::ActionExecTrack => unmarshall_exec_track(
                     section,
@@ -316,33 +1382,1285 @@ impl RawEventSectionFactory for OvsEventFactory {
                         .as_mut()
                         .ok_or_else(|| anyhow!("received action track without action"))?,
                 )?,
-                OvsDataType::OutputAction => unmarshall_output(
+                OvsDataType::OutputAction => {
+                    unmarshall_output(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::RecircAction => {
+                    unmarshall_recirc(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::ConntrackAction => {
+                    unmarshall_ct(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::PopVlanAction => {
+                    unmarshall_pop_vlan(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::PopNshAction => {
+                    unmarshall_pop_nsh(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::CheckPktLenAction => unmarshall_check_pkt_len(
                     section,
-                    event
-                        .as_mut()
-                        .ok_or_else(|| anyhow!("received action data without action"))?,
+                    event.as_mut().ok_or_else(|| no_event("action"))?,
                 )?,
-                OvsDataType::RecircAction => unmarshall_recirc(
+                OvsDataType::AddMplsAction => {
+                    unmarshall_add_mpls(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::UserspaceAction => unmarshall_userspace(
                     section,
-                    event
-                        .as_mut()
-                        .ok_or_else(|| anyhow!("received action data without action"))?,
+                    event.as_mut().ok_or_else(|| no_event("action"))?,
                 )?,
-                OvsDataType::ConntrackAction => unmarshall_ct(
+                OvsDataType::HashAction => {
+                    unmarshall_hash(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::MeterAction => {
+                    unmarshall_meter(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::SetAction => {
+                    unmarshall_set(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::TruncAction => {
+                    unmarshall_trunc(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::PushVlanAction => unmarshall_push_vlan(
                     section,
-                    event
-                        .as_mut()
-                        .ok_or_else(|| anyhow!("received action data without action"))?,
+                    event.as_mut().ok_or_else(|| no_event("action"))?,
+                )?,
+                OvsDataType::PushMplsAction => unmarshall_push_mpls(
+                    section,
+                    event.as_mut().ok_or_else(|| no_event("action"))?,
                 )?,
+                OvsDataType::SampleAction => {
+                    unmarshall_sample(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::DecTtlAction => {
+                    unmarshall_dec_ttl(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::PushEthAction => {
+                    unmarshall_push_eth(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
+                OvsDataType::CloneAction => {
+                    unmarshall_clone(section, event.as_mut().ok_or_else(|| no_event("action"))?)?
+                }
             };
         }
 
-        Ok(Box::new(
-            event.ok_or_else(|| anyhow!("Incomplete OVS event"))?,
+        let mut event = event.ok_or_else(|| anyhow!("Incomplete OVS event"))?;
+        if self.strict_unknown {
+            Self::check_unknown(&event)?;
+        }
+
+        if let (
+            Some(port_names),
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Output(output)),
+                ..
+            }),
+        ) = (self.port_names.as_mut(), &mut event.event)
+        {
+            output.out_port_name = output
+                .dp_name
+                .as_deref()
+                .and_then(|dp| port_names.get(dp, output.port));
+        }
+
+        #[cfg(any(debug_assertions, feature = "validate_events"))]
+        super::validate::validate(&event, exec_action);
+
+        *self.stats.entry(event.event.name()).or_default() += 1;
+        Ok(Box::new(event))
+    }
+
+    fn stats_summary(&self) -> Option<String> {
+        if self.stats.is_empty() {
+            return None;
+        }
+
+        let mut stats = self.stats().iter().collect::<Vec<_>>();
+        stats.sort();
+        Some(format!(
+            "ovs: {}",
+            stats
+                .iter()
+                .map(|(name, count)| format!("{name} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
         ))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::events::BpfRawSectionHeader, helpers::testing::raw_bytes};
+
+    #[test]
+    fn data_types() {
+        let data_types = (0..=u8::MAX)
+            .filter_map(|val| OvsDataType::from_u8(val).ok())
+            .collect::<Vec<_>>();
+        // Data types are contiguous, from 0 to the last one.
+        assert_eq!(data_types.len(), OvsDataType::CloneAction as usize + 1);
+
+        for (val, data_type) in data_types.iter().enumerate() {
+            assert_eq!(data_type.to_u8(), val as u8);
+            assert_eq!(OvsDataType::from_u8(data_type.to_u8()).unwrap(), *data_type);
+        }
+
+        assert_eq!(OvsDataType::UpcallEnqueue.to_string(), "upcall_enqueue");
+        assert_eq!(OvsDataType::PushMplsAction.to_string(), "push_mpls_action");
+    }
+
+    #[test]
+    fn action_ids() {
+        // The private set_to_masked action comes after the uapi ones, its id
+        // depends on the kernel used by the tests.
+        let set_to_masked = set_to_masked_id().unwrap();
+        assert!(set_to_masked > 23);
+        assert_eq!(action_from_id(set_to_masked).unwrap().name(), "set_masked");
+        assert_eq!(action_name(set_to_masked as u16), Some("set_masked"));
+
+        assert_eq!(action_from_id(23).unwrap().name(), "dec_ttl");
+        for (id, name) in [(24, "drop"), (25, "psample")] {
+            if id != set_to_masked {
+                assert_eq!(action_from_id(id).unwrap().name(), name);
+                assert_eq!(action_name(id as u16), Some(name));
+            }
+        }
+        assert_eq!(action_from_id(24).unwrap().name(), "drop");
+    }
+
+    #[test]
+    fn unknown_ids() -> Result<()> {
+        let raw = exec_event {
+            recirc_id: 1,
+            action: 99,
+        };
+        let data = raw_bytes(&raw);
+        let event = unmarshall_exec(&BpfRawSection {
+            data: &data,
+            ..Default::default()
+        })?;
+        assert_eq!(
+            event.event,
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Unknown(OvsActionUnknown { id: 99 })),
+                recirc_id: 1,
+                has_detail: Some(false),
+                ..Default::default()
+            })
+        );
+        assert_eq!(event.to_json()["event_type"], "action_execute");
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "[recirc_id 0x1] exec unknown(99)"
+        );
+
+        let raw = ovs_operation_event {
+            type_: 99,
+            ..Default::default()
+        };
+        let data = raw_bytes(&raw);
+        let event = unmarshall_operation(&BpfRawSection {
+            data: &data,
+            ..Default::default()
+        })?;
+        assert_eq!(event.to_json()["op_type"], "unknown(99)");
+        Ok(())
+    }
+
+    #[test]
+    fn factory_stats() -> Result<()> {
+        fn section(data_type: OvsDataType, data: &[u8]) -> BpfRawSection<'_> {
+            BpfRawSection {
+                header: BpfRawSectionHeader {
+                    data_type: data_type.to_u8(),
+                    ..Default::default()
+                },
+                data,
+            }
+        }
+        let upcall = raw_bytes(&upcall_event {
+            port: 1,
+            cpu: 0,
+            cmd: 1,
+        });
+        let exec = raw_bytes(&exec_event {
+            recirc_id: 0,
+            action: 1,
+        });
+        let output = raw_bytes(&exec_output {
+            port: 2,
+            ..Default::default()
+        });
+        let ret = raw_bytes(&upcall_ret_event::default());
+
+        let mut factory = OvsEventFactory::default();
+        assert!(factory.stats().is_empty());
+
+        let events = [
+            vec![section(OvsDataType::Upcall, &upcall)],
+            vec![
+                section(OvsDataType::ActionExec, &exec),
+                section(OvsDataType::OutputAction, &output),
+            ],
+            vec![section(OvsDataType::ActionExec, &exec)],
+            vec![section(OvsDataType::UpcallReturn, &ret)],
+        ];
+        for sections in events {
+            factory.create(sections)?;
+        }
+        // Events failing to be decoded are not accounted for.
+        assert!(factory
+            .create(vec![section(OvsDataType::OutputAction, &output)])
+            .is_err());
+
+        assert_eq!(
+            factory.stats(),
+            &HashMap::from([("upcall", 1), ("action_execute", 2), ("upcall_return", 1)])
+        );
+        assert_eq!(
+            factory.stats_summary().as_deref(),
+            Some("ovs: action_execute 2, upcall 1, upcall_return 1")
+        );
+        assert!(OvsEventFactory::default().stats_summary().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn strict_unknown() -> Result<()> {
+        let exec = |action| {
+            raw_bytes(&exec_event {
+                recirc_id: 0,
+                action,
+            })
+        };
+        let create = |factory: &mut OvsEventFactory, data: &[u8]| {
+            factory.create(vec![BpfRawSection {
+                header: BpfRawSectionHeader {
+                    data_type: OvsDataType::ActionExec.to_u8(),
+                    ..Default::default()
+                },
+                data,
+            }])
+        };
+
+        let mut lenient = OvsEventFactory::default();
+        let event = create(&mut lenient, &exec(99))?;
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec unknown(99)"
+        );
+
+        let mut strict = OvsEventFactory {
+            strict_unknown: true,
+            ..Default::default()
+        };
+        assert!(create(&mut strict, &exec(1)).is_ok());
+        let err = create(&mut strict, &exec(99)).err().unwrap();
+        assert_eq!(err.to_string(), "Unknown action id (99)");
+        Ok(())
+    }
+
+    #[test]
+    fn set_ipv6() {
+        let src: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let dst: Ipv6Addr = "fe80::1:2".parse().unwrap();
+
+        let mut value = [0u8; 40];
+        value[0..16].copy_from_slice(&src.octets());
+        value[16..32].copy_from_slice(&dst.octets());
+        value[32..36].copy_from_slice(&0x12345u32.to_be_bytes());
+        value[36] = 17;
+        value[37] = 0x2e;
+        value[38] = 64;
+
+        let set = unmarshall_set_key(OVS_KEY_ATTR_IPV6, &value, false);
+        assert!(set.ipv4.is_none());
+        assert_eq!(
+            set.ipv6,
+            Some(OvsSetIpv6 {
+                src: Some("2001:db8::1".to_string()),
+                dst: Some("fe80::1:2".to_string()),
+                label: Some(0x12345),
+                tclass: Some(0x2e),
+                hlimit: Some(64),
+            })
+        );
+
+        // Only set the destination address and the hop limit.
+        let mut mask = [0u8; 40];
+        mask[16..32].fill(0xff);
+        mask[38] = 0xff;
+        let set = unmarshall_set_key(OVS_KEY_ATTR_IPV6, &[value, mask].concat(), true);
+        assert_eq!(
+            set.ipv6,
+            Some(OvsSetIpv6 {
+                dst: Some("fe80::1:2".to_string()),
+                hlimit: Some(64),
+                ..Default::default()
+            })
+        );
+    }
+
+    fn nlattr(nla_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut attr = Vec::new();
+        attr.extend_from_slice(&((NLA_HDRLEN + payload.len()) as u16).to_ne_bytes());
+        attr.extend_from_slice(&nla_type.to_ne_bytes());
+        attr.extend_from_slice(payload);
+        attr.resize(attr.len().next_multiple_of(4), 0);
+        attr
+    }
+
+    fn check_pkt_len(pkt_len: u16, gt: &[u8], le: &[u8]) -> Vec<u8> {
+        let mut arg = pkt_len.to_ne_bytes().to_vec();
+        arg.extend_from_slice(&[0, 1]);
+        [nlattr(4, &arg), nlattr(3, le), nlattr(2, gt)].concat()
+    }
+
+    #[test]
+    fn check_pkt_len_actions() {
+        // check_pkt_len(size=1500,gt(check_pkt_len(size=9000,gt(drop),le(userspace))),
+        //               le(2,recirc(0x5)))
+        let inner = check_pkt_len(9000, &[], &nlattr(2, &[]));
+        let le = [
+            nlattr(1, &2u32.to_ne_bytes()),
+            nlattr(7, &5u32.to_ne_bytes()),
+        ]
+        .concat();
+        let attrs = check_pkt_len(1500, &nlattr(21, &inner), &le);
+
+        let mut truncated = false;
+        let (pkt_len, gt, le) = parse_check_pkt_len(&attrs, 0, &mut truncated);
+        assert!(!truncated);
+        assert_eq!(pkt_len, Some(1500));
+        assert_eq!(gt, vec!["check_pkt_len(size=9000,gt(drop),le(userspace))"]);
+        assert_eq!(le, vec!["2", "recirc(0x5)"]);
+
+        // Attributes cut in the middle are reported.
+        let mut truncated = false;
+        let (_, gt, le) = parse_check_pkt_len(&attrs[..attrs.len() - 2], 0, &mut truncated);
+        assert!(truncated);
+        assert!(gt.is_empty());
+        assert_eq!(le, vec!["2", "recirc(0x5)"]);
+
+        // Recursion is bounded.
+        let mut attrs = check_pkt_len(1, &[], &[]);
+        for _ in 0..=CHECK_PKT_LEN_MAX_DEPTH {
+            attrs = check_pkt_len(1, &nlattr(21, &attrs), &[]);
+        }
+        let mut truncated = false;
+        parse_check_pkt_len(&attrs, 0, &mut truncated);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn check_pkt_len_raw() -> Result<()> {
+        // check_pkt_len(size=1500,gt(2),le(3)), the packet being shorter.
+        let attrs = check_pkt_len(
+            1500,
+            &nlattr(1, &2u32.to_ne_bytes()),
+            &nlattr(1, &3u32.to_ne_bytes()),
+        );
+        let mut raw = exec_check_pkt_len {
+            pkt_len: 1500,
+            len: attrs.len() as u16,
+            greater: 0,
+            ..Default::default()
+        };
+        raw.attrs[..attrs.len()].copy_from_slice(&attrs);
+        let data = raw_bytes(&raw);
+
+        let mut event = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::CheckPktLen(OvsActionCheckPktLen::default())),
+                ..Default::default()
+            }),
+        };
+        unmarshall_check_pkt_len(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        let cpl = match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::CheckPktLen(cpl)),
+                ..
+            }) => cpl,
+            _ => panic!("not a check_pkt_len action event"),
+        };
+        assert_eq!(cpl.pkt_len, 1500);
+        assert!(!cpl.greater);
+        assert_eq!(cpl.actions_if_greater, vec!["2"]);
+        assert_eq!(cpl.actions_if_less_equal, vec!["3"]);
+        assert!(!cpl.truncated);
+        Ok(())
+    }
+
+    #[test]
+    fn sample_raw() -> Result<()> {
+        // struct sample_arg: the exec flag, padding and the probability.
+        let mut arg = vec![1, 0, 0, 0];
+        arg.extend_from_slice(&(u32::MAX / 2).to_ne_bytes());
+        // sample(sample=50%,actions(push_vlan(...),2)). The push_vlan id is
+        // the same as the one of the private sample argument.
+        let attrs = [
+            nlattr(4, &arg),
+            nlattr(4, &[0x81, 0x00, 0x00, 0x64]),
+            nlattr(1, &2u32.to_ne_bytes()),
+        ]
+        .concat();
+
+        let sample = |len: usize| -> Result<OvsActionSample> {
+            let mut raw = exec_sample {
+                len: len as u16,
+                ..Default::default()
+            };
+            raw.attrs[..attrs.len()].copy_from_slice(&attrs);
+            let data = raw_bytes(&raw);
+
+            let mut event = OvsEvent {
+                event: OvsEventType::Action(ActionEvent {
+                    action: Some(OvsAction::Sample(OvsActionSample::default())),
+                    ..Default::default()
+                }),
+            };
+            unmarshall_sample(
+                &BpfRawSection {
+                    data: &data,
+                    ..Default::default()
+                },
+                &mut event,
+            )?;
+            match event.event {
+                OvsEventType::Action(ActionEvent {
+                    action: Some(OvsAction::Sample(sample)),
+                    ..
+                }) => Ok(sample),
+                _ => panic!("not a sample action event"),
+            }
+        };
+
+        let s = sample(attrs.len())?;
+        assert!((s.probability.unwrap() - 50.0).abs() < 0.001);
+        assert_eq!(s.nested_actions, Some(2));
+
+        // Actions not fully retrieved are not counted.
+        let s = sample(attrs.len() + 32)?;
+        assert!((s.probability.unwrap() - 50.0).abs() < 0.001);
+        assert_eq!(s.nested_actions, None);
+
+        // Userspace format.
+        let attrs = [
+            nlattr(1, &u32::MAX.to_ne_bytes()),
+            nlattr(2, &nlattr(1, &2u32.to_ne_bytes())),
+        ]
+        .concat();
+        let s = parse_sample(&attrs, &mut false);
+        assert_eq!(s.probability, Some(100.0));
+        assert_eq!(s.nested_actions, Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn trunc_meter_raw() -> Result<()> {
+        // Unmarshals an action execution of the given action id, followed by its data section.
+        let action_event = |action, data_type: OvsDataType, data: &[u8]| -> Result<OvsEvent> {
+            let exec = raw_bytes(&exec_event {
+                recirc_id: 0,
+                action,
+            });
+            let mut event = unmarshall_exec(&BpfRawSection {
+                data: &exec,
+                ..Default::default()
+            })?;
+            let section = BpfRawSection {
+                data,
+                ..Default::default()
+            };
+            match data_type {
+                OvsDataType::TruncAction => unmarshall_trunc(&section, &mut event)?,
+                OvsDataType::MeterAction => unmarshall_meter(&section, &mut event)?,
+                _ => panic!("unexpected data type"),
+            }
+            Ok(event)
+        };
+
+        let trunc = action_event(
+            13,
+            OvsDataType::TruncAction,
+            &raw_bytes(&exec_trunc { max_len: 1500 }),
+        )?;
+        assert_eq!(
+            trunc.event,
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Trunc(OvsActionTrunc {
+                    max_len: Some(1500),
+                    sampled: false,
+                })),
+                has_detail: Some(true),
+                ..Default::default()
+            })
+        );
+
+        let meter = action_event(
+            19,
+            OvsDataType::MeterAction,
+            &raw_bytes(&exec_meter { id: 3 }),
+        )?;
+        assert_eq!(
+            meter.event,
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Meter(OvsActionMeter { id: 3, color: None })),
+                has_detail: Some(true),
+                ..Default::default()
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn upcall_raw() -> Result<()> {
+        // Must match struct upcall_event in kernel_upcall_tp.bpf.c.
+        assert_eq!(std::mem::size_of::<upcall_event>(), 12);
+
+        let data = raw_bytes(&upcall_event {
+            port: 3936214682,
+            cpu: 2,
+            cmd: 1,
+        });
+        let event = unmarshall_upcall(&BpfRawSection {
+            data: &data,
+            ..Default::default()
+        })?;
+        assert_eq!(
+            event.event,
+            OvsEventType::Upcall(UpcallEvent {
+                cmd: 1,
+                port: 3936214682,
+                cpu: 2,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn upcall_return_raw() -> Result<()> {
+        let upcall_return = |ret| -> Result<UpcallReturnEvent> {
+            let data = raw_bytes(&upcall_ret_event {
+                upcall_ts: 1000,
+                upcall_cpu: 1,
+                ret,
+            });
+            match unmarshall_upcall_return(&BpfRawSection {
+                data: &data,
+                ..Default::default()
+            })?
+            .event
+            {
+                OvsEventType::UpcallReturn(ret) => Ok(ret),
+                _ => bail!("not an upcall return event"),
+            }
+        };
+
+        let ret = upcall_return(-105)?;
+        assert_eq!((ret.ret, ret.ret_str.as_deref()), (-105, Some("ENOBUFS")));
+        let ret = upcall_return(-11)?;
+        assert_eq!((ret.ret, ret.ret_str.as_deref()), (-11, Some("EAGAIN")));
+        // Not errors.
+        assert_eq!(upcall_return(0)?.ret_str, None);
+        assert_eq!(upcall_return(12)?.ret_str, None);
+        // Unknown errno.
+        assert_eq!(upcall_return(-4095)?.ret_str, None);
+
+        let data = raw_bytes(&upcall_enqueue_event {
+            ret: -12,
+            ..Default::default()
+        });
+        match unmarshall_upcall_enqueue(&BpfRawSection {
+            data: &data,
+            ..Default::default()
+        })?
+        .event
+        {
+            OvsEventType::UpcallEnqueue(enqueue) => {
+                assert_eq!(enqueue.ret_str.as_deref(), Some("ENOMEM"))
+            }
+            _ => bail!("not an upcall enqueue event"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn push_eth_raw() -> Result<()> {
+        let exec = |action| {
+            raw_bytes(&exec_event {
+                recirc_id: 0,
+                action,
+            })
+        };
+
+        let data = exec(14);
+        let mut event = unmarshall_exec(&BpfRawSection {
+            data: &data,
+            ..Default::default()
+        })?;
+        let data = raw_bytes(&exec_push_eth {
+            eth_src: [0x0a, 0x58, 0x0a, 0xf4, 0x00, 0x01],
+            eth_dst: [0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        });
+        unmarshall_push_eth(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::PushEth(push_eth)),
+                has_detail,
+                ..
+            }) => {
+                assert_eq!(push_eth.src.as_deref(), Some("0a:58:0a:f4:00:01"));
+                assert_eq!(push_eth.dst.as_deref(), Some("ff:ff:ff:ff:ff:ff"));
+                assert_eq!(*has_detail, Some(true));
+            }
+            _ => bail!("not a push_eth action event"),
+        }
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec push_eth 0a:58:0a:f4:00:01 > ff:ff:ff:ff:ff:ff"
+        );
+
+        // Actions without detail (ct_clear), and with details not reported yet (output).
+        for (action, has_detail) in [(16, Some(false)), (1, None)] {
+            let data = exec(action);
+            match unmarshall_exec(&BpfRawSection {
+                data: &data,
+                ..Default::default()
+            })?
+            .event
+            {
+                OvsEventType::Action(event) => assert_eq!(event.has_detail, has_detail),
+                _ => bail!("not an action event"),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn recirc_raw() -> Result<()> {
+        let data = raw_bytes(&exec_event {
+            recirc_id: 0,
+            action: 7,
+        });
+        let mut event = unmarshall_exec(&BpfRawSection {
+            data: &data,
+            ..Default::default()
+        })?;
+        let data = raw_bytes(&exec_recirc { id: 5 });
+        unmarshall_recirc(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Recirc(recirc)),
+                recirc_id,
+                ..
+            }) => {
+                // Current recirculation id, and the one the packet jumps to.
+                assert_eq!(*recirc_id, 0);
+                assert_eq!(recirc.id, 5);
+            }
+            _ => bail!("not a recirc action event"),
+        }
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec recirc 0x5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clone_raw() -> Result<()> {
+        let clone = |cloned| -> Result<OvsEvent> {
+            let data = raw_bytes(&exec_event {
+                recirc_id: 0,
+                action: 20,
+            });
+            let mut event = unmarshall_exec(&BpfRawSection {
+                data: &data,
+                ..Default::default()
+            })?;
+            let data = raw_bytes(&exec_clone { cloned });
+            unmarshall_clone(
+                &BpfRawSection {
+                    data: &data,
+                    ..Default::default()
+                },
+                &mut event,
+            )?;
+            Ok(event)
+        };
+
+        let event = clone(1)?;
+        match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Clone(clone)),
+                has_detail,
+                ..
+            }) => {
+                assert_eq!(clone.cloned, Some(true));
+                assert_eq!(*has_detail, Some(true));
+            }
+            _ => bail!("not a clone action event"),
+        }
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec clone (copy)"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                clone(0)?.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec clone (no copy)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dec_ttl_raw() -> Result<()> {
+        let dec_ttl = |has_action| -> Result<String> {
+            let data = raw_bytes(&exec_dec_ttl { has_action });
+            let mut event = OvsEvent {
+                event: OvsEventType::Action(ActionEvent {
+                    action: Some(OvsAction::DecTtl(OvsActionDecTtl::default())),
+                    ..Default::default()
+                }),
+            };
+            unmarshall_dec_ttl(
+                &BpfRawSection {
+                    data: &data,
+                    ..Default::default()
+                },
+                &mut event,
+            )?;
+            Ok(format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ))
+        };
+
+        assert_eq!(dec_ttl(1)?, "exec dec_ttl(le_1(actions))");
+        assert_eq!(dec_ttl(0)?, "exec dec_ttl(le_1(drop))");
+        Ok(())
+    }
+
+    #[test]
+    fn hash_raw() -> Result<()> {
+        let data = raw_bytes(&exec_hash {
+            hash_alg: OVS_HASH_ALG_L4,
+            hash_basis: 0x1234,
+        });
+        let mut event = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Hash(OvsActionHash::default())),
+                ..Default::default()
+            }),
+        };
+        unmarshall_hash(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+
+        let hash = match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Hash(hash)),
+                ..
+            }) => hash,
+            _ => panic!("not a hash action event"),
+        };
+        assert_eq!(hash.alg_str(), "l4");
+        assert_eq!(hash.hash_basis, Some(0x1234));
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec hash(l4) basis 0x1234"
+        );
+
+        let hash = OvsActionHash {
+            hash_alg: 7,
+            ..Default::default()
+        };
+        assert_eq!(hash.alg_str(), "unknown(7)");
+
+        let event = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Hash(hash)),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec hash(unknown(7))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn flow_match() {
+        let ipv4 = |src: [u8; 4], dst: [u8; 4], proto, tos, ttl, frag| {
+            [src, dst, [proto, tos, ttl, frag]].concat()
+        };
+        let key = [
+            nlattr(3, &2u32.to_ne_bytes()),
+            nlattr(6, &0x0800u16.to_be_bytes()),
+            nlattr(7, &ipv4([10, 0, 0, 1], [10, 0, 0, 2], 6, 0, 64, 0)),
+            nlattr(9, &[1234u16.to_be_bytes(), 80u16.to_be_bytes()].concat()),
+            nlattr(42, &[1, 2]),
+            nlattr(15, &1u32.to_ne_bytes()),
+        ]
+        .concat();
+        let mask = [
+            nlattr(3, &u32::MAX.to_ne_bytes()),
+            nlattr(6, &u16::MAX.to_be_bytes()),
+            nlattr(
+                7,
+                &ipv4([255, 255, 255, 0], [255, 0, 255, 0], 0xff, 0, 0, 0xff),
+            ),
+            nlattr(9, &[0u16.to_be_bytes(), 0xfff0u16.to_be_bytes()].concat()),
+            nlattr(42, &[0xff, 0]),
+        ]
+        .concat();
+
+        let fields = |flow: &OvsFlowMatch| {
+            flow.fields
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Megaflow: fields missing from the mask (skb_mark) or with a null
+        // mask are wildcarded.
+        let mut flow = OvsFlowMatch::default();
+        parse_flow_match(&key, Some(&mask), &mut flow);
+        assert_eq!(
+            fields(&flow),
+            vec![
+                "in_port=2",
+                "eth_type=0x800",
+                "nw_src=10.0.0.0/24",
+                "nw_dst=10.0.0.0/255.0.255.0",
+                "nw_proto=6",
+                "nw_frag=0",
+                "tp_dst=80/0xfff0",
+                "key(42)=0100/ff00",
+            ]
+        );
+        assert!(!flow.truncated);
+
+        // Exact match.
+        let mut flow = OvsFlowMatch::default();
+        parse_flow_match(&key[..34], None, &mut flow);
+        assert_eq!(
+            fields(&flow),
+            vec![
+                "in_port=2",
+                "eth_type=0x800",
+                "nw_src=10.0.0.1",
+                "nw_dst=10.0.0.2",
+                "nw_proto=6",
+                "nw_tos=0x0",
+                "nw_ttl=64",
+                "nw_frag=0",
+            ]
+        );
+        // The TCP attribute is incomplete.
+        assert!(flow.truncated);
+    }
+
+    #[test]
+    fn ct_raw() -> Result<()> {
+        let mut raw = exec_ct {
+            flags: R_OVS_CT_COMMIT | R_OVS_CT_IP4 | R_OVS_CT_LABELS | R_OVS_CT_MARK,
+            zone_id: 5,
+            mark: 0x2a,
+            mark_mask: 0xff,
+            ..Default::default()
+        };
+        raw.labels[15] = 0x1;
+        raw.labels_mask = [0xff; 16];
+        let data = raw_bytes(&raw);
+
+        let mut event = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Ct(OvsActionCt::default())),
+                ..Default::default()
+            }),
+        };
+        unmarshall_ct(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        let ct = match &event.event {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Ct(ct)),
+                ..
+            }) => ct,
+            _ => panic!("not a ct action event"),
+        };
+        assert_eq!(ct.zone_id, 5);
+        assert!(ct.is_commit());
+        assert!(ct.nat.is_none());
+        assert_eq!(ct.label.as_deref(), Some("0x1"));
+        assert!(ct.label_mask.is_none());
+        assert_eq!(ct.mark, Some(0x2a));
+        assert_eq!(ct.mark_mask, Some(0xff));
+
+        // Mark with a full mask.
+        raw.mark_mask = u32::MAX;
+        raw.flags &= !R_OVS_CT_LABELS;
+        let data = raw_bytes(&raw);
+        unmarshall_ct(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec ct zone 5 commit mark=0x2a"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ct_labels() {
+        // Labels are in network byte order: the last byte holds the lowest bits.
+        let mut labels = [0; 16];
+        labels[0] = 0x80;
+        labels[15] = 0x2a;
+        let mut mask = [0; 16];
+        mask[15] = 0xff;
+        assert_eq!(
+            unmarshall_ct_labels(&labels, &mask),
+            (String::from("0x2a"), Some(String::from("0xff")))
+        );
+
+        mask = [0xff; 16];
+        assert_eq!(
+            unmarshall_ct_labels(&labels, &mask),
+            (String::from("0x8000000000000000000000000000002a"), None)
+        );
+    }
+
+    #[test]
+    fn mpls_lse() {
+        // label 100, tc 1, bos, ttl 64.
+        assert_eq!(
+            parse_mpls_lse(0x0006_4340),
+            MplsLse {
+                label: 100,
+                tc: 1,
+                bos: true,
+                ttl: 64,
+            }
+        );
+        // label 1048575 (max), tc 7, not bos, ttl 255.
+        assert_eq!(
+            parse_mpls_lse(0xffff_feff),
+            MplsLse {
+                label: 0xfffff,
+                tc: 7,
+                bos: false,
+                ttl: 255,
+            }
+        );
+    }
+
+    #[test]
+    fn push_mpls_raw() -> Result<()> {
+        let raw = exec_push_mpls {
+            lse: 0x0006_4340u32.to_be(),
+            ethertype: 0x8847u16.to_be(),
+        };
+        let data = raw_bytes(&raw);
+
+        let mut event = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::PushMpls(OvsActionPushMpls::default())),
+                ..Default::default()
+            }),
+        };
+        unmarshall_push_mpls(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec push_mpls label 100 tc 1 ttl 64 bos ethertype MPLS unicast (0x8847)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn add_mpls() {
+        // label 100, tc 1, bos, ttl 64.
+        let lse = 100 << 12 | 1 << 9 | 1 << 8 | 64u32;
+        let mpls = parse_add_mpls(&exec_add_mpls {
+            lse: lse.to_be(),
+            ethertype: 0x8847u16.to_be(),
+            tun_flags: 0,
+        });
+        assert_eq!(
+            mpls,
+            OvsActionAddMpls {
+                label: Some(100),
+                tc: Some(1),
+                ttl: Some(64),
+                bos: true,
+                ethertype: Some(0x8847),
+                position: Some(OvsMplsPosition::AfterL2),
+                unknown_flags: None,
+            }
+        );
+
+        let mpls = parse_add_mpls(&exec_add_mpls {
+            lse: 0,
+            ethertype: 0x8848u16.to_be(),
+            tun_flags: OVS_MPLS_L3_TUNNEL_FLAG | 0x4,
+        });
+        assert_eq!(mpls.position, Some(OvsMplsPosition::Start));
+        assert_eq!(mpls.unknown_flags, Some(0x4));
+        assert!(!mpls.bos);
+    }
+
+    #[test]
+    fn userspace_attrs() {
+        let attrs = [
+            nlattr(1, &3u32.to_ne_bytes()),
+            nlattr(2, &[0x01, 0x02, 0x03]),
+            nlattr(4, &[]),
+            nlattr(3, &4u32.to_ne_bytes()),
+            nlattr(9, &[0x0a, 0x0b]),
+        ]
+        .concat();
+
+        let mut userspace = OvsActionUserspace::default();
+        parse_userspace(&attrs, &mut userspace);
+        assert_eq!(
+            userspace,
+            OvsActionUserspace {
+                pid: Some(3),
+                userdata: Some("010203".to_string()),
+                egress_tun_port: Some(4),
+                actions: true,
+                unknown_attrs: vec![OvsUserspaceAttr {
+                    attr_type: 9,
+                    data: "0a0b".to_string(),
+                }],
+                truncated: false,
+            }
+        );
+
+        // Attributes cut in the middle are reported.
+        let mut userspace = OvsActionUserspace::default();
+        parse_userspace(&attrs[..10], &mut userspace);
+        assert_eq!(userspace.pid, Some(3));
+        assert!(userspace.userdata.is_none());
+        assert!(userspace.truncated);
+    }
+
+    #[test]
+    fn set_ipv4() {
+        let mut value = [0u8; 12];
+        value[0..4].copy_from_slice(&[10, 0, 0, 1]);
+        value[4..8].copy_from_slice(&[192, 168, 1, 2]);
+        value[10] = 63;
+        let mut mask = [0u8; 12];
+        mask[0..4].fill(0xff);
+        mask[10] = 0xff;
+
+        let set = unmarshall_set_key(OVS_KEY_ATTR_IPV4, &[value, mask].concat(), true);
+        assert_eq!(set.field.as_deref(), Some("ipv4"));
+        assert_eq!(
+            set.ipv4,
+            Some(OvsSetIpv4 {
+                src: Some("10.0.0.1".to_string()),
+                ttl: Some(63),
+                ..Default::default()
+            })
+        );
+
+        // Unknown keys are only reported by type.
+        let set = unmarshall_set_key(1, &value, false);
+        assert!(set.ipv4.is_none() && set.ipv6.is_none());
+
+        // Keys whose content is not decoded are only reported by type.
+        let set = unmarshall_set_key(OVS_KEY_ATTR_TUNNEL, &value, false);
+        assert!(set.ipv4.is_none() && set.ipv6.is_none());
+        assert_eq!(set.field.as_deref(), Some("tunnel"));
+
+        let set = unmarshall_set_key(4, &[value, mask].concat(), true);
+        assert_eq!(set.field.as_deref(), Some("eth"));
+        let set = unmarshall_set_key(200, &value, false);
+        assert_eq!(set.field.as_deref(), Some("unknown(200)"));
+    }
+
+    #[test]
+    fn push_vlan_raw() -> Result<()> {
+        let raw = exec_push_vlan {
+            vlan_tpid: 0x8100u16.to_be(),
+            vlan_tci: 0x6064u16.to_be(),
+        };
+        let data = raw_bytes(&raw);
+
+        let mut event = OvsEvent {
+            event: OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::PushVlan(OvsActionPushVlan::default())),
+                ..Default::default()
+            }),
+        };
+        unmarshall_push_vlan(
+            &BpfRawSection {
+                data: &data,
+                ..Default::default()
+            },
+            &mut event,
+        )?;
+        assert_eq!(
+            event.event,
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::PushVlan(OvsActionPushVlan {
+                    vlan_vid: Some(100),
+                    vlan_pcp: Some(3),
+                    vlan_proto: Some(0x8100),
+                })),
+                has_detail: Some(true),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "exec push_vlan vid 100 pcp 3 proto 802.1Q (0x8100)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn output_port_names() -> Result<()> {
+        let exec = raw_bytes(&exec_event {
+            recirc_id: 0,
+            action: 1,
+        });
+        let mut output = exec_output {
+            port: 2,
+            ..Default::default()
+        };
+        b"ovs-system"
+            .iter()
+            .enumerate()
+            .for_each(|(i, c)| output.dp_name[i] = *c as _);
+        let output = raw_bytes(&output);
+        let sections = || {
+            vec![
+                BpfRawSection {
+                    header: BpfRawSectionHeader {
+                        data_type: OvsDataType::ActionExec.to_u8(),
+                        ..Default::default()
+                    },
+                    data: &exec,
+                },
+                BpfRawSection {
+                    header: BpfRawSectionHeader {
+                        data_type: OvsDataType::OutputAction.to_u8(),
+                        ..Default::default()
+                    },
+                    data: &output,
+                },
+            ]
+        };
+        let display = |section: Box<dyn EventSection>| {
+            format!(
+                "{}",
+                section.display(&DisplayFormat::new(), &FormatterConf::new())
+            )
+        };
+
+        let mut factory = OvsEventFactory::default();
+        assert_eq!(display(factory.create(sections())?), "exec oport 2");
+
+        let mut factory = OvsEventFactory {
+            port_names: Some(PortNames::from_names(&[
+                ("ovs-system", 2, Some("eth0")),
+                ("dp1", 2, Some("eth1")),
+            ])),
+            ..Default::default()
+        };
+        assert_eq!(display(factory.create(sections())?), "exec oport 2 (eth0)");
+
+        // Ports of other datapaths are not used.
+        let mut factory = OvsEventFactory {
+            port_names: Some(PortNames::from_names(&[("dp1", 2, Some("eth1"))])),
+            ..Default::default()
+        };
+        assert_eq!(display(factory.create(sections())?), "exec oport 2");
+
+        // Unresolved ports are reported by number.
+        let mut factory = OvsEventFactory {
+            port_names: Some(PortNames::from_names(&[("ovs-system", 2, None)])),
+            ..Default::default()
+        };
+        assert_eq!(display(factory.create(sections())?), "exec oport 2");
+        Ok(())
+    }
+}
+
 #[cfg(feature = "benchmark")]
 pub(crate) mod benchmark {
     use anyhow::Result;
@@ -359,7 +2677,7 @@ pub(crate) mod benchmark {
             build_raw_section(
                 out,
                 FactoryId::Ovs as u8,
-                OvsDataType::ActionExec as u8,
+                OvsDataType::ActionExec.to_u8(),
                 &mut as_u8_vec(&data),
             );
             Ok(())
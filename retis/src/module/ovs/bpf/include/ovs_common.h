@@ -18,6 +18,23 @@ enum trace_ovs_data_type {
 	OVS_DP_ACTION_OUTPUT = 7,
 	OVS_DP_ACTION_RECIRC = 8,
 	OVS_DP_ACTION_CONNTRACK = 9,
+	OVS_DP_ACTION_POP_VLAN = 10,
+	OVS_DP_ACTION_HASH = 11,
+	OVS_DP_ACTION_METER = 12,
+	OVS_DP_ACTION_SET = 13,
+	OVS_DP_ACTION_POP_NSH = 14,
+	OVS_DP_ACTION_CHECK_PKT_LEN = 15,
+	OVS_DP_ACTION_USERSPACE = 16,
+	OVS_OPERATION_FLOW = 17,
+	OVS_DP_ACTION_ADD_MPLS = 18,
+	OVS_DP_ACTION_ERROR = 19,
+	OVS_DP_ACTION_TRUNC = 20,
+	OVS_DP_ACTION_PUSH_VLAN = 21,
+	OVS_DP_ACTION_PUSH_MPLS = 22,
+	OVS_DP_ACTION_SAMPLE = 23,
+	OVS_DP_ACTION_DEC_TTL = 24,
+	OVS_DP_ACTION_PUSH_ETH = 25,
+	OVS_DP_ACTION_CLONE = 26,
 };
 
 /* Used to keep the context of an upcall operation for its upcall enqueue
@@ -52,6 +69,23 @@ struct execute_actions_ctx {
 	BINDING_PTR(struct sk_buff *, skb);
 	u32 queue_id;
 	bool command;
+	/* Last action executed, and the recirculation id of its flow. */
+	u8 last_action;
+	u32 last_recirc_id;
+} __binding;
+
+/* Failure of the execution of an action list, reported against the last
+ * action executed.
+ */
+struct exec_error {
+	int error;
+	u32 recirc_id;
+	u32 queue_id;
+	u8 action;
+	/* The execution is the result of a userspace command, queue_id is
+	 * set.
+	 */
+	bool command;
 } __binding;
 
 /* Map used to store context between the begining and end of
@@ -9,6 +9,8 @@
 enum ovs_operation_type {
 	OVS_OP_EXEC = 0,
 	OVS_OP_PUT = 1,
+	OVS_OP_DEL = 2,
+	OVS_OP_GET = 3,
 };
 
 struct ovs_operation_event {
@@ -17,6 +19,26 @@ struct ovs_operation_event {
 	u8 batch_idx;
 	/* enum ovs_operation_type */
 	u8 type;
+	/* Kernel error of the operation, 0 if it succeeded or if the result is
+	 * not known. USDT probes are hit before the operation is sent to the
+	 * kernel, so they always report 0. */
+	s32 error;
+} __binding;
+
+/* Size of the flow key and mask copied to the event. Longer ones are
+ * reported as truncated. */
+#define OVS_FLOW_KEY_SIZE	160
+
+/* Flow key and mask of a flow operation, as netlink attributes
+ * (enum ovs_key_attr). A mask_len of 0 means the flow has no mask, i.e. it is
+ * an exact match. */
+struct ovs_operation_flow {
+	/* Lengths of the key and mask, which can be larger than what was
+	 * copied. */
+	u16 key_len;
+	u16 mask_len;
+	u8 key[OVS_FLOW_KEY_SIZE];
+	u8 mask[OVS_FLOW_KEY_SIZE];
 } __binding;
 
 /* Upcall Batching.
@@ -1,9 +1,32 @@
 #include <common.h>
 #include <ovs_common.h>
 
-/* Hook for kretprobe:ovs_packet_cmd_execute. */
+/* Hook for kretprobe:ovs_execute_actions. */
 DEFINE_HOOK_RAW(
+	struct execute_actions_ctx *ectx;
 	u64 tid = bpf_get_current_pid_tgid();
+	struct exec_error *error;
+	int ret = (int) ctx->regs.ret;
+
+	ectx = bpf_map_lookup_elem(&inflight_exec, &tid);
+	/* Filtering is done at the ovs_execute_actions kprobe. */
+	if (!ectx)
+		return 0;
+
+	/* The execution stops at the first action failing: report the error
+	 * against the last action executed, if any.
+	 */
+	if (ret < 0 && ectx->last_action) {
+		error = get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_ERROR, sizeof(*error));
+		if (error) {
+			error->error = ret;
+			error->action = ectx->last_action;
+			error->recirc_id = ectx->last_recirc_id;
+			error->command = ectx->command;
+			error->queue_id = ectx->queue_id;
+		}
+	}
 
 	/* The execute command has finished. Remove the entry from the
 	* inflight_exec map. */
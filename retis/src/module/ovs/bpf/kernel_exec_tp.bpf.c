@@ -15,14 +15,136 @@ struct exec_track_event {
 	u32 queue_id;
 } __binding;
 
+#define IFNAMSIZ	16
+
 struct exec_output {
 	u32 port;
+	/* Name of the datapath, empty if unknown. */
+	char dp_name[IFNAMSIZ];
 } __binding;
 
 struct exec_recirc {
 	u32 id;
 } __binding;
 
+struct exec_hash {
+	u32 hash_alg;
+	u32 hash_basis;
+} __binding;
+
+/* Large enough for the value and mask of the biggest key attribute we decode
+ * (struct ovs_key_ipv6). */
+#define EXEC_SET_DATA_SIZE	80
+struct exec_set {
+	/* enum ovs_key_attr */
+	u16 key_type;
+	u8 masked;
+	u8 len;
+	/* Value of the key attribute, followed by its mask if masked. */
+	u8 data[EXEC_SET_DATA_SIZE];
+} __binding;
+
+struct exec_meter {
+	u32 id;
+} __binding;
+
+struct exec_trunc {
+	u32 max_len;
+} __binding;
+
+struct exec_clone {
+	/* The packet is copied. Clone actions being the last of their list
+	 * execute their nested actions on the packet itself. */
+	u8 cloned;
+} __binding;
+
+/* Mirrors struct ovs_key_ethernet. */
+struct exec_push_eth {
+	u8 eth_src[6];
+	u8 eth_dst[6];
+} __binding;
+
+struct exec_push_vlan {
+	/* Tag protocol identifier, in network byte order. */
+	u16 vlan_tpid;
+	/* Tag control information, in network byte order. */
+	u16 vlan_tci;
+} __binding;
+
+struct exec_pop_vlan {
+	/* Ethertype revealed by the pop, in network byte order. 0 if unknown. */
+	u16 inner_ethertype;
+} __binding;
+
+struct exec_pop_nsh {
+	/* Next protocol field of the NSH header being removed. 0 if unknown. */
+	u8 next_proto;
+} __binding;
+
+struct exec_push_mpls {
+	/* Label stack entry being pushed, in network byte order. */
+	u32 lse;
+	/* Ethertype of the packet after the label is pushed, in network byte
+	 * order. */
+	u16 ethertype;
+} __binding;
+
+struct exec_add_mpls {
+	/* Label stack entry being added, in network byte order. */
+	u32 lse;
+	/* Ethertype of the packet after the label is added, in network byte
+	 * order. */
+	u16 ethertype;
+	/* Tunnel flags (e.g. OVS_MPLS_L3_TUNNEL_FLAG_MASK), telling where the
+	 * label is added. */
+	u16 tun_flags;
+} __binding;
+
+/* Size of the check_pkt_len nested attributes copied to the event. Longer
+ * action lists are reported as truncated. */
+#define EXEC_CHECK_PKT_LEN_ATTRS_SIZE	128
+struct exec_check_pkt_len {
+	/* Packet length threshold. */
+	u16 pkt_len;
+	/* Length of the nested attributes, which can be larger than what was
+	 * copied. */
+	u16 len;
+	/* The packet is longer than the threshold. */
+	u8 greater;
+	/* Nested attributes: the check_pkt_len argument and both action
+	 * lists. */
+	u8 attrs[EXEC_CHECK_PKT_LEN_ATTRS_SIZE];
+} __binding;
+
+struct exec_dec_ttl {
+	/* An action list is executed when the TTL expires. Otherwise the packet
+	 * is dropped. */
+	u8 has_action;
+} __binding;
+
+/* Size of the sample nested attributes copied to the event. Longer action
+ * lists are not counted. */
+#define EXEC_SAMPLE_ATTRS_SIZE	128
+struct exec_sample {
+	/* Length of the nested attributes, which can be larger than what was
+	 * copied. */
+	u16 len;
+	/* Nested attributes: the sample argument, holding the probability,
+	 * followed by the sampled actions. */
+	u8 attrs[EXEC_SAMPLE_ATTRS_SIZE];
+} __binding;
+
+/* Size of the userspace nested attributes copied to the event. Longer
+ * attributes are reported as truncated. */
+#define EXEC_USERSPACE_ATTRS_SIZE	96
+struct exec_userspace {
+	/* Length of the nested attributes, which can be larger than what was
+	 * copied. */
+	u16 len;
+	/* Nested attributes: pid, userdata, egress tunnel port, etc. */
+	u8 attrs[EXEC_USERSPACE_ATTRS_SIZE];
+} __binding;
+
 /* Please keep in sync with its Rust counterpart in retis-events::ovs. */
 #define R_OVS_CT_COMMIT				(1 << 0)
 #define R_OVS_CT_FORCE				(1 << 1)
@@ -36,6 +158,12 @@ struct exec_recirc {
 #define R_OVS_CT_NAT_RANGE_PROTO_RANDOM		(1 << 9)
 #define R_OVS_CT_NAT_RANGE_PERSISTENT		(1 << 10)
 #define R_OVS_CT_NAT_RANGE_PROTO_RANDOM_FULLY	(1 << 11)
+#define R_OVS_CT_CTINFO				(1 << 12)
+#define R_OVS_CT_EVENTMASK			(1 << 13)
+#define R_OVS_CT_LABELS				(1 << 14)
+#define R_OVS_CT_MARK				(1 << 15)
+
+#define NFCT_INFOMASK	7UL
 
 union exec_ip {
 	u32 addr4;
@@ -49,6 +177,18 @@ struct exec_ct {
 	u16 zone_id;
 	u16 min_port;
 	u16 max_port;
+	/* Conntrack info of the packet, if R_OVS_CT_CTINFO is set. */
+	u8 ctinfo;
+	/* Conntrack events to be delivered, if R_OVS_CT_EVENTMASK is set. */
+	u32 eventmask;
+	/* Conntrack labels set and their mask, in network byte order, if
+	 * R_OVS_CT_LABELS is set.
+	 */
+	u8 labels[16];
+	u8 labels_mask[16];
+	/* Conntrack mark set and its mask, if R_OVS_CT_MARK is set. */
+	u32 mark;
+	u32 mark_mask;
 } __binding;
 
 static __always_inline void fill_nat(struct ovs_conntrack_info *info,
@@ -97,14 +237,114 @@ static __always_inline void fill_nat(struct ovs_conntrack_info *info,
 	}
 }
 
+/* Retrieves the ethertype the pop_vlan action will reveal. The tracepoint is
+ * hit before the action is executed, so the tag is still there. */
+static __always_inline u16 pop_vlan_inner_ethertype(struct sk_buff *skb)
+{
+	unsigned char *head;
+	u16 etype = 0;
+
+	/* Accelerated tag: skb->protocol already holds the inner ethertype. */
+	if (BPF_CORE_READ(skb, vlan_all))
+		return BPF_CORE_READ(skb, protocol);
+
+	if (!is_mac_data_valid(skb))
+		return 0;
+
+	/* In-band tag: its ethertype follows the MAC addresses and the tag. */
+	head = BPF_CORE_READ(skb, head);
+	bpf_probe_read_kernel(&etype, sizeof(etype),
+			      head + BPF_CORE_READ(skb, mac_header) + 16);
+	return etype;
+}
+
+/* Retrieves the next protocol of the NSH header the pop_nsh action will
+ * remove. NSH packets have no Ethernet header, the NSH one starts at
+ * skb->data. */
+static __always_inline u8 pop_nsh_next_proto(struct sk_buff *skb)
+{
+	unsigned char *data = BPF_CORE_READ(skb, data);
+	u8 np = 0;
+
+	/* struct nshhdr: ver_flags_ttl_len (2 bytes), mdtype, np. */
+	bpf_probe_read_kernel(&np, sizeof(np), data + 3);
+	return np;
+}
+
+/* Port number of the datapath local port, whose device is named after the
+ * datapath. */
+#define OVSP_LOCAL		0
+/* Maximum number of ports looked at in a datapath port hash bucket. */
+#define EXEC_DP_BUCKET_MAX	8
+
+/* Retrieves the name of a datapath, i.e. the name of its local port device
+ * (see ovs_dp_name()). The local port is in the first hash bucket of the
+ * datapath ports (see vport_hash_bucket()). The name is left empty if the
+ * port is not found. */
+static __always_inline void exec_dp_name(struct datapath *dp, char *name)
+{
+	struct hlist_head *ports;
+	struct hlist_node *node;
+	struct vport *vport = NULL;
+	u32 offset;
+	int i;
+
+	ports = BPF_CORE_READ(dp, ports);
+	if (!ports)
+		return;
+
+	offset = bpf_core_field_offset(vport->dp_hash_node);
+	node = BPF_CORE_READ(ports, first);
+	for (i = 0; i < EXEC_DP_BUCKET_MAX && node; i++) {
+		vport = (void *)node - offset;
+		if (BPF_CORE_READ(vport, port_no) == OVSP_LOCAL) {
+			struct net_device *dev = BPF_CORE_READ(vport, dev);
+
+			if (dev)
+				bpf_probe_read_kernel_str(name, IFNAMSIZ,
+							  dev->name);
+			return;
+		}
+		node = BPF_CORE_READ(node, next);
+	}
+}
+
+/* Reports the key of a set, set_masked or set_to_masked action. */
+static __always_inline int exec_set(struct retis_raw_event *event,
+				    struct nlattr *attr, bool masked)
+{
+	struct nlattr *set_key = nla_data(attr);
+	struct exec_set *set =
+		get_event_section(event, COLLECTOR_OVS, OVS_DP_ACTION_SET,
+				  sizeof(*set));
+	if (!set)
+		return 0;
+
+	set->key_type = nla_type(set_key);
+	set->masked = masked;
+
+	u32 len = nla_len(set_key);
+	if (len > EXEC_SET_DATA_SIZE)
+		len = EXEC_SET_DATA_SIZE;
+	set->len = len;
+	bpf_probe_read_kernel(set->data, len, nla_data(set_key));
+	return 0;
+}
+
 /* Hook for ovs_do_execute_action tracepoint. */
 DEFINE_HOOK_RAW(
 	struct nlattr *attr;
+	struct sk_buff *skb;
+	struct datapath *dp;
 	struct sw_flow_key *key;
 	struct exec_event *exec;
 	struct execute_actions_ctx *ectx;
 	u64 tid = bpf_get_current_pid_tgid();
 
+	skb = (struct sk_buff *) ctx->regs.reg[1];
+	if (!skb)
+		return 0;
+
 	key = (struct sw_flow_key *) ctx->regs.reg[2];
 	if (!key)
 		return 0;
@@ -125,6 +365,8 @@ DEFINE_HOOK_RAW(
 
 	exec->action = nla_type(attr);
 	exec->recirc_id = BPF_CORE_READ(key, recirc_id);
+	ectx->last_action = exec->action;
+	ectx->last_recirc_id = exec->recirc_id;
 	/* Do not emit tracking information if it's not a flow_exec action. */
 	if (ectx->command) {
 		struct exec_track_event *track =
@@ -136,19 +378,46 @@ DEFINE_HOOK_RAW(
 		track->queue_id = ectx->queue_id;
 	}
 
+	/* OVS_ACTION_ATTR_SET_TO_MASKED is private to the kernel and its value
+	 * depends on the running one, it can't be a constant case below. */
+	if (bpf_core_enum_value_exists(enum ovs_action_attr,
+				       OVS_ACTION_ATTR_SET_TO_MASKED) &&
+	    exec->action == bpf_core_enum_value(enum ovs_action_attr,
+						OVS_ACTION_ATTR_SET_TO_MASKED))
+		return exec_set(event, attr, true);
+
 	// Add action-specific data for some actions.
 	switch (exec->action) {
 	case OVS_ACTION_ATTR_OUTPUT:
 		{
 		struct exec_output *output =
-			get_event_section(event, COLLECTOR_OVS,
-					  OVS_DP_ACTION_OUTPUT,
-					  sizeof(*output));
+			get_event_zsection(event, COLLECTOR_OVS,
+					   OVS_DP_ACTION_OUTPUT,
+					   sizeof(*output));
 		if (!output)
 			return 0;
 
 		bpf_probe_read_kernel(&output->port, sizeof(output->port),
 				      nla_data(attr));
+		dp = (struct datapath *) ctx->regs.reg[0];
+		if (dp)
+			exec_dp_name(dp, output->dp_name);
+		break;
+		}
+	case OVS_ACTION_ATTR_USERSPACE:
+		{
+		struct exec_userspace *userspace =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_USERSPACE,
+					  sizeof(*userspace));
+		if (!userspace)
+			return 0;
+
+		u32 len = nla_len(attr);
+		userspace->len = len;
+		if (len > EXEC_USERSPACE_ATTRS_SIZE)
+			len = EXEC_USERSPACE_ATTRS_SIZE;
+		bpf_probe_read_kernel(userspace->attrs, len, nla_data(attr));
 		break;
 		}
 	case OVS_ACTION_ATTR_RECIRC:
@@ -164,6 +433,209 @@ DEFINE_HOOK_RAW(
 				      nla_data(attr));
 		break;
 		}
+	case OVS_ACTION_ATTR_PUSH_VLAN:
+		{
+		struct ovs_action_push_vlan *vlan_act = nla_data(attr);
+		struct exec_push_vlan *push_vlan =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_PUSH_VLAN,
+					  sizeof(*push_vlan));
+		if (!push_vlan)
+			return 0;
+
+		bpf_probe_read_kernel(push_vlan, sizeof(*push_vlan), vlan_act);
+		break;
+		}
+	case OVS_ACTION_ATTR_CLONE:
+		{
+		/* Length of the action list from this action, included. */
+		int rem = (int) ctx->regs.reg[4];
+		struct exec_clone *clone =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_CLONE,
+					  sizeof(*clone));
+		if (!clone)
+			return 0;
+
+		/* See nla_is_last(). */
+		clone->cloned = BPF_CORE_READ(attr, nla_len) < rem;
+		break;
+		}
+	case OVS_ACTION_ATTR_PUSH_ETH:
+		{
+		struct ovs_action_push_eth *eth_act = nla_data(attr);
+		struct exec_push_eth *push_eth =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_PUSH_ETH,
+					  sizeof(*push_eth));
+		if (!push_eth)
+			return 0;
+
+		bpf_probe_read_kernel(push_eth, sizeof(*push_eth),
+				      &eth_act->addresses);
+		break;
+		}
+	case OVS_ACTION_ATTR_POP_VLAN:
+		{
+		struct exec_pop_vlan *pop_vlan =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_POP_VLAN,
+					  sizeof(*pop_vlan));
+		if (!pop_vlan)
+			return 0;
+
+		pop_vlan->inner_ethertype = pop_vlan_inner_ethertype(skb);
+		break;
+		}
+	case OVS_ACTION_ATTR_POP_NSH:
+		{
+		struct exec_pop_nsh *pop_nsh =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_POP_NSH,
+					  sizeof(*pop_nsh));
+		if (!pop_nsh)
+			return 0;
+
+		pop_nsh->next_proto = pop_nsh_next_proto(skb);
+		break;
+		}
+	case OVS_ACTION_ATTR_PUSH_MPLS:
+		{
+		struct ovs_action_push_mpls mpls_act;
+		bpf_probe_read_kernel(&mpls_act, sizeof(mpls_act), nla_data(attr));
+
+		struct exec_push_mpls *push_mpls =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_PUSH_MPLS,
+					  sizeof(*push_mpls));
+		if (!push_mpls)
+			return 0;
+
+		push_mpls->lse = mpls_act.mpls_lse;
+		push_mpls->ethertype = mpls_act.mpls_ethertype;
+		break;
+		}
+	case OVS_ACTION_ATTR_ADD_MPLS:
+		{
+		struct ovs_action_add_mpls mpls_act;
+		bpf_probe_read_kernel(&mpls_act, sizeof(mpls_act), nla_data(attr));
+
+		struct exec_add_mpls *add_mpls =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_ADD_MPLS,
+					  sizeof(*add_mpls));
+		if (!add_mpls)
+			return 0;
+
+		add_mpls->lse = mpls_act.mpls_lse;
+		add_mpls->ethertype = mpls_act.mpls_ethertype;
+		add_mpls->tun_flags = mpls_act.tun_flags;
+		break;
+		}
+	case OVS_ACTION_ATTR_DEC_TTL:
+		{
+		/* The first nested attribute is always OVS_DEC_TTL_ATTR_ACTION,
+		 * holding the (possibly empty) action list. */
+		struct nlattr *actions = nla_data(attr);
+		struct exec_dec_ttl *dec_ttl =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_DEC_TTL,
+					  sizeof(*dec_ttl));
+		if (!dec_ttl)
+			return 0;
+
+		dec_ttl->has_action = nla_len(actions) > 0;
+		break;
+		}
+	case OVS_ACTION_ATTR_SAMPLE:
+		{
+		struct exec_sample *sample =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_SAMPLE,
+					  sizeof(*sample));
+		if (!sample)
+			return 0;
+
+		u32 len = nla_len(attr);
+		sample->len = len;
+		if (len > EXEC_SAMPLE_ATTRS_SIZE)
+			len = EXEC_SAMPLE_ATTRS_SIZE;
+		bpf_probe_read_kernel(sample->attrs, len, nla_data(attr));
+		break;
+		}
+	case OVS_ACTION_ATTR_CHECK_PKT_LEN:
+		{
+		/* The first nested attribute is always the (private)
+		 * check_pkt_len argument, starting with the threshold. */
+		struct nlattr *arg = nla_data(attr);
+		struct exec_check_pkt_len *cpl =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_CHECK_PKT_LEN,
+					  sizeof(*cpl));
+		if (!cpl)
+			return 0;
+
+		bpf_probe_read_kernel(&cpl->pkt_len, sizeof(cpl->pkt_len),
+				      nla_data(arg));
+		/* The datapath also accounts for the MRU of defragmented
+		 * packets and for GSO, which we can't see here. */
+		cpl->greater = BPF_CORE_READ(skb, len) > cpl->pkt_len;
+
+		u32 len = nla_len(attr);
+		cpl->len = len;
+		if (len > EXEC_CHECK_PKT_LEN_ATTRS_SIZE)
+			len = EXEC_CHECK_PKT_LEN_ATTRS_SIZE;
+		bpf_probe_read_kernel(cpl->attrs, len, arg);
+		break;
+		}
+	case OVS_ACTION_ATTR_HASH:
+		{
+		struct ovs_action_hash *hash_act = nla_data(attr);
+		struct exec_hash *hash =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_HASH,
+					  sizeof(*hash));
+		if (!hash)
+			return 0;
+
+		bpf_probe_read_kernel(&hash->hash_alg, sizeof(hash->hash_alg),
+				      &hash_act->hash_alg);
+		bpf_probe_read_kernel(&hash->hash_basis,
+				      sizeof(hash->hash_basis),
+				      &hash_act->hash_basis);
+		break;
+		}
+	case OVS_ACTION_ATTR_SET:
+	case OVS_ACTION_ATTR_SET_MASKED:
+		return exec_set(event, attr,
+				exec->action == OVS_ACTION_ATTR_SET_MASKED);
+	case OVS_ACTION_ATTR_METER:
+		{
+		struct exec_meter *meter =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_METER,
+					  sizeof(*meter));
+		if (!meter)
+			return 0;
+
+		bpf_probe_read_kernel(&meter->id, sizeof(meter->id),
+				      nla_data(attr));
+		break;
+		}
+	case OVS_ACTION_ATTR_TRUNC:
+		{
+		struct ovs_action_trunc *trunc_act = nla_data(attr);
+		struct exec_trunc *trunc =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_TRUNC,
+					  sizeof(*trunc));
+		if (!trunc)
+			return 0;
+
+		bpf_probe_read_kernel(&trunc->max_len, sizeof(trunc->max_len),
+				      &trunc_act->max_len);
+		break;
+		}
 	case OVS_ACTION_ATTR_CT:
 		{
 		struct ovs_conntrack_info info;
@@ -183,6 +655,28 @@ DEFINE_HOOK_RAW(
 			ct->flags |= R_OVS_CT_COMMIT;
 		if (info.force)
 			ct->flags |= R_OVS_CT_FORCE;
+		if (info.have_eventmask) {
+			ct->flags |= R_OVS_CT_EVENTMASK;
+			ct->eventmask = info.eventmask;
+		}
+		/* Labels are only set when the mask is not zero. */
+		if (info.labels.mask.ct_labels_32[0] ||
+		    info.labels.mask.ct_labels_32[1] ||
+		    info.labels.mask.ct_labels_32[2] ||
+		    info.labels.mask.ct_labels_32[3]) {
+			ct->flags |= R_OVS_CT_LABELS;
+			__builtin_memcpy(ct->labels, info.labels.value.ct_labels,
+					 sizeof(ct->labels));
+			__builtin_memcpy(ct->labels_mask,
+					 info.labels.mask.ct_labels,
+					 sizeof(ct->labels_mask));
+		}
+		/* Same for the mark. */
+		if (info.mark.mask) {
+			ct->flags |= R_OVS_CT_MARK;
+			ct->mark = info.mark.value;
+			ct->mark_mask = info.mark.mask;
+		}
 
 		if (info.family == NFPROTO_IPV4)
 			ct->flags |= R_OVS_CT_IP4;
@@ -193,6 +687,19 @@ DEFINE_HOOK_RAW(
 			ct->flags |= R_OVS_CT_NAT;
 			fill_nat(&info, ct);
 		}
+
+		/* Report the conntrack info the packet already has (e.g. from a
+		 * previous ct action, before a recirculation) as it tells in
+		 * which direction the packet is.
+		 */
+		if (bpf_core_field_exists(skb->_nfct)) {
+			unsigned long nfct = (unsigned long) BPF_CORE_READ(skb, _nfct);
+
+			if (nfct) {
+				ct->flags |= R_OVS_CT_CTINFO;
+				ct->ctinfo = (u8)(nfct & NFCT_INFOMASK);
+			}
+		}
 		break;
 		}
 	}
@@ -5,6 +5,7 @@
 #include <ovs_common.h>
 
 struct upcall_event {
+	/* Netlink port id of the handler socket the upcall is sent to. */
 	u32 port;
 	u32 cpu;
 	u8 cmd;
@@ -3,9 +3,48 @@
 #include <user_common.h>
 #include <ovs_operation.h>
 
+/* Beginning of ovs-vswitchd's struct dpif_flow_put (lib/dpif.h). */
+struct dpif_flow_put {
+	u32 flags;
+	const void *key;
+	size_t key_len;
+	const void *mask;
+	size_t mask_len;
+};
+
 /* Hook for usdt:dpif_netlink_operate__::op_flow_put. */
 DEFINE_USDT_HOOK (
-	return batch_process_op(OVS_OP_PUT, event, NULL);
+	struct ovs_operation_event *op;
+	struct ovs_operation_flow *flow;
+	struct dpif_flow_put put;
+	u32 len;
+
+	batch_process_op(OVS_OP_PUT, event, &op);
+	if (!op)
+		return 0;
+
+	if (bpf_probe_read_user(&put, sizeof(put), (void *)ctx->args[1]))
+		return 0;
+
+	flow = get_event_zsection(event, COLLECTOR_OVS, OVS_OPERATION_FLOW,
+				  sizeof(*flow));
+	if (!flow)
+		return 0;
+
+	flow->key_len = put.key_len;
+	flow->mask_len = put.mask ? put.mask_len : 0;
+
+	len = put.key_len;
+	if (len > OVS_FLOW_KEY_SIZE)
+		len = OVS_FLOW_KEY_SIZE;
+	bpf_probe_read_user(flow->key, len, put.key);
+
+	len = flow->mask_len;
+	if (len > OVS_FLOW_KEY_SIZE)
+		len = OVS_FLOW_KEY_SIZE;
+	bpf_probe_read_user(flow->mask, len, put.mask);
+
+	return 0;
 )
 
 char __license[] SEC("license") = "GPL";
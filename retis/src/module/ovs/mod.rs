@@ -8,6 +8,9 @@ pub(crate) mod ovs;
 // Re-export ovs.rs
 pub(crate) use ovs::*;
 pub(crate) mod bpf;
+mod port_names;
+#[cfg(any(debug_assertions, feature = "validate_events"))]
+mod validate;
 
 mod hooks {
     pub(super) mod kernel_enqueue {
@@ -9,7 +9,7 @@ use std::{
 use anyhow::{anyhow, bail, Result};
 use clap::{arg, Parser};
 
-use super::{bpf::OvsEventFactory, hooks};
+use super::{bpf::OvsEventFactory, hooks, port_names::PortNames};
 use crate::{
     bindings::{
         ovs_common_uapi::{execute_actions_ctx, upcall_context},
@@ -49,11 +49,25 @@ pub(crate) struct OvsCollectorArgs {
 See https://docs.openvswitch.org/en/latest/topics/usdt-probes/ for instructions."
     )]
     ovs_track: bool,
+    #[arg(
+        long,
+        default_value = "false",
+        help = "Report the name of the datapath ports used by output actions. Names are retrieved using ovs-dpctl."
+    )]
+    ovs_port_names: bool,
+    #[arg(
+        long,
+        default_value = "false",
+        help = "Fail to process OVS events holding action ids or flow operation types not known by Retis, instead of reporting them as unknown."
+    )]
+    ovs_strict_unknown: bool,
 }
 
 #[derive(Default)]
 pub(crate) struct OvsModule {
     track: bool,
+    port_names: bool,
+    strict_unknown: bool,
     inflight_upcalls_map: Option<libbpf_rs::MapHandle>,
     inflight_exec_map: Option<libbpf_rs::MapHandle>,
 
@@ -104,9 +118,10 @@ impl Collector for OvsModule {
         probes: &mut ProbeBuilderManager,
         _: Arc<RetisEventsFactory>,
     ) -> Result<()> {
-        self.track = cli
-            .get_section::<OvsCollectorArgs>(SectionId::Ovs)?
-            .ovs_track;
+        let args = cli.get_section::<OvsCollectorArgs>(SectionId::Ovs)?;
+        self.track = args.ovs_track;
+        self.port_names = args.ovs_port_names;
+        self.strict_unknown = args.ovs_strict_unknown;
 
         self.inflight_upcalls_map = Some(Self::create_inflight_upcalls_map()?);
 
@@ -146,7 +161,11 @@ impl Module for OvsModule {
         self
     }
     fn section_factory(&self) -> Result<Option<Box<dyn EventSectionFactory>>> {
-        Ok(Some(Box::new(OvsEventFactory {})))
+        Ok(Some(Box::new(OvsEventFactory {
+            port_names: self.port_names.then(PortNames::new).transpose()?,
+            strict_unknown: self.strict_unknown,
+            ..Default::default()
+        })))
     }
 }
 
@@ -0,0 +1,222 @@
+//! OVS datapath port names.
+//!
+//! Output actions report the datapath and its port number; this resolves them to the name of the
+//! port by querying the datapaths (`ovs-dpctl show`). Names are resolved once when the collection
+//! starts; unknown ports are then resolved by a background thread, refreshing the names, so that
+//! events are not held while querying the datapaths.
+
+use std::{
+    collections::HashMap,
+    process::Command,
+    sync::{
+        mpsc::{sync_channel, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use log::debug;
+
+static OVS_DPCTL_BIN: &str = "ovs-dpctl";
+
+/// Minimum interval between two queries of the datapaths.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Port names by datapath name, then by port number. `None` if the port was looked up but not
+/// found.
+type PortMap = HashMap<String, HashMap<u32, Option<String>>>;
+
+pub(crate) struct PortNames {
+    names: Arc<Mutex<PortMap>>,
+    /// Requests the background thread to refresh the names. The thread stops once this is
+    /// dropped.
+    refresh: Option<SyncSender<()>>,
+}
+
+impl PortNames {
+    /// Resolves the names of the ports of all datapaths and starts the thread refreshing them.
+    pub(crate) fn new() -> Result<Self> {
+        let names = Arc::new(Mutex::new(PortMap::new()));
+        Self::refresh(&names);
+
+        // A single pending request is enough, as a refresh resolves all the ports.
+        let (tx, rx) = sync_channel(1);
+        let thread_names = Arc::clone(&names);
+        thread::Builder::new()
+            .name("ovs-port-names".to_string())
+            .spawn(move || {
+                while rx.recv().is_ok() {
+                    Self::refresh(&thread_names);
+                    thread::sleep(REFRESH_INTERVAL);
+                }
+            })?;
+
+        Ok(Self {
+            names,
+            refresh: Some(tx),
+        })
+    }
+
+    #[cfg(test)]
+    pub(super) fn from_names(names: &[(&str, u32, Option<&str>)]) -> Self {
+        let mut map = PortMap::new();
+        names.iter().for_each(|(dp, port, name)| {
+            map.entry(dp.to_string())
+                .or_default()
+                .insert(*port, name.map(str::to_string));
+        });
+
+        Self {
+            names: Arc::new(Mutex::new(map)),
+            refresh: None,
+        }
+    }
+
+    /// Get the name of a datapath port, if it is resolved. Unknown ports are resolved in the
+    /// background, for the next events.
+    pub(crate) fn get(&mut self, dp: &str, port: u32) -> Option<String> {
+        let mut names = self.names.lock().unwrap();
+        if let Some(name) = names.get(dp).and_then(|ports| ports.get(&port)) {
+            return name.clone();
+        }
+
+        // Do not request a refresh again for this port.
+        names.entry(dp.to_string()).or_default().insert(port, None);
+        if let Some(refresh) = &self.refresh {
+            // A refresh is already pending if the channel is full.
+            let _ = refresh.try_send(());
+        }
+        None
+    }
+
+    fn refresh(names: &Mutex<PortMap>) {
+        match Self::query() {
+            Ok(ports) => update(&mut names.lock().unwrap(), ports),
+            Err(e) => debug!("Could not resolve OVS datapath port names: {e}"),
+        }
+    }
+
+    fn query() -> Result<Vec<(String, u32, String)>> {
+        let output = Command::new(OVS_DPCTL_BIN).arg("show").output()?;
+        if !output.status.success() {
+            bail!(
+                "{OVS_DPCTL_BIN} failed with code: {:?}",
+                output.status.code()
+            );
+        }
+
+        Ok(parse_dpctl_show(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Add the resolved ports to the names. Ports which are not resolved keep their previous state.
+fn update(names: &mut PortMap, ports: Vec<(String, u32, String)>) {
+    ports.into_iter().for_each(|(dp, port, name)| {
+        names.entry(dp).or_default().insert(port, Some(name));
+    });
+}
+
+/// Parse the datapath (e.g. "system@ovs-system:") and port lines (e.g. "  port 2: eth0" or
+/// "  port 3: vxlan_sys_4789 (vxlan: packet_type=ptap)") of `ovs-dpctl show`.
+fn parse_dpctl_show(output: &str) -> Vec<(String, u32, String)> {
+    let mut dp = None;
+    output
+        .lines()
+        .filter_map(|line| {
+            if !line.starts_with(char::is_whitespace) {
+                dp = line
+                    .strip_suffix(':')
+                    .and_then(|l| l.split_once('@'))
+                    .map(|(_, name)| name.to_string());
+                return None;
+            }
+
+            let (port, name) = line.trim().strip_prefix("port ")?.split_once(": ")?;
+            let name = name.split_whitespace().next()?;
+            Some((dp.clone()?, port.parse().ok()?, name.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dpctl_show() {
+        let output = "system@ovs-system:
+  lookups: hit:1205 missed:86 lost:0
+  flows: 2
+  masks: hit:2338 total:2 hit/pkt:1.81
+  cache: hit:1130 hit-rate:87.53%
+  caches:
+    masks-cache: size:256
+  port 0: ovs-system (internal)
+  port 1: br0 (internal)
+  port 2: eth0
+  port 3: vxlan_sys_4789 (vxlan: packet_type=ptap)
+";
+
+        assert_eq!(
+            parse_dpctl_show(output),
+            vec![
+                ("ovs-system".to_string(), 0, "ovs-system".to_string()),
+                ("ovs-system".to_string(), 1, "br0".to_string()),
+                ("ovs-system".to_string(), 2, "eth0".to_string()),
+                ("ovs-system".to_string(), 3, "vxlan_sys_4789".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn dpctl_show_datapaths() {
+        let output = "system@dp0:
+  lookups: hit:0 missed:0 lost:0
+  port 0: dp0 (internal)
+  port 1: eth0
+system@dp1:
+  lookups: hit:0 missed:0 lost:0
+  port 0: dp1 (internal)
+  port 1: eth1
+";
+
+        assert_eq!(
+            parse_dpctl_show(output),
+            vec![
+                ("dp0".to_string(), 0, "dp0".to_string()),
+                ("dp0".to_string(), 1, "eth0".to_string()),
+                ("dp1".to_string(), 0, "dp1".to_string()),
+                ("dp1".to_string(), 1, "eth1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn port_names_cache() {
+        let mut names = PortNames::from_names(&[
+            ("ovs-system", 2, Some("eth0")),
+            ("ovs-system", 7, None),
+            ("dp1", 2, Some("eth1")),
+        ]);
+
+        assert_eq!(names.get("ovs-system", 2), Some("eth0".to_string()));
+        assert_eq!(names.get("dp1", 2), Some("eth1".to_string()));
+        // Known to be missing, the datapath is not queried again.
+        assert_eq!(names.get("ovs-system", 7), None);
+        // Unknown, marked as missing until resolved.
+        assert_eq!(names.get("dp1", 3), None);
+        assert_eq!(
+            names.names.lock().unwrap().get("dp1").unwrap().get(&3),
+            Some(&None)
+        );
+
+        update(
+            &mut names.names.lock().unwrap(),
+            vec![("dp1".to_string(), 3, "eth3".to_string())],
+        );
+        assert_eq!(names.get("dp1", 3), Some("eth3".to_string()));
+        assert_eq!(names.get("ovs-system", 7), None);
+    }
+}
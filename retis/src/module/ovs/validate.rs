@@ -0,0 +1,136 @@
+//! Consistency checks of unmarshaled OVS events.
+//!
+//! Some field combinations can't be reported by the probes (e.g. a flow match on a flow_del
+//! operation, or a positive return code). Finding one means an unmarshaler, or the BPF side,
+//! does not agree with the event definitions. This module is only built in debug builds or when
+//! the `validate_events` feature is enabled, and violations are logged.
+
+use std::mem::{discriminant, Discriminant};
+
+use log::warn;
+
+use crate::events::*;
+
+/// Kind of the action an action event was created with, if any.
+pub(super) fn action_kind(event: &OvsEvent) -> Option<Discriminant<OvsAction>> {
+    match &event.event {
+        OvsEventType::Action(ActionEvent {
+            action: Some(action),
+            ..
+        }) => Some(discriminant(action)),
+        _ => None,
+    }
+}
+
+/// Returns the inconsistencies found in an event. `exec_action` is the kind of the action the
+/// event was created with, as action data sections must not change it.
+pub(super) fn check(event: &OvsEvent, exec_action: Option<Discriminant<OvsAction>>) -> Vec<String> {
+    let mut violations = Vec::new();
+    let mut check = |cond: bool, msg: &dyn Fn() -> String| {
+        if !cond {
+            violations.push(msg());
+        }
+    };
+
+    match &event.event {
+        OvsEventType::Upcall(upcall) => {
+            check(upcall.cmd <= 3, &|| {
+                format!("unknown upcall cmd {}", upcall.cmd)
+            });
+        }
+        OvsEventType::UpcallEnqueue(enqueue) => {
+            check(enqueue.cmd <= 3, &|| {
+                format!("unknown upcall cmd {}", enqueue.cmd)
+            });
+            check(enqueue.ret <= 0, &|| {
+                format!("positive enqueue return code {}", enqueue.ret)
+            });
+        }
+        OvsEventType::UpcallReturn(ret) => {
+            check(ret.ret <= 0, &|| {
+                format!("positive upcall return code {}", ret.ret)
+            });
+        }
+        OvsEventType::RecvUpcall(_) => (),
+        OvsEventType::Operation(op) => {
+            check(op.op_type <= OVS_OP_GET, &|| {
+                format!("unknown operation type {}", op.op_type)
+            });
+            check(!matches!(op.error, Some(e) if e >= 0), &|| {
+                format!("non-negative operation error {:?}", op.error)
+            });
+            check(op.flow.is_none() || op.op_type == OVS_OP_PUT, &|| {
+                format!("flow match on operation type {}", op.op_type)
+            });
+        }
+        OvsEventType::Action(action) => match &action.action {
+            Some(a) => check(exec_action == Some(discriminant(a)), &|| {
+                format!("action data ({a:?}) does not match the executed action")
+            }),
+            None => check(action.queue_id.is_none(), &|| {
+                "tracking information without an action".to_string()
+            }),
+        },
+    }
+
+    violations
+}
+
+/// Checks an event and logs its inconsistencies, if any.
+pub(super) fn validate(event: &OvsEvent, exec_action: Option<Discriminant<OvsAction>>) {
+    check(event, exec_action)
+        .iter()
+        .for_each(|v| warn!("Inconsistent OVS event ({v}): {event:?}"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event: OvsEventType) -> OvsEvent {
+        OvsEvent { event }
+    }
+
+    #[test]
+    fn check_events() {
+        let put = event(OvsEventType::Operation(OperationEvent {
+            op_type: OVS_OP_PUT,
+            flow: Some(OvsFlowMatch::default()),
+            ..Default::default()
+        }));
+        assert!(check(&put, None).is_empty());
+
+        let del = event(OvsEventType::Operation(OperationEvent {
+            op_type: OVS_OP_DEL,
+            error: Some(2),
+            flow: Some(OvsFlowMatch::default()),
+            ..Default::default()
+        }));
+        assert_eq!(check(&del, None).len(), 2);
+
+        let ret = event(OvsEventType::UpcallReturn(UpcallReturnEvent {
+            ret: 1,
+            ..Default::default()
+        }));
+        assert_eq!(check(&ret, None).len(), 1);
+
+        // Output data on an action created as a recirc one.
+        let recirc = event(OvsEventType::Action(ActionEvent {
+            action: Some(OvsAction::Recirc(OvsActionRecirc::default())),
+            ..Default::default()
+        }));
+        let mut output = event(OvsEventType::Action(ActionEvent {
+            action: Some(OvsAction::Output(OvsActionOutput::default())),
+            ..Default::default()
+        }));
+        let kind = action_kind(&output);
+        assert!(check(&output, kind).is_empty());
+        assert_eq!(check(&output, action_kind(&recirc)).len(), 1);
+
+        if let OvsEventType::Action(action) = &mut output.event {
+            action.action = None;
+            action.queue_id = Some(1);
+        }
+        assert_eq!(check(&output, None).len(), 1);
+    }
+}
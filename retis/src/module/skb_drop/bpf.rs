@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::Result;
-use btf_rs::Type;
 use log::warn;
+use once_cell::sync::OnceCell;
 
 use crate::bindings::skb_drop_hook_uapi::skb_drop_event;
 
@@ -22,24 +24,16 @@ use crate::{
     events::*,
 };
 
-fn parse_enum(r#enum: &str, trim_start: &[&str]) -> Result<HashMap<u32, String>> {
-    let mut values = HashMap::new();
+/// Drop reason definitions, by sub-system id. Shared by all factories as
+/// retrieving them from the BTF is expensive.
+static DROP_REASONS: OnceCell<HashMap<u16, DropReasons>> = OnceCell::new();
 
-    if let Ok(types) = inspector()?.kernel.btf.resolve_types_by_name(r#enum) {
-        if let Some((btf, Type::Enum(r#enum))) =
-            types.iter().find(|(_, t)| matches!(t, Type::Enum(_)))
-        {
-            for member in r#enum.members.iter() {
-                let mut val = btf.resolve_name(member)?;
-                trim_start
-                    .iter()
-                    .for_each(|p| val = val.trim_start_matches(p).to_string());
-                values.insert(member.val(), val.to_string());
-            }
-        }
-    }
+/// Number of times the drop reasons were retrieved from the BTF.
+#[cfg(test)]
+static DROP_REASONS_PARSED: AtomicUsize = AtomicUsize::new(0);
 
-    Ok(values)
+fn parse_enum(r#enum: &str, trim_start: &[&str]) -> Result<HashMap<u32, String>> {
+    inspector()?.kernel.btf.resolve_enum(r#enum, trim_start)
 }
 
 /// Per-subsystem drop reason definitions.
@@ -62,9 +56,13 @@ impl DropReasons {
             "mac80211_unusable" => parse_enum("mac80211_drop_reason", &[])?,
             "mac80211_monitor" => parse_enum("mac80211_drop_reason", &[])?,
             "openvswitch" => parse_enum("ovs_drop_reason", &[])?,
+            // Newer sub-systems are likely to follow the same naming.
             x => {
-                warn!("Unknown drop reason subsystem ({x})");
-                HashMap::new()
+                let reasons = parse_enum(&format!("{x}_drop_reason"), &[])?;
+                if reasons.is_empty() {
+                    warn!("Unknown drop reason subsystem ({x})");
+                }
+                reasons
             }
         };
 
@@ -78,29 +76,120 @@ impl DropReasons {
     }
 }
 
+/// Filters drop events by their reason. Patterns match the reason name, with or
+/// without its sub-system (e.g. "NO_SOCKET" or "openvswitch/OVS_DROP_FLOW"),
+/// and can end with a '*' to match a prefix (e.g. "TCP_*").
+#[derive(Default)]
+pub(crate) struct DropReasonFilter {
+    /// If not empty, only reasons matching one of these patterns are kept.
+    allow: Vec<String>,
+    /// Reasons matching one of these patterns are filtered out.
+    deny: Vec<String>,
+}
+
+impl DropReasonFilter {
+    pub(crate) fn new(allow: &[String], deny: &[String]) -> Self {
+        Self {
+            allow: allow.to_vec(),
+            deny: deny.to_vec(),
+        }
+    }
+
+    /// Does the filter keep all events?
+    fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Should events with this drop reason be kept?
+    fn keep(&self, subsys: Option<&str>, reason: &str) -> bool {
+        let full = subsys.map(|subsys| format!("{subsys}/{reason}"));
+        let matches = |pattern: &String| {
+            let name_matches = |name: &str| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            };
+            name_matches(reason) || full.as_deref().is_some_and(name_matches)
+        };
+
+        (self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+    }
+}
+
 #[event_section_factory(FactoryId::SkbDrop)]
 pub(crate) struct SkbDropEventFactory {
     /// Map of sub-system reason ids to their custom drop reason definitions.
-    reasons: HashMap<u16, DropReasons>,
+    reasons: &'static HashMap<u16, DropReasons>,
+    /// Cache of drop location addresses to their function names.
+    locations: HashMap<u64, Option<String>>,
+    /// Events whose drop reason is filtered out are discarded.
+    filter: DropReasonFilter,
 }
 
 impl RawEventSectionFactory for SkbDropEventFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>) -> Result<Box<dyn EventSection>> {
-        let raw = parse_single_raw_section::<skb_drop_event>(&raw_sections)?;
+        Ok(Box::new(self.unmarshall(&raw_sections)?))
+    }
 
-        let drop_reason = raw.drop_reason;
-        let (subsys, drop_reason) = self.get_reason(drop_reason);
+    fn create_filtered(
+        &mut self,
+        raw_sections: Vec<BpfRawSection>,
+    ) -> Result<Option<Box<dyn EventSection>>> {
+        let event = self.unmarshall(&raw_sections)?;
+        if !self
+            .filter
+            .keep(event.subsys.as_deref(), &event.drop_reason)
+        {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(event)))
+    }
 
-        Ok(Box::new(SkbDropEvent {
-            subsys,
-            drop_reason,
-        }))
+    fn filters(&self) -> bool {
+        !self.filter.is_empty()
     }
 }
 
 impl SkbDropEventFactory {
     /// Initialize a new skb drop factory.
-    pub(crate) fn new() -> Result<Self> {
+    pub(crate) fn new(filter: DropReasonFilter) -> Result<Self> {
+        Ok(Self {
+            reasons: Self::parse_drop_reasons()?,
+            locations: HashMap::new(),
+            filter,
+        })
+    }
+
+    fn unmarshall(&mut self, raw_sections: &[BpfRawSection]) -> Result<SkbDropEvent> {
+        let raw = parse_single_raw_section::<skb_drop_event>(raw_sections)?;
+
+        let drop_reason = raw.drop_reason;
+        let (subsys, drop_reason, reason_resolved) = self.get_reason(drop_reason);
+        let drop_location = self.get_location(raw.location);
+        // Tell drops from normal frees: either the skb:consume_skb tracepoint
+        // generated the event or the skb was freed with the consumed reason.
+        let consumed = raw.consumed != 0 || (subsys.is_none() && drop_reason == "CONSUMED");
+
+        Ok(SkbDropEvent {
+            subsys,
+            drop_reason,
+            reason_resolved,
+            drop_location,
+            consumed,
+            ct: None,
+        })
+    }
+
+    /// Retrieve the drop reason definitions from the kernel, if not done
+    /// already. Sub-systems whose reasons couldn't be retrieved have an empty
+    /// reason map.
+    pub(crate) fn parse_drop_reasons() -> Result<&'static HashMap<u16, DropReasons>> {
+        DROP_REASONS.get_or_try_init(Self::walk_drop_reasons)
+    }
+
+    fn walk_drop_reasons() -> Result<HashMap<u16, DropReasons>> {
+        #[cfg(test)]
+        DROP_REASONS_PARSED.fetch_add(1, Ordering::Relaxed);
+
         let subsys = parse_enum("skb_drop_reason_subsys", &["SKB_DROP_REASON_SUBSYS_"])?;
 
         // Parse each sub-system drop reasons.
@@ -119,16 +208,35 @@ impl SkbDropEventFactory {
             reasons.insert(0, DropReasons::from_subsystem("core")?);
         }
 
-        Ok(Self { reasons })
+        Ok(reasons)
+    }
+
+    /// Converts a raw drop location to the name of the function it belongs
+    /// to. Returns None if the location is unknown.
+    fn get_location(&mut self, addr: u64) -> Option<String> {
+        if addr == 0 {
+            return None;
+        }
+
+        self.locations
+            .entry(addr)
+            .or_insert_with(|| {
+                inspector()
+                    .and_then(|i| i.kernel.get_name_offt_from_addr_near(addr))
+                    .map(|(name, _)| name)
+                    .ok()
+            })
+            .clone()
     }
 
     /// Converts a raw drop reason value to a tuple of an optional sub-system
-    /// name and a string representation of the drop reason.
-    fn get_reason(&self, raw_val: i32) -> (Option<String>, String) {
+    /// name, a string representation of the drop reason and whether it was
+    /// resolved to its name.
+    fn get_reason(&self, raw_val: i32) -> (Option<String>, String, bool) {
         // Special case when drop reasons aren't supported by the kernel. Fake a
         // core NOT_SPECIFIED reason.
         if raw_val < 0 {
-            return (None, "NOT_SPECIFIED".to_string());
+            return (None, "NOT_SPECIFIED".to_string(), true);
         }
         let raw_val = raw_val as u32;
 
@@ -141,17 +249,221 @@ impl SkbDropEventFactory {
             // being defined in the sub-systems list.
             None => {
                 warn!("Unknown drop reason subsystem id ({subsys_id})");
-                return (Some(subsys_id.to_string()), raw_val.to_string());
+                return (
+                    Some(subsys_id.to_string()),
+                    Self::local_reason(raw_val),
+                    false,
+                );
             }
         };
 
-        // Looks genuine, generate a proper (subsys, drop reason) tuple.
-        (
-            subsys.subsys_name.clone(),
-            match subsys.reasons.get(&raw_val) {
-                Some(reason) => reason.clone(),
-                None => raw_val.to_string(),
+        // Looks genuine, generate a proper (subsys, drop reason) tuple. The
+        // reason map is empty if it couldn't be retrieved from the BTF.
+        match subsys.reasons.get(&raw_val) {
+            Some(reason) => (subsys.subsys_name.clone(), reason.clone(), true),
+            None => (
+                subsys.subsys_name.clone(),
+                Self::local_reason(raw_val),
+                false,
+            ),
+        }
+    }
+
+    /// String representation of an unknown drop reason, without its
+    /// sub-system bits as the sub-system is reported separately.
+    fn local_reason(raw_val: u32) -> String {
+        (raw_val & ((1 << SKB_DROP_REASON_SUBSYS_SHIFT) - 1)).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::raw_bytes;
+
+    /// Factory resolving the given core drop reasons, plus the reasons of the
+    /// `extra` sub-systems.
+    fn factory(
+        core: &[(u32, &str)],
+        extra: Vec<(u16, DropReasons)>,
+        filter: DropReasonFilter,
+    ) -> SkbDropEventFactory {
+        let mut reasons = HashMap::from([(
+            0,
+            DropReasons {
+                subsys_name: None,
+                reasons: core
+                    .iter()
+                    .map(|(val, name)| (*val, name.to_string()))
+                    .collect(),
             },
-        )
+        )]);
+        reasons.extend(extra);
+
+        SkbDropEventFactory {
+            reasons: Box::leak(Box::new(reasons)),
+            locations: HashMap::new(),
+            filter,
+        }
+    }
+
+    #[test]
+    fn drop_reasons() {
+        let factory = factory(
+            &[(2, "NO_SOCKET")],
+            vec![(
+                3,
+                DropReasons {
+                    subsys_name: Some("openvswitch".to_string()),
+                    reasons: HashMap::from([(3 << 16 | 1, "OVS_DROP_FLOW".to_string())]),
+                },
+            )],
+            DropReasonFilter::default(),
+        );
+
+        assert_eq!(factory.get_reason(2), (None, "NO_SOCKET".to_string(), true));
+        assert_eq!(
+            factory.get_reason(-1),
+            (None, "NOT_SPECIFIED".to_string(), true)
+        );
+        assert_eq!(
+            factory.get_reason(3 << 16 | 1),
+            (
+                Some("openvswitch".to_string()),
+                "OVS_DROP_FLOW".to_string(),
+                true
+            )
+        );
+        // Unknown reasons are reported relative to their sub-system.
+        assert_eq!(
+            factory.get_reason(3 << 16 | 42),
+            (Some("openvswitch".to_string()), "42".to_string(), false)
+        );
+        assert_eq!(
+            factory.get_reason(5 << 16 | 7),
+            (Some("5".to_string()), "7".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn consumed() -> Result<()> {
+        let mut factory = factory(
+            &[(2, "NO_SOCKET"), (3, "CONSUMED")],
+            Vec::new(),
+            DropReasonFilter::default(),
+        );
+        let mut display = |raw: skb_drop_event| -> Result<String> {
+            let data = raw_bytes(&raw);
+            let event = factory.create(vec![BpfRawSection {
+                data: &data,
+                ..Default::default()
+            }])?;
+            Ok(format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ))
+        };
+
+        let drop = |drop_reason, consumed| skb_drop_event {
+            drop_reason,
+            consumed,
+            ..Default::default()
+        };
+        assert_eq!(display(drop(2, 0))?, "drop (reason NO_SOCKET)");
+        assert_eq!(display(drop(3, 0))?, "free");
+        // skb:consume_skb, which has no reason.
+        assert_eq!(display(drop(-1, 1))?, "free");
+        // The distinction holds when reasons can't be resolved.
+        assert_eq!(display(drop(42, 1))?, "free");
+        assert_eq!(display(drop(42, 0))?, "drop (reason#42)");
+        Ok(())
+    }
+
+    #[test]
+    fn unresolved_reasons() -> Result<()> {
+        // Reasons couldn't be retrieved from the BTF.
+        let mut factory = factory(&[], Vec::new(), DropReasonFilter::default());
+        let mut unmarshall = |drop_reason| -> Result<SkbDropEvent> {
+            let raw = skb_drop_event {
+                drop_reason,
+                ..Default::default()
+            };
+            let data = raw_bytes(&raw);
+            factory.unmarshall(&[BpfRawSection {
+                data: &data,
+                ..Default::default()
+            }])
+        };
+
+        let event = unmarshall(22)?;
+        assert_eq!(event.drop_reason, "22");
+        assert!(!event.reason_resolved);
+        assert_eq!(
+            format!(
+                "{}",
+                event.display(&DisplayFormat::new(), &FormatterConf::new())
+            ),
+            "drop (reason#22)"
+        );
+        // Unsupported drop reasons are faked, thus resolved.
+        assert!(unmarshall(-1)?.reason_resolved);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_reason_filter() -> Result<()> {
+        let to_vec = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let filter = DropReasonFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.keep(None, "NO_SOCKET"));
+
+        let filter = DropReasonFilter::new(
+            &to_vec(&["TCP_*", "openvswitch/*"]),
+            &to_vec(&["TCP_CSUM", "openvswitch/OVS_DROP_LAST_ACTION"]),
+        );
+        assert!(filter.keep(None, "TCP_INVALID"));
+        assert!(!filter.keep(None, "TCP_CSUM"));
+        assert!(!filter.keep(None, "NO_SOCKET"));
+        assert!(filter.keep(Some("openvswitch"), "OVS_DROP_FLOW"));
+        assert!(!filter.keep(Some("openvswitch"), "OVS_DROP_LAST_ACTION"));
+
+        // Filtered events are not created.
+        let mut factory = factory(
+            &[(2, "NO_SOCKET"), (4, "TCP_INVALID")],
+            Vec::new(),
+            DropReasonFilter::new(&to_vec(&["TCP_*", "IP_*"]), &[]),
+        );
+        assert!(factory.filters());
+        let mut create = |drop_reason| -> Result<Option<String>> {
+            let raw = skb_drop_event {
+                drop_reason,
+                ..Default::default()
+            };
+            let data = raw_bytes(&raw);
+            Ok(factory
+                .create_filtered(vec![BpfRawSection {
+                    data: &data,
+                    ..Default::default()
+                }])?
+                .map(|e| {
+                    format!(
+                        "{}",
+                        e.display(&DisplayFormat::new(), &FormatterConf::new())
+                    )
+                }))
+        };
+        assert_eq!(create(4)?.as_deref(), Some("drop (reason TCP_INVALID)"));
+        assert_eq!(create(2)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_reasons_cache() -> Result<()> {
+        let first = SkbDropEventFactory::new(DropReasonFilter::default())?;
+        let second = SkbDropEventFactory::new(DropReasonFilter::default())?;
+        assert!(std::ptr::eq(first.reasons, second.reasons));
+        assert_eq!(DROP_REASONS_PARSED.load(Ordering::Relaxed), 1);
+        Ok(())
     }
 }
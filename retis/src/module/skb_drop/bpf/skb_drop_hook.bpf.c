@@ -4,19 +4,48 @@
 
 #include <common.h>
 
+/* Hook configuration.
+ *
+ * kfree_skb_ksym: address of the skb:kfree_skb tracepoint. Its second
+ *		   parameter is the location of the drop.
+ * consume_skb_ksym: address of the skb:consume_skb tracepoint, 0 if not
+ *		     found. Events it generates are normal frees, not drops.
+ */
+struct skb_drop_config {
+	u64 kfree_skb_ksym;
+	u64 consume_skb_ksym;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct skb_drop_config);
+} skb_drop_config_map SEC(".maps");
+
 struct skb_drop_event {
+	u64 location;
 	s32 drop_reason;
+	/* The skb was consumed (freed normally), not dropped. */
+	u8 consumed;
 } __binding;
 
 DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct skb_drop_config *cfg;
 	struct skb_drop_event *e;
+	bool consumed;
+	u32 zero = 0;
+
+	cfg = bpf_map_lookup_elem(&skb_drop_config_map, &zero);
+	consumed = cfg && cfg->consume_skb_ksym &&
+		   cfg->consume_skb_ksym == ctx->ksym;
 
 	/* Check if the kernel knows about skb drop reasons, and if so check we
 	 * can retrieve it. This should be the common case. In case the kernel
 	 * doesn't know skb drop reasons, this hook will generate fake events
-	 * and will only be attached to specific hooks.
+	 * and will only be attached to specific hooks. skb:consume_skb does
+	 * not have a drop reason but its events are still reported, as frees.
 	 */
-	if (bpf_core_type_exists(enum skb_drop_reason) &&
+	if (!consumed && bpf_core_type_exists(enum skb_drop_reason) &&
 	    !retis_arg_valid(ctx, skb_drop_reason))
 		return 0;
 
@@ -24,9 +53,14 @@ DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
 	if (!e)
 		return 0;
 
-	e->drop_reason = bpf_core_type_exists(enum skb_drop_reason) ?
+	e->consumed = consumed;
+	e->drop_reason = !consumed && bpf_core_type_exists(enum skb_drop_reason) ?
 		retis_get_skb_drop_reason(ctx) : -1;
 
+	/* The drop location is only known when hooked to skb:kfree_skb. */
+	e->location = cfg && cfg->kfree_skb_ksym == ctx->ksym ?
+		retis_get_param(ctx, 1, u64) : 0;
+
 	return 0;
 )
 
@@ -1,10 +1,19 @@
-use std::sync::Arc;
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd},
+    sync::Arc,
+};
 
 use anyhow::{bail, Result};
+use clap::Parser;
 use log::warn;
 
-use super::{bpf::SkbDropEventFactory, skb_drop_hook};
+use super::{
+    bpf::{DropReasonFilter, SkbDropEventFactory},
+    skb_drop_hook,
+};
 use crate::{
+    bindings::skb_drop_hook_uapi::skb_drop_config,
     cli::{dynamic::DynamicCommand, CliConfig},
     collect::Collector,
     core::{
@@ -17,14 +26,63 @@ use crate::{
     module::Module,
 };
 
+#[derive(Parser, Default)]
+pub(crate) struct SkbDropCollectorArgs {
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma separated list of drop reasons whose events are collected, other ones being
+discarded. Reasons can be given with their subsystem (e.g. openvswitch/OVS_DROP_FLOW) and end with
+a '*' to match a prefix (e.g. TCP_*)."
+    )]
+    skb_drop_reasons: Vec<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma separated list of drop reasons whose events are discarded. Uses the same
+format as --skb-drop-reasons."
+    )]
+    skb_drop_ignore_reasons: Vec<String>,
+}
+
 pub(crate) struct SkbDropModule {
     reasons_available: bool,
+    /// Drop reasons whose events are collected, all if empty.
+    allow_reasons: Vec<String>,
+    /// Drop reasons whose events are discarded.
+    ignore_reasons: Vec<String>,
+    // Used to keep a reference to our internal config map.
+    #[allow(dead_code)]
+    config_map: Option<libbpf_rs::MapHandle>,
+}
+
+impl SkbDropModule {
+    fn config_map() -> Result<libbpf_rs::MapHandle> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/skb_drop_hook.bpf.c
+        libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Array,
+            Some("skb_drop_config_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<skb_drop_config>() as u32,
+            1,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the skb drop config map: {}", e))
+    }
 }
 
 impl Collector for SkbDropModule {
     fn new() -> Result<Self> {
         Ok(Self {
             reasons_available: true,
+            allow_reasons: Vec::new(),
+            ignore_reasons: Vec::new(),
+            config_map: None,
         })
     }
 
@@ -37,7 +95,7 @@ impl Collector for SkbDropModule {
     }
 
     fn register_cli(&self, cmd: &mut DynamicCommand) -> Result<()> {
-        cmd.register_module_noargs(SectionId::SkbDrop)
+        cmd.register_module::<SkbDropCollectorArgs>(SectionId::SkbDrop)
     }
 
     fn can_run(&mut self, _: &CliConfig) -> Result<()> {
@@ -76,12 +134,34 @@ impl Collector for SkbDropModule {
 
     fn init(
         &mut self,
-        _: &CliConfig,
+        cli: &CliConfig,
         probes: &mut ProbeBuilderManager,
         _: Arc<RetisEventsFactory>,
     ) -> Result<()> {
-        let mut probe = Probe::raw_tracepoint(Symbol::from_name("skb:kfree_skb")?)?;
-        let hook = Hook::from(skb_drop_hook::DATA);
+        let args = cli.get_section::<SkbDropCollectorArgs>(SectionId::SkbDrop)?;
+        self.allow_reasons = args.skb_drop_reasons;
+        self.ignore_reasons = args.skb_drop_ignore_reasons;
+
+        let symbol = Symbol::from_name("skb:kfree_skb")?;
+
+        // Let the hook know where skb:kfree_skb is, to retrieve the drop
+        // location there.
+        let config_map = Self::config_map()?;
+        let cfg = skb_drop_config {
+            kfree_skb_ksym: symbol.addr()?,
+            // Events from skb:consume_skb, if probed, are reported as frees.
+            consume_skb_ksym: Symbol::from_name("skb:consume_skb")
+                .and_then(|s| s.addr())
+                .unwrap_or(0),
+        };
+        let cfg = unsafe { plain::as_bytes(&cfg) };
+        let key = 0_u32.to_ne_bytes();
+        config_map.update(&key, cfg, libbpf_rs::MapFlags::empty())?;
+
+        let mut probe = Probe::raw_tracepoint(symbol)?;
+        let hook = Hook::from(skb_drop_hook::DATA)
+            .reuse_map("skb_drop_config_map", config_map.as_fd().as_raw_fd())?
+            .to_owned();
 
         if self.reasons_available {
             probes.register_kernel_hook(hook)?;
@@ -96,6 +176,7 @@ impl Collector for SkbDropModule {
             bail!("Could not attach to skb:kfree_skb: {}", e);
         }
 
+        self.config_map = Some(config_map);
         Ok(())
     }
 }
@@ -105,6 +186,8 @@ impl Module for SkbDropModule {
         self
     }
     fn section_factory(&self) -> Result<Option<Box<dyn EventSectionFactory>>> {
-        Ok(Some(Box::new(SkbDropEventFactory::new()?)))
+        Ok(Some(Box::new(SkbDropEventFactory::new(
+            DropReasonFilter::new(&self.allow_reasons, &self.ignore_reasons),
+        )?)))
     }
 }
@@ -15,3 +15,9 @@ pub(crate) use python::*;
 
 pub(crate) mod sort;
 pub(crate) use sort::*;
+
+pub(crate) mod summary;
+pub(crate) use summary::*;
+
+pub(crate) mod validate;
+pub(crate) use validate::*;
@@ -16,9 +16,20 @@ use crate::{
     },
     helpers::signals::Running,
     module::Modules,
-    process::display::*,
+    process::{display::*, flow_ops::*, ovs_batch::*, series::SeriesOrderCheck},
 };
 
+/// Does the event represent a modification of the packet? Startup events are always kept, as
+/// they are needed to format the others.
+fn is_mutating(event: &Event) -> bool {
+    event
+        .get_section::<OvsEvent>(SectionId::Ovs)
+        .is_some_and(|ovs| ovs.is_mutating())
+        || event
+            .get_section::<StartupEvent>(SectionId::Startup)
+            .is_some()
+}
+
 /// Print stored events to stdout
 #[derive(Parser, Debug, Default)]
 #[command(name = "print")]
@@ -29,8 +40,61 @@ pub(crate) struct Print {
     #[arg(long, help = "Format used when printing an event.")]
     #[clap(value_enum, default_value_t=CliDisplayFormat::MultiLine)]
     pub(super) format: CliDisplayFormat,
-    #[arg(long, help = "Print the time as UTC")]
+    #[arg(long, help = "Print the time as UTC", conflicts_with = "time_format")]
     pub(super) utc: bool,
+    #[arg(long, help = "Format used when printing the time of an event.")]
+    #[clap(value_enum, default_value_t=CliTimeFormat::Monotonic)]
+    pub(super) time_format: CliTimeFormat,
+    #[arg(long, help = "How much details are displayed for each event.")]
+    #[clap(value_enum, default_value_t=CliVerbosity::Normal)]
+    pub(super) verbosity: CliVerbosity,
+    #[arg(
+        long,
+        help = "Prefix fields with the name of the module that generated them (e.g. ovs.action=output),
+for events composed by multiple modules."
+    )]
+    pub(super) module_prefix: bool,
+    #[arg(
+        long,
+        help = "Only print events generated at or after this monotonic timestamp (in ns). Events
+without a timestamp are always printed."
+    )]
+    pub(super) since: Option<u64>,
+    #[arg(
+        long,
+        help = "Only print events generated at or before this monotonic timestamp (in ns). Events
+without a timestamp are always printed."
+    )]
+    pub(super) until: Option<u64>,
+    #[arg(
+        long,
+        help = "Only print events which modified the packet (e.g. OvS set, push/pop, ct or dec_ttl
+actions), discarding the ones which only observed it."
+    )]
+    pub(super) mutating_only: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma separated list of drop reasons expected on the system (e.g. TCP_INVALID, or
+openvswitch/OVS_DROP_LAST_ACTION to also match the subsystem), whose events are not printed. They are
+still stored and accounted for by summaries; the number of events not printed is reported at the end."
+    )]
+    pub(super) quiet_known: Vec<String>,
+    #[arg(
+        long,
+        value_name = "INTERVAL_MS",
+        help = "Do not print OvS flow operation events individually but report their number, by
+operation type and upcall queue, over windows of INTERVAL_MS milliseconds (of event time). A value
+of 0 reports a single count at the end."
+    )]
+    pub(super) aggregate_flow_ops: Option<u64>,
+    #[arg(
+        long,
+        conflicts_with = "aggregate_flow_ops",
+        help = "Group OvS upcall receive and flow operation events by userspace batch, printing each
+batch once complete: every received upcall followed by the flow operations it triggered."
+    )]
+    pub(super) ovs_batches: bool,
 }
 
 impl SubCommandParserRunner for Print {
@@ -41,6 +105,7 @@ impl SubCommandParserRunner for Print {
 
         // Create event factory.
         let mut factory = FileEventsFactory::new(self.input.as_path())?;
+        factory.set_time_range(self.since, self.until);
 
         // Format.
         let format = DisplayFormat::new()
@@ -48,36 +113,149 @@ impl SubCommandParserRunner for Print {
             .time_format(if self.utc {
                 TimeFormat::UtcDate
             } else {
-                TimeFormat::MonotonicTimestamp
-            });
+                self.time_format.into()
+            })
+            .module_prefix(self.module_prefix)
+            .verbosity(self.verbosity.into());
 
         match factory.file_type() {
             FileType::Event => {
                 // Formatter & printer for events.
                 let mut event_output =
-                    PrintEvent::new(Box::new(stdout()), PrintEventFormat::Text(format));
+                    PrintEvent::new(Box::new(stdout()), PrintEventFormat::Text(format))
+                        .with_quiet_drops(&self.quiet_known);
+                let mut flow_ops = self
+                    .aggregate_flow_ops
+                    .map(FlowOpsAggregator::from_interval_ms)
+                    .transpose()?;
+                let mut batcher = self
+                    .ovs_batches
+                    .then(|| OvsBatcher::new(OVS_BATCH_TIMEOUT_NS));
 
                 while run.running() {
                     match factory.next_event()? {
+                        Some(event) if self.mutating_only && !is_mutating(&event) => continue,
+                        Some(event) if flow_ops.is_some() && is_flow_op(&event) => {
+                            let flushed = flow_ops.as_mut().and_then(|f| f.record(&event));
+                            write_flow_ops(event_output.writer(), flushed)?;
+                        }
+                        Some(event) if batcher.is_some() && is_batch_event(&event) => {
+                            let done = batcher
+                                .as_mut()
+                                .map(|b| b.process_one(&event))
+                                .unwrap_or_default();
+                            write_ovs_batches(event_output.writer(), done, &format)?;
+                        }
                         Some(event) => event_output.process_one(&event)?,
                         None => break,
                     }
                 }
+                if let Some(flow_ops) = flow_ops.as_mut() {
+                    write_flow_ops(event_output.writer(), flow_ops.flush())?;
+                }
+                if let Some(batcher) = batcher.as_mut() {
+                    write_ovs_batches(event_output.writer(), batcher.flush(), &format)?;
+                }
+                event_output.flush()?;
             }
             FileType::Series => {
                 // Formatter & printer for series.
                 let mut series_output =
                     PrintSeries::new(Box::new(stdout()), PrintEventFormat::Text(format));
+                let mut quiet = QuietDrops::new(&self.quiet_known);
+                let mut order = SeriesOrderCheck::new();
 
                 while run.running() {
                     match factory.next_series()? {
-                        Some(series) => series_output.process_one(&series)?,
+                        Some(mut series) => {
+                            order.check(&series);
+                            if self.mutating_only {
+                                series.events.retain(is_mutating);
+                            }
+                            series.events.retain(|e| quiet.filter(e));
+                            if series.events.is_empty() {
+                                continue;
+                            }
+                            series_output.process_one(&series)?
+                        }
                         None => break,
                     }
                 }
+                series_output.flush()?;
+                write_quiet(&mut stdout(), quiet.take_suppressed())?;
+                order.report();
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io::Write, rc::Rc};
+
+    use super::*;
+    use crate::helpers::testing::ovs_event;
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn mutating_only_utc() {
+        let mut startup = Event::new();
+        startup
+            .insert_section(SectionId::Common, Box::new(CommonEvent::default()))
+            .unwrap();
+        startup
+            .insert_section(
+                SectionId::Startup,
+                Box::new(StartupEvent {
+                    retis_version: "test".to_string(),
+                    clock_monotonic_offset: TimeSpec::new(1_700_000_000, 0),
+                    session_id: None,
+                    clock_source: None,
+                }),
+            )
+            .unwrap();
+        let action = |ts, action| {
+            ovs_event(
+                ts,
+                OvsEventType::Action(ActionEvent {
+                    action: Some(action),
+                    ..Default::default()
+                }),
+            )
+        };
+        let events = [
+            startup,
+            action(1_000_000_000, OvsAction::Output(OvsActionOutput::default())),
+            action(1_500_000_042, OvsAction::Ct(OvsActionCt::default())),
+        ];
+
+        let out = SharedWriter::default();
+        let mut printer = PrintEvent::new(
+            Box::new(out.clone()),
+            PrintEventFormat::Text(DisplayFormat::new().time_format(TimeFormat::UtcDate)),
+        );
+        let kept = events.iter().filter(|e| is_mutating(e)).collect::<Vec<_>>();
+        assert_eq!(kept.len(), 2);
+        kept.iter()
+            .try_for_each(|e| printer.process_one(e))
+            .unwrap();
+
+        let out = String::from_utf8(out.0.borrow().clone()).unwrap();
+        assert!(out.contains("2023-11-14 22:13:21.500000"));
+        assert!(!out.contains("1500000042"));
+    }
+}
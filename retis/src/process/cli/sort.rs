@@ -16,7 +16,14 @@ use crate::{
     events::{file::FileEventsFactory, *},
     helpers::signals::Running,
     module::Modules,
-    process::{display::*, series::EventSorter, tracking::AddTracking},
+    process::{
+        ct_join::CtDropJoin,
+        display::*,
+        series::{EventSorter, SeriesOrderCheck},
+        tracking::AddTracking,
+        trunc::TruncAttribution,
+        upcall_latency::UpcallLatency,
+    },
 };
 
 /// The default size of the sorting buffer
@@ -27,6 +34,9 @@ const DEFAULT_BUFFER: usize = 1000;
 /// Reads events from the INPUT file and arranges them by tracking id. The output is a number of
 /// "event sets". An event set is a list of events that share the same tracking id (i.e: belong to
 /// the same packet).
+///
+/// Netfilter drops are enriched with the conntrack state of their connection, when conntrack
+/// events are available.
 #[derive(Parser, Debug, Default)]
 #[command(name = "sort")]
 pub(crate) struct Sort {
@@ -58,8 +68,29 @@ pub(crate) struct Sort {
     pub(super) format: CliDisplayFormat,
 
     /// Print the time as UTC.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "time_format")]
     pub(super) utc: bool,
+
+    /// Format used when printing the time of an event.
+    #[arg(long)]
+    #[clap(value_enum, default_value_t=CliTimeFormat::Monotonic)]
+    pub(super) time_format: CliTimeFormat,
+
+    /// How much details are displayed for each event.
+    #[arg(long)]
+    #[clap(value_enum, default_value_t=CliVerbosity::Normal)]
+    pub(super) verbosity: CliVerbosity,
+
+    /// Prefix fields with the name of the module that generated them (e.g. ovs.action=output),
+    /// for events composed by multiple modules.
+    #[arg(long)]
+    pub(super) module_prefix: bool,
+
+    /// Print the events of each packet in a block headed by its tracking id, and the events that
+    /// can't be attributed to a packet in "unattributed" sections. Those are buffered too, up to
+    /// --max-buffer events, and written at the end or when the buffer is full.
+    #[arg(long)]
+    pub(super) per_packet: bool,
 }
 
 impl SubCommandParserRunner for Sort {
@@ -73,11 +104,23 @@ impl SubCommandParserRunner for Sort {
 
         if matches!(factory.file_type(), file::FileType::Series) {
             log::info!("File already sorted");
+            // The file is not sorted again, but check it actually was.
+            let mut order = SeriesOrderCheck::new();
+            while run.running() {
+                match factory.next_series()? {
+                    Some(series) => order.check(&series),
+                    None => break,
+                }
+            }
+            order.report();
             return Ok(());
         }
 
         let mut series = EventSorter::new();
         let mut tracker = AddTracking::new();
+        let mut ct_join = CtDropJoin::new();
+        let mut trunc = TruncAttribution::new();
+        let mut upcall_latency = UpcallLatency::new();
         let mut printers = Vec::new();
 
         if let Some(out) = &self.out {
@@ -113,13 +156,17 @@ impl SubCommandParserRunner for Sort {
                 .time_format(if self.utc {
                     TimeFormat::UtcDate
                 } else {
-                    TimeFormat::MonotonicTimestamp
-                });
-
-            printers.push(PrintSeries::new(
-                Box::new(stdout()),
-                PrintEventFormat::Text(format),
-            ));
+                    self.time_format.into()
+                })
+                .module_prefix(self.module_prefix)
+                .verbosity(self.verbosity.into());
+
+            let printer = PrintSeries::new(Box::new(stdout()), PrintEventFormat::Text(format));
+            printers.push(if self.per_packet {
+                printer.with_per_packet(self.max_buffer)
+            } else {
+                printer
+            });
         }
 
         while run.running() {
@@ -127,6 +174,12 @@ impl SubCommandParserRunner for Sort {
                 Some(mut event) => {
                     // Add tracking information
                     tracker.process_one(&mut event)?;
+                    // Explain netfilter drops using conntrack information
+                    ct_join.process_one(&mut event);
+                    // Attribute OvS truncations to the packet copies they apply to
+                    trunc.process_one(&mut event);
+                    // Measure OvS upcall enqueue latencies
+                    upcall_latency.process_one(&mut event);
 
                     // Add to sorter
                     series.add(event);
@@ -159,6 +212,21 @@ impl SubCommandParserRunner for Sort {
 
         // Flush writers
         printers.iter_mut().try_for_each(|p| p.flush())?;
+
+        let (evicted, pending) = (upcall_latency.evicted(), upcall_latency.pending() as u64);
+        if evicted + pending > 0 {
+            log::info!(
+                "{} upcall(s) without a matching return event ({evicted} evicted, {pending} pending)",
+                evicted + pending
+            );
+        }
+        if ct_join.unmatched() > 0 {
+            log::info!(
+                "{} netfilter drop(s) without matching conntrack information ({} conntrack tuple(s) evicted)",
+                ct_join.unmatched(),
+                ct_join.evicted()
+            );
+        }
         Ok(())
     }
 }
@@ -0,0 +1,106 @@
+//! # Summary
+//!
+//! Summary is a post-processing command aggregating stored events to give an overview of a
+//! capture.
+
+use std::{io::stdout, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{
+    cli::*,
+    events::file::FileEventsFactory,
+    helpers::signals::Running,
+    module::Modules,
+    process::{
+        summary::{
+            CpuSummary, DropSummary, FailedActionsSummary, FailedOpsSummary, FlowInstallLatency,
+            OvsRateSummary, QueueFullDetector, RecircCycles, UpcallPortSummary,
+            QUEUE_FULL_THRESHOLD_DEFAULT,
+        },
+        top_n::TOP_N_CAPACITY_DEFAULT,
+    },
+};
+
+/// Print a summary of stored events.
+///
+/// Drops are aggregated by location (the kernel function which dropped the packets) and then by
+/// drop reason. The rates of OvS upcalls and flow operations are reported too, as well as the ports
+/// generating the most upcalls, the flow install latency (from a flow miss upcall to its flow put
+/// operation), the flow operations and action executions which failed in the kernel, the upcall
+/// queues which were persistently full, the cycles in the recirculation graph and the number of
+/// events per CPU.
+#[derive(Parser, Debug, Default)]
+#[command(name = "summary")]
+pub(crate) struct Summary {
+    /// File from which to read events.
+    #[arg(default_value = "retis.data")]
+    pub(super) input: PathBuf,
+
+    /// Number of entries to display at each level of the breakdown.
+    #[arg(long, default_value_t = 10)]
+    pub(super) top: usize,
+
+    /// Maximum number of entries (e.g. drop reasons, ports) tracked by each top-N aggregation,
+    /// which bounds memory usage. Once more entries than this were seen counts are estimates,
+    /// overestimated by at most the number of events divided by this capacity.
+    #[arg(long, default_value_t = TOP_N_CAPACITY_DEFAULT)]
+    pub(super) top_capacity: usize,
+
+    /// Size of the time windows used to compute rates, in milliseconds.
+    #[arg(long, default_value_t = 1000)]
+    pub(super) window: u64,
+
+    /// Minimum number of consecutive upcall enqueue failures with ENOBUFS on the same queue
+    /// (i.e. upcall port) for it to be reported as persistently full.
+    #[arg(long, default_value_t = QUEUE_FULL_THRESHOLD_DEFAULT)]
+    pub(super) queue_full_threshold: u64,
+}
+
+impl SubCommandParserRunner for Summary {
+    fn run(&mut self, _: Modules) -> Result<()> {
+        // Create running instance that will handle signal termination.
+        let run = Running::new();
+        run.register_term_signals()?;
+
+        // Create event factory.
+        let mut factory = FileEventsFactory::new(self.input.as_path())?;
+        let mut drops = DropSummary::new(self.top_capacity);
+        let mut upcall_ports = UpcallPortSummary::new(self.top_capacity);
+        let mut ovs_rates = OvsRateSummary::new(self.window.saturating_mul(1_000_000));
+        let mut flow_install = FlowInstallLatency::new();
+        let mut failed_ops = FailedOpsSummary::new();
+        let mut failed_actions = FailedActionsSummary::new();
+        let mut queue_full = QueueFullDetector::new(self.queue_full_threshold);
+        let mut recirc_cycles = RecircCycles::new();
+        let mut cpus = CpuSummary::new();
+
+        while run.running() {
+            match factory.next_event()? {
+                Some(event) => {
+                    drops.process_one(&event);
+                    ovs_rates.process_one(&event);
+                    upcall_ports.process_one(&event);
+                    flow_install.process_one(&event);
+                    failed_ops.process_one(&event);
+                    failed_actions.process_one(&event);
+                    queue_full.process_one(&event);
+                    recirc_cycles.process_one(&event);
+                    cpus.process_one(&event);
+                }
+                None => break,
+            }
+        }
+
+        drops.write(&mut stdout(), self.top)?;
+        ovs_rates.write(&mut stdout())?;
+        upcall_ports.write(&mut stdout(), self.top)?;
+        flow_install.write(&mut stdout())?;
+        failed_ops.write(&mut stdout())?;
+        failed_actions.write(&mut stdout())?;
+        queue_full.write(&mut stdout())?;
+        recirc_cycles.write(&mut stdout())?;
+        cpus.write(&mut stdout())
+    }
+}
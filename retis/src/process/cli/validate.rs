@@ -0,0 +1,82 @@
+//! # Validate
+//!
+//! Validate checks the integrity of stored events, e.g. before analyzing them.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    cli::*,
+    events::file::{FileEventsFactory, FileType},
+    module::Modules,
+};
+
+/// Check a file of stored events for corruption or truncation.
+///
+/// Every event is checked, as well as the file index if any. A report is printed and the command
+/// fails if the file is corrupted. Sections unknown to this version of Retis are reported but are
+/// not considered errors.
+#[derive(Parser, Debug, Default)]
+#[command(name = "validate")]
+pub(crate) struct Validate {
+    /// File from which to read events.
+    #[arg(default_value = "retis.data")]
+    pub(super) input: PathBuf,
+}
+
+impl SubCommandParserRunner for Validate {
+    fn run(&mut self, _: Modules) -> Result<()> {
+        let report = FileEventsFactory::validate(self.input.as_path())?;
+
+        match report.file_type {
+            Some(FileType::Event) => println!("{} event(s)", report.events),
+            Some(FileType::Series) => {
+                println!("{} event(s) in {} series", report.events, report.series)
+            }
+            None => (),
+        }
+        if let (Some(first), Some(last)) = (report.first_ts, report.last_ts) {
+            println!("timestamps: {first} - {last}");
+        }
+        println!("index: {}", if report.indexed { "yes" } else { "no" });
+        if report.checksums > 0 {
+            println!("checksums: {} verified", report.checksums);
+        }
+        if report.is_valid() {
+            for session in FileEventsFactory::sessions(self.input.as_path())?.iter() {
+                match session.id {
+                    Some(id) => print!("session {id:016x}"),
+                    None => print!("session (no id)"),
+                }
+                print!(": {} event(s)", session.events);
+                if let Some(version) = &session.retis_version {
+                    print!(", Retis version {version}");
+                }
+                if let Some(clock) = session.clock_source {
+                    print!(", clock {clock}");
+                }
+                println!();
+            }
+        }
+        for (section, count) in report.unknown_sections.iter() {
+            println!("unknown section (preserved): {section} ({count} event(s))");
+        }
+        for gap in report.gaps.iter() {
+            println!("gap: {gap}");
+        }
+        for error in report.errors.iter() {
+            println!("error: {error}");
+        }
+
+        if !report.is_valid() {
+            bail!(
+                "{} is corrupted ({} error(s))",
+                self.input.display(),
+                report.errors.len()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,447 @@
+//! Conntrack join processor.
+//!
+//! Packets dropped by netfilter are usually explained by their conntrack state. When the ct
+//! module is used, the conntrack events seen so far are indexed by 5-tuple and used to enrich
+//! netfilter drops of packets belonging to the same connection, in any direction.
+//!
+//! OVS ct actions are also given a correlation key, made of their zone and the 5-tuple of the
+//! packet (when the skb module is used), for them to be joined with conntrack entries.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::events::*;
+
+/// Default maximum number of indexed tuples, i.e. both directions of 8192 connections.
+const CT_JOIN_MAX_TUPLES: usize = 16384;
+
+/// Connection 5-tuple, as seen in the original direction of a packet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct FiveTuple {
+    src: String,
+    dst: String,
+    /// IP protocol.
+    proto: u8,
+    sport: u16,
+    dport: u16,
+}
+
+impl FiveTuple {
+    fn from_ct(tuple: &CtTuple) -> Self {
+        let (proto, sport, dport) = match &tuple.proto {
+            CtProto::Tcp(tcp) => (6, tcp.sport, tcp.dport),
+            CtProto::Udp(udp) => (17, udp.sport, udp.dport),
+            CtProto::Icmp(_) => (1, 0, 0),
+        };
+        Self {
+            src: tuple.ip.src.clone(),
+            dst: tuple.ip.dst.clone(),
+            proto,
+            sport,
+            dport,
+        }
+    }
+
+    fn from_skb(skb: &SkbEvent) -> Option<Self> {
+        let ip = skb.ip.as_ref()?;
+        let (sport, dport) = match (&skb.tcp, &skb.udp) {
+            (Some(tcp), _) => (tcp.sport, tcp.dport),
+            (_, Some(udp)) => (udp.sport, udp.dport),
+            _ => (0, 0),
+        };
+        Some(Self {
+            src: ip.saddr.clone(),
+            dst: ip.daddr.clone(),
+            proto: ip.protocol,
+            sport,
+            dport,
+        })
+    }
+}
+
+/// Is a drop reason related to netfilter or conntrack?
+fn is_ct_reason(reason: &str) -> bool {
+    reason.starts_with("NETFILTER") || reason.starts_with("NF_") || reason.contains("CONNTRACK")
+}
+
+/// CtDropJoin enriches netfilter drop events with the conntrack state of the connection they
+/// belong to.
+pub(crate) struct CtDropJoin {
+    /// Last conntrack information seen for a tuple, and when it was seen. Both the original and
+    /// reply tuples of a connection are indexed.
+    conns: HashMap<FiveTuple, (u64, SkbDropCt)>,
+    /// Tuples indexed by when their conntrack information was last seen. The least recently
+    /// seen tuples are evicted first.
+    last_seen: BTreeMap<u64, FiveTuple>,
+    /// Counter ordering the conntrack information.
+    seq: u64,
+    /// Maximum number of indexed tuples.
+    max_tuples: usize,
+    /// Number of tuples evicted.
+    evicted: u64,
+    /// Number of netfilter drops without a matching conntrack event.
+    unmatched: u64,
+    /// Collection session of the events being processed. Events of different sessions are never
+    /// joined: the conntrack information is dropped when a new session starts.
+    session_id: Option<u64>,
+}
+
+impl CtDropJoin {
+    pub(crate) fn new() -> Self {
+        Self::with_max_tuples(CT_JOIN_MAX_TUPLES)
+    }
+
+    pub(crate) fn with_max_tuples(max_tuples: usize) -> Self {
+        Self {
+            conns: HashMap::new(),
+            last_seen: BTreeMap::new(),
+            seq: 0,
+            max_tuples,
+            evicted: 0,
+            unmatched: 0,
+            session_id: None,
+        }
+    }
+
+    /// Process one event: index its conntrack information, if any, and enrich it if it is a
+    /// netfilter drop. Drops with no matching conntrack information are left untouched.
+    pub(crate) fn process_one(&mut self, event: &mut Event) {
+        let session_id = event
+            .get_section::<CommonEvent>(SectionId::Common)
+            .and_then(|c| c.session_id);
+        if session_id != self.session_id {
+            self.conns.clear();
+            self.last_seen.clear();
+            self.session_id = session_id;
+        }
+
+        Self::set_ovs_ct_key(event);
+
+        if let Some(ct) = event.get_section::<CtEvent>(SectionId::Ct) {
+            let info = SkbDropCt {
+                state: ct.state.clone(),
+                zone_id: ct.base.zone_id,
+                tcp_state: ct.base.tcp_state.clone(),
+            };
+            self.insert(FiveTuple::from_ct(&ct.base.reply), info.clone());
+            self.insert(FiveTuple::from_ct(&ct.base.orig), info);
+        }
+
+        match event.get_section::<SkbDropEvent>(SectionId::SkbDrop) {
+            Some(drop) if drop.ct.is_none() && is_ct_reason(&drop.drop_reason) => (),
+            _ => return,
+        }
+
+        let info = event
+            .get_section::<SkbEvent>(SectionId::Skb)
+            .and_then(FiveTuple::from_skb)
+            .and_then(|tuple| self.conns.get(&tuple))
+            .map(|(_, info)| info);
+        match (
+            info,
+            event.get_section_mut::<SkbDropEvent>(SectionId::SkbDrop),
+        ) {
+            (Some(info), Some(drop)) => drop.ct = Some(info.clone()),
+            _ => self.unmatched += 1,
+        }
+    }
+
+    /// Index the conntrack information of a tuple, evicting the least recently seen tuple if
+    /// too many are indexed.
+    fn insert(&mut self, tuple: FiveTuple, info: SkbDropCt) {
+        self.seq += 1;
+        if let Some((seq, _)) = self.conns.insert(tuple.clone(), (self.seq, info)) {
+            self.last_seen.remove(&seq);
+        }
+        self.last_seen.insert(self.seq, tuple);
+
+        if self.conns.len() > self.max_tuples {
+            if let Some((_, tuple)) = self.last_seen.pop_first() {
+                self.conns.remove(&tuple);
+                self.evicted += 1;
+            }
+        }
+    }
+
+    /// Set the correlation key of OVS ct action events, from the packet headers.
+    fn set_ovs_ct_key(event: &mut Event) {
+        let zone_id = match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event:
+                    OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(ct)),
+                        ..
+                    }),
+            }) => ct.zone_id,
+            _ => return,
+        };
+        let key = match event.get_section::<SkbEvent>(SectionId::Skb) {
+            Some(skb) => OvsCtKey::from_skb(zone_id, skb),
+            None => return,
+        };
+
+        if let Some(OvsEvent {
+            event:
+                OvsEventType::Action(ActionEvent {
+                    action: Some(OvsAction::Ct(ct)),
+                    ..
+                }),
+        }) = event.get_section_mut::<OvsEvent>(SectionId::Ovs)
+        {
+            ct.ct_key = key;
+        }
+    }
+
+    /// Number of netfilter drops for which no conntrack information was found.
+    pub(crate) fn unmatched(&self) -> u64 {
+        self.unmatched
+    }
+
+    /// Number of tuples evicted before the end of their session.
+    pub(crate) fn evicted(&self) -> u64 {
+        self.evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuple(src: &str, dst: &str, sport: u16, dport: u16) -> CtTuple {
+        CtTuple {
+            ip: CtIp {
+                src: src.to_string(),
+                dst: dst.to_string(),
+                version: CtIpVersion::V4,
+            },
+            proto: CtProto::Tcp(CtTcp { sport, dport }),
+        }
+    }
+
+    fn ct_event() -> Event {
+        conn_event("10.0.0.1", "10.0.0.2")
+    }
+
+    /// Conntrack event of a connection from `src:1234` to `dst:80`.
+    fn conn_event(src: &str, dst: &str) -> Event {
+        let mut event = Event::new();
+        event
+            .insert_section(
+                SectionId::Ct,
+                Box::new(CtEvent {
+                    state: CtState::New,
+                    base: CtConnEvent {
+                        zone_id: 3,
+                        orig: tuple(src, dst, 1234, 80),
+                        reply: tuple(dst, src, 80, 1234),
+                        tcp_state: Some("SYN_SENT".to_string()),
+                        ..Default::default()
+                    },
+                    parent: None,
+                }),
+            )
+            .unwrap();
+        event
+    }
+
+    fn drop_event(reason: &str, saddr: &str, daddr: &str, sport: u16, dport: u16) -> Event {
+        let mut event = Event::new();
+        event
+            .insert_section(
+                SectionId::SkbDrop,
+                Box::new(SkbDropEvent {
+                    subsys: None,
+                    drop_reason: reason.to_string(),
+                    reason_resolved: true,
+                    drop_location: None,
+                    consumed: false,
+                    ct: None,
+                }),
+            )
+            .unwrap();
+        insert_skb(&mut event, saddr, daddr, sport, dport);
+        event
+    }
+
+    fn insert_skb(event: &mut Event, saddr: &str, daddr: &str, sport: u16, dport: u16) {
+        event
+            .insert_section(
+                SectionId::Skb,
+                Box::new(SkbEvent {
+                    ip: Some(SkbIpEvent {
+                        saddr: saddr.to_string(),
+                        daddr: daddr.to_string(),
+                        version: SkbIpVersion::V4(SkbIpv4Event {
+                            tos: 0,
+                            id: 0,
+                            flags: 0,
+                            offset: 0,
+                        }),
+                        protocol: 6,
+                        len: 60,
+                        ttl: 64,
+                        ecn: 0,
+                    }),
+                    tcp: Some(SkbTcpEvent {
+                        sport,
+                        dport,
+                        seq: 0,
+                        ack_seq: 0,
+                        window: 0,
+                        doff: 5,
+                        flags: 0,
+                    }),
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+    }
+
+    fn ovs_ct_event(zone_id: u16, saddr: &str, daddr: &str, sport: u16, dport: u16) -> Event {
+        let mut event = Event::new();
+        event
+            .insert_section(
+                SectionId::Ovs,
+                Box::new(OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(OvsActionCt {
+                            zone_id,
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    }),
+                }),
+            )
+            .unwrap();
+        insert_skb(&mut event, saddr, daddr, sport, dport);
+        event
+    }
+
+    fn ovs_ct_key(event: &Event) -> Option<&OvsCtKey> {
+        match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event:
+                    OvsEventType::Action(ActionEvent {
+                        action: Some(OvsAction::Ct(ct)),
+                        ..
+                    }),
+            }) => ct.ct_key.as_ref(),
+            _ => panic!("not a ct action event"),
+        }
+    }
+
+    fn drop_ct(event: &Event) -> Option<&SkbDropCt> {
+        event
+            .get_section::<SkbDropEvent>(SectionId::SkbDrop)
+            .unwrap()
+            .ct
+            .as_ref()
+    }
+
+    #[test]
+    fn ct_drop_join() {
+        let mut join = CtDropJoin::new();
+        join.process_one(&mut ct_event());
+
+        // Reply direction of a known connection.
+        let mut drop = drop_event("NETFILTER_DROP", "10.0.0.2", "10.0.0.1", 80, 1234);
+        join.process_one(&mut drop);
+        let ct = drop_ct(&drop).unwrap();
+        assert_eq!(ct.zone_id, 3);
+        assert_eq!(ct.tcp_state.as_deref(), Some("SYN_SENT"));
+
+        // Non-netfilter drops are not enriched.
+        let mut drop = drop_event("NO_SOCKET", "10.0.0.1", "10.0.0.2", 1234, 80);
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_none());
+
+        // Unknown connections are left as-is.
+        let mut drop = drop_event("NETFILTER_DROP", "10.0.0.1", "10.0.0.3", 1234, 80);
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_none());
+        assert_eq!(join.unmatched(), 1);
+    }
+
+    fn session(mut event: Event, session_id: u64) -> Event {
+        event
+            .insert_section(
+                SectionId::Common,
+                Box::new(CommonEvent {
+                    session_id: Some(session_id),
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+        event
+    }
+
+    #[test]
+    fn ct_drop_join_eviction() {
+        let mut join = CtDropJoin::with_max_tuples(3);
+        join.process_one(&mut conn_event("10.0.0.1", "10.0.0.2"));
+        join.process_one(&mut conn_event("10.0.0.1", "10.0.0.3"));
+        // Seen again, the first connection is indexed back, evicting the oldest tuples.
+        join.process_one(&mut conn_event("10.0.0.1", "10.0.0.2"));
+        assert_eq!(join.evicted(), 3);
+
+        let mut drop = drop_event("NETFILTER_DROP", "10.0.0.2", "10.0.0.1", 80, 1234);
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_some());
+        // One of the directions of the second connection was evicted.
+        let mut drop = drop_event("NETFILTER_DROP", "10.0.0.3", "10.0.0.1", 80, 1234);
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_none());
+        let mut drop = drop_event("NETFILTER_DROP", "10.0.0.1", "10.0.0.3", 1234, 80);
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_some());
+        assert_eq!(join.unmatched(), 1);
+        assert_eq!(join.conns.len(), 3);
+        assert_eq!(join.last_seen.len(), 3);
+    }
+
+    #[test]
+    fn ct_drop_join_session_boundary() {
+        let mut join = CtDropJoin::new();
+        join.process_one(&mut session(ct_event(), 1));
+
+        let mut drop = session(
+            drop_event("NETFILTER_DROP", "10.0.0.2", "10.0.0.1", 80, 1234),
+            1,
+        );
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_some());
+
+        // The same connection is not known in a later session.
+        let mut drop = session(
+            drop_event("NETFILTER_DROP", "10.0.0.2", "10.0.0.1", 80, 1234),
+            2,
+        );
+        join.process_one(&mut drop);
+        assert!(drop_ct(&drop).is_none());
+        assert_eq!(join.unmatched(), 1);
+    }
+
+    #[test]
+    fn ovs_ct_join_key() {
+        let mut join = CtDropJoin::new();
+        let ct = ct_event();
+        let conn = &ct.get_section::<CtEvent>(SectionId::Ct).unwrap().base;
+
+        // Original direction.
+        let mut event = ovs_ct_event(3, "10.0.0.1", "10.0.0.2", 1234, 80);
+        join.process_one(&mut event);
+        let key = ovs_ct_key(&event).unwrap();
+        assert_eq!(key.proto, 6);
+        assert_eq!((key.sport, key.dport), (1234, 80));
+        assert!(key.matches(conn));
+
+        // Reply direction.
+        let mut event = ovs_ct_event(3, "10.0.0.2", "10.0.0.1", 80, 1234);
+        join.process_one(&mut event);
+        assert!(ovs_ct_key(&event).unwrap().matches(conn));
+
+        // Same tuple in another zone.
+        let mut event = ovs_ct_event(4, "10.0.0.1", "10.0.0.2", 1234, 80);
+        join.process_one(&mut event);
+        assert!(!ovs_ct_key(&event).unwrap().matches(conn));
+    }
+}
@@ -1,8 +1,19 @@
-use std::io::{ErrorKind, Write};
+use std::{
+    collections::HashSet,
+    io::{ErrorKind, Write},
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use log::warn;
 
-use crate::events::*;
+use crate::{
+    events::{
+        file::{append_checksum, FileIndexBuilder},
+        *,
+    },
+    helpers::time::ClockCheck,
+};
 
 /// Select the format to follow when printing events with `PrintEvent`.
 pub(crate) enum PrintEventFormat {
@@ -13,24 +24,241 @@ pub(crate) enum PrintEventFormat {
     Json,
 }
 
+/// Limits the number of events processed per second (of wall-clock time).
+struct Throttle {
+    /// Maximum number of events per second.
+    max: u32,
+    /// Start of the current one second window.
+    window_start: Option<Instant>,
+    /// Number of events allowed in the current window.
+    count: u32,
+    /// Number of events throttled in the current window.
+    throttled: u64,
+}
+
+impl Throttle {
+    fn new(max: u32) -> Self {
+        Self {
+            max,
+            window_start: None,
+            count: 0,
+            throttled: 0,
+        }
+    }
+
+    /// Should an event seen at `now` be allowed? When a new window starts,
+    /// the number of events throttled in the previous one is returned, if any.
+    fn allow(&mut self, now: Instant) -> (bool, Option<u64>) {
+        let mut throttled = None;
+
+        let new_window = match self.window_start {
+            Some(start) => now.duration_since(start) >= Duration::from_secs(1),
+            None => true,
+        };
+        if new_window {
+            throttled = self.take_throttled();
+            self.window_start = Some(now);
+            self.count = 0;
+        }
+
+        if self.count < self.max {
+            self.count += 1;
+            (true, throttled)
+        } else {
+            self.throttled += 1;
+            (false, throttled)
+        }
+    }
+
+    /// Returns the number of events throttled in the current window, if any,
+    /// and resets it.
+    fn take_throttled(&mut self) -> Option<u64> {
+        match std::mem::take(&mut self.throttled) {
+            0 => None,
+            n => Some(n),
+        }
+    }
+}
+
+/// Drop reasons expected on the system, whose events are not printed.
+pub(crate) struct QuietDrops {
+    /// Drop reasons, with or without their subsystem (e.g. "openvswitch/OVS_DROP_LAST_ACTION" or
+    /// "OVS_DROP_LAST_ACTION").
+    reasons: HashSet<String>,
+    /// Number of events not printed.
+    suppressed: u64,
+}
+
+impl QuietDrops {
+    pub(crate) fn new(reasons: &[String]) -> Self {
+        Self {
+            reasons: reasons.iter().cloned().collect(),
+            suppressed: 0,
+        }
+    }
+
+    /// Should the event be printed? Events which are not are accounted for.
+    pub(crate) fn filter(&mut self, event: &Event) -> bool {
+        if self.is_quiet(event) {
+            self.suppressed += 1;
+            return false;
+        }
+        true
+    }
+
+    /// Returns the number of events not printed so far, and resets it.
+    pub(crate) fn take_suppressed(&mut self) -> u64 {
+        std::mem::take(&mut self.suppressed)
+    }
+
+    fn is_quiet(&self, event: &Event) -> bool {
+        event
+            .get_section::<SkbDropEvent>(SectionId::SkbDrop)
+            .is_some_and(|drop| {
+                self.reasons.contains(&drop.drop_reason)
+                    || drop.subsys.as_ref().is_some_and(|subsys| {
+                        self.reasons
+                            .contains(&format!("{subsys}/{}", drop.drop_reason))
+                    })
+            })
+    }
+}
+
+/// Updates the display format with the time information carried by an event: the monotonic clock
+/// offset of startup events and the timestamp of the first event, used as reference for relative
+/// times.
+fn update_time_info(format: &mut DisplayFormat, clocks: &mut ClockCheck, event: &Event) {
+    if let Some(startup) = event.get_section::<StartupEvent>(SectionId::Startup) {
+        format.monotonic_offset = Some(startup.clock_monotonic_offset);
+        clocks.check(startup);
+    }
+    if format.time_reference.is_none() {
+        if let Some(common) = event.get_section::<CommonEvent>(SectionId::Common) {
+            format.time_reference = Some(common.timestamp);
+        }
+    }
+}
+
+/// Reports the number of known drops which were not printed, if any.
+pub(crate) fn write_quiet(writer: &mut dyn Write, n: u64) -> Result<()> {
+    if n > 0 {
+        if let Err(e) = writeln!(writer, "{n} known drop(s) not printed") {
+            if e.kind() != ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Handles event individually and write to a `Write`.
 pub(crate) struct PrintEvent {
     writer: Box<dyn Write>,
     format: PrintEventFormat,
+    index: Option<FileIndexBuilder>,
+    /// Append a checksum to each event (Json format only).
+    checksums: bool,
+    throttle: Option<Throttle>,
+    quiet: Option<QuietDrops>,
+    clocks: ClockCheck,
 }
 
 impl PrintEvent {
     pub(crate) fn new(writer: Box<dyn Write>, format: PrintEventFormat) -> Self {
-        Self { writer, format }
+        Self {
+            writer,
+            format,
+            index: None,
+            checksums: false,
+            throttle: None,
+            quiet: None,
+            clocks: ClockCheck::new(),
+        }
+    }
+
+    /// Only print up to `max` events per second, the excess being dropped
+    /// and reported by a periodic note. Only applies to the Text format, which
+    /// is meant for interactive use.
+    pub(crate) fn with_throttle(mut self, max: u32) -> Self {
+        if let PrintEventFormat::Text(_) = self.format {
+            self.throttle = Some(Throttle::new(max));
+        }
+        self
+    }
+
+    /// Do not print drop events whose reason is in `reasons`, as they are expected. Their number
+    /// is reported when flushing. Only applies to the Text format, which is meant for interactive
+    /// use.
+    pub(crate) fn with_quiet_drops(mut self, reasons: &[String]) -> Self {
+        if let (PrintEventFormat::Text(_), false) = (&self.format, reasons.is_empty()) {
+            self.quiet = Some(QuietDrops::new(reasons));
+        }
+        self
+    }
+
+    fn write_quiet(&mut self, n: u64) -> Result<()> {
+        write_quiet(&mut self.writer, n)
+    }
+
+    fn write_throttled(&mut self, throttled: Option<u64>) -> Result<()> {
+        if let Some(n) = throttled {
+            if let Err(e) = writeln!(self.writer, "throttled {n} events") {
+                if e.kind() != ErrorKind::BrokenPipe {
+                    return Err(e.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Build an index of the events while writing them, using `builder`
+    /// (which can resume the index of an existing file, e.g. when appending to
+    /// it). The index is written when flushing. Only applies to the Json
+    /// format.
+    pub(crate) fn with_index(mut self, builder: FileIndexBuilder) -> Self {
+        if let PrintEventFormat::Json = self.format {
+            self.index = Some(builder);
+        }
+        self
+    }
+
+    /// Store a checksum along with each event, to detect corrupted or tampered events when reading
+    /// them back. Only applies to the Json format.
+    pub(crate) fn with_checksums(mut self) -> Self {
+        if let PrintEventFormat::Json = self.format {
+            self.checksums = true;
+        }
+        self
+    }
+
+    /// Writer the events are printed to, to interleave other output with them.
+    pub(crate) fn writer(&mut self) -> &mut dyn Write {
+        &mut self.writer
     }
 
     /// Process events one by one (format & print).
     pub(crate) fn process_one(&mut self, e: &Event) -> Result<()> {
+        if let Some(quiet) = self.quiet.as_mut() {
+            if !quiet.filter(e) {
+                return Ok(());
+            }
+        }
+
+        // Never throttle the startup event as it is needed to format others.
+        if let (Some(throttle), None) = (
+            self.throttle.as_mut(),
+            e.get_section::<StartupEvent>(SectionId::Startup),
+        ) {
+            let (allow, throttled) = throttle.allow(Instant::now());
+            self.write_throttled(throttled)?;
+            if !allow {
+                return Ok(());
+            }
+        }
+
         match self.format {
             PrintEventFormat::Text(ref mut format) => {
-                if let Some(common) = e.get_section::<StartupEvent>(SectionId::Startup) {
-                    format.monotonic_offset = Some(common.clock_monotonic_offset);
-                }
+                update_time_info(format, &mut self.clocks, e);
 
                 let mut event = format!("{}", e.display(format, &FormatterConf::new()));
                 if !event.is_empty() {
@@ -47,17 +275,86 @@ impl PrintEvent {
             }
             PrintEventFormat::Json => {
                 let mut event = serde_json::to_vec(&e.to_json())?;
+                if self.checksums {
+                    append_checksum(&mut event);
+                }
                 event.push(b'\n');
                 self.writer.write_all(&event)?;
+
+                if let Some(index) = self.index.as_mut() {
+                    index.record(e, event.len() as u64);
+                }
             }
         }
 
         Ok(())
     }
 
-    /// Flush underlying writers.
+    /// Flush underlying writers. If an index was built, it is written once
+    /// the events are; no event should be processed after this.
     pub(crate) fn flush(&mut self) -> Result<()> {
-        Ok(self.writer.flush()?)
+        if let Some(throttled) = self.throttle.as_mut().and_then(|t| t.take_throttled()) {
+            self.write_throttled(Some(throttled))?;
+        }
+        if let Some(n) = self.quiet.as_mut().map(|q| q.take_suppressed()) {
+            self.write_quiet(n)?;
+        }
+        self.writer.flush()?;
+        if let Some(index) = self.index.take() {
+            index.write()?;
+        }
+        Ok(())
+    }
+}
+
+/// Drives multiple `PrintEvent` from a single stream of events, e.g. to display events on the
+/// console while writing them to a file. Each output gets every event; an output failing is
+/// reported and not used anymore, while the others keep going.
+#[derive(Default)]
+pub(crate) struct PrintEventFanOut {
+    /// Outputs, along with their name used when reporting errors. Failed outputs are removed.
+    outputs: Vec<(String, PrintEvent)>,
+}
+
+impl PrintEventFanOut {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an output, `name` describing it when reporting errors.
+    pub(crate) fn push(&mut self, name: &str, output: PrintEvent) {
+        self.outputs.push((name.to_string(), output));
+    }
+
+    /// Runs `op` on all outputs, removing the ones failing. An error is returned when the last
+    /// outputs fail.
+    fn for_each<F>(&mut self, mut op: F) -> Result<()>
+    where
+        F: FnMut(&mut PrintEvent) -> Result<()>,
+    {
+        let count = self.outputs.len();
+        self.outputs.retain_mut(|(name, output)| match op(output) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Failed to write events to {name}, disabling it: {e}");
+                false
+            }
+        });
+
+        if count > 0 && self.outputs.is_empty() {
+            bail!("Failed to write events to all outputs");
+        }
+        Ok(())
+    }
+
+    /// Process events one by one, giving them to all outputs.
+    pub(crate) fn process_one(&mut self, e: &Event) -> Result<()> {
+        self.for_each(|output| output.process_one(e))
+    }
+
+    /// Flush all outputs.
+    pub(crate) fn flush(&mut self) -> Result<()> {
+        self.for_each(|output| output.flush())
     }
 }
 
@@ -65,11 +362,57 @@ impl PrintEvent {
 pub(crate) struct PrintSeries {
     writer: Box<dyn Write>,
     format: PrintEventFormat,
+    per_packet: bool,
+    /// Formatted events which could not be attributed to a packet, in
+    /// per-packet mode.
+    unattributed: Vec<String>,
+    /// Maximum number of unattributed events to buffer before writing them.
+    /// Zero means the buffer can grow endlessly.
+    max_unattributed: usize,
+    clocks: ClockCheck,
 }
 
 impl PrintSeries {
     pub(crate) fn new(writer: Box<dyn Write>, format: PrintEventFormat) -> Self {
-        Self { writer, format }
+        Self {
+            writer,
+            format,
+            per_packet: false,
+            unattributed: Vec::new(),
+            max_unattributed: 0,
+            clocks: ClockCheck::new(),
+        }
+    }
+
+    /// Print each series in a block headed by its packet tracking id, and
+    /// events which can't be attributed to a packet in a dedicated section
+    /// when flushing. At most `max_buffer` unattributed events are buffered,
+    /// a section being written each time the limit is reached (zero means no
+    /// limit). Only applies to the Text format.
+    pub(crate) fn with_per_packet(mut self, max_buffer: usize) -> Self {
+        if let PrintEventFormat::Text(_) = self.format {
+            self.per_packet = true;
+            self.max_unattributed = max_buffer;
+        }
+        self
+    }
+
+    /// Write the buffered unattributed events, if any.
+    fn write_unattributed(&mut self) -> Result<()> {
+        if !self.unattributed.is_empty() {
+            let events = std::mem::take(&mut self.unattributed);
+            self.write_content(&format!("unattributed\n{}\n\n", events.join("\n")))?;
+        }
+        Ok(())
+    }
+
+    fn write_content(&mut self, content: &str) -> Result<()> {
+        if let Err(e) = self.writer.write_all(content.as_bytes()) {
+            if e.kind() != ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+        Ok(())
     }
 
     /// Process events one by one (format & print).
@@ -80,10 +423,43 @@ impl PrintSeries {
                 let mut fconf = FormatterConf::new();
                 let mut first = true;
 
-                for event in series.events.iter() {
-                    if let Some(common) = event.get_section::<StartupEvent>(SectionId::Startup) {
-                        format.monotonic_offset = Some(common.clock_monotonic_offset);
+                if self.per_packet {
+                    let tracking = series
+                        .events
+                        .first()
+                        .and_then(|e| e.get_section::<TrackingInfo>(SectionId::Tracking));
+
+                    match tracking {
+                        Some(tracking) => {
+                            content
+                                .push_str(&format!("packet #{:x}\n", tracking.skb.tracking_id()));
+                            fconf = FormatterConf::with_level(2);
+                        }
+                        // Untracked events come in their own series.
+                        None => {
+                            for event in series.events.iter() {
+                                update_time_info(format, &mut self.clocks, event);
+
+                                let event = format!(
+                                    "{}",
+                                    event.display(format, &FormatterConf::with_level(2))
+                                );
+                                if !event.is_empty() {
+                                    self.unattributed.push(event);
+                                }
+                            }
+                            if self.max_unattributed != 0
+                                && self.unattributed.len() >= self.max_unattributed
+                            {
+                                self.write_unattributed()?;
+                            }
+                            return Ok(());
+                        }
                     }
+                }
+
+                for event in series.events.iter() {
+                    update_time_info(format, &mut self.clocks, event);
 
                     content.push_str(&format!("{}", event.display(format, &fconf)));
                     if !content.is_empty() {
@@ -98,12 +474,7 @@ impl PrintSeries {
 
                 if !content.is_empty() {
                     content.push('\n');
-
-                    if let Err(e) = self.writer.write_all(content.as_bytes()) {
-                        if e.kind() != ErrorKind::BrokenPipe {
-                            return Err(e.into());
-                        }
-                    }
+                    self.write_content(&content)?;
                 }
             }
             PrintEventFormat::Json => {
@@ -116,8 +487,190 @@ impl PrintSeries {
         Ok(())
     }
 
-    /// Flush underlying writers.
+    /// Flush underlying writers. In per-packet mode, events which could not
+    /// be attributed to a packet and are still buffered are written first.
     pub(crate) fn flush(&mut self) -> Result<()> {
+        self.write_unattributed()?;
         Ok(self.writer.flush()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle() {
+        let mut throttle = Throttle::new(2);
+        let start = Instant::now();
+
+        assert_eq!(throttle.allow(start), (true, None));
+        assert_eq!(throttle.allow(start), (true, None));
+        assert_eq!(
+            throttle.allow(start + Duration::from_millis(10)),
+            (false, None)
+        );
+        assert_eq!(
+            throttle.allow(start + Duration::from_millis(999)),
+            (false, None)
+        );
+
+        // New window: the throttled events of the previous one are reported.
+        assert_eq!(
+            throttle.allow(start + Duration::from_secs(1)),
+            (true, Some(2))
+        );
+        assert_eq!(throttle.allow(start + Duration::from_secs(1)), (true, None));
+        assert_eq!(
+            throttle.allow(start + Duration::from_secs(1)),
+            (false, None)
+        );
+        assert_eq!(throttle.take_throttled(), Some(1));
+        assert_eq!(throttle.take_throttled(), None);
+    }
+
+    /// Writer failing after `ok` writes.
+    struct FailingWriter {
+        ok: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            match self.ok {
+                0 => Err(std::io::Error::other("failed")),
+                _ => {
+                    self.ok -= 1;
+                    Ok(buf.len())
+                }
+            }
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fan_out() {
+        let mut event = Event::new();
+        event
+            .insert_section(SectionId::Common, Box::new(CommonEvent::default()))
+            .unwrap();
+
+        let mut fan_out = PrintEventFanOut::new();
+        fan_out.push(
+            "first",
+            PrintEvent::new(Box::new(FailingWriter { ok: 1 }), PrintEventFormat::Json),
+        );
+        fan_out.push(
+            "second",
+            PrintEvent::new(Box::new(FailingWriter { ok: 2 }), PrintEventFormat::Json),
+        );
+
+        assert!(fan_out.process_one(&event).is_ok());
+        // The first output fails, the second one keeps going.
+        assert!(fan_out.process_one(&event).is_ok());
+        assert_eq!(fan_out.outputs.len(), 1);
+        // All outputs failed.
+        assert!(fan_out.process_one(&event).is_err());
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unattributed_buffer() {
+        let out = SharedWriter::default();
+        let mut printer = PrintSeries::new(
+            Box::new(out.clone()),
+            PrintEventFormat::Text(DisplayFormat::new()),
+        )
+        .with_per_packet(2);
+        let untracked = |reason: &str| {
+            let mut event = Event::new();
+            event
+                .insert_section(SectionId::Common, Box::new(CommonEvent::default()))
+                .unwrap();
+            event
+                .insert_section(
+                    SectionId::SkbDrop,
+                    Box::new(SkbDropEvent {
+                        subsys: None,
+                        drop_reason: reason.to_string(),
+                        reason_resolved: true,
+                        drop_location: None,
+                        consumed: false,
+                        ct: None,
+                    }),
+                )
+                .unwrap();
+            EventSeries {
+                events: vec![event],
+            }
+        };
+
+        printer.process_one(&untracked("NO_SOCKET")).unwrap();
+        assert!(out.0.borrow().is_empty());
+        // The buffer is full, its events are written.
+        printer.process_one(&untracked("TCP_CSUM")).unwrap();
+        printer.process_one(&untracked("TCP_INVALID")).unwrap();
+        printer.flush().unwrap();
+        assert_eq!(
+            String::from_utf8(out.0.borrow().clone()).unwrap(),
+            "unattributed
+  0 drop (reason NO_SOCKET)
+  0 drop (reason TCP_CSUM)
+
+unattributed
+  0 drop (reason TCP_INVALID)
+
+"
+        );
+    }
+
+    #[test]
+    fn quiet_drops() {
+        let mut quiet = QuietDrops::new(&[
+            "TCP_INVALID".to_string(),
+            "openvswitch/OVS_DROP_LAST_ACTION".to_string(),
+        ]);
+        let drop = |subsys: Option<&str>, reason: &str| {
+            let mut event = Event::new();
+            event
+                .insert_section(
+                    SectionId::SkbDrop,
+                    Box::new(SkbDropEvent {
+                        subsys: subsys.map(String::from),
+                        drop_reason: reason.to_string(),
+                        reason_resolved: true,
+                        drop_location: None,
+                        consumed: false,
+                        ct: None,
+                    }),
+                )
+                .unwrap();
+            event
+        };
+
+        assert!(quiet.is_quiet(&drop(None, "TCP_INVALID")));
+        assert!(quiet.is_quiet(&drop(Some("openvswitch"), "OVS_DROP_LAST_ACTION")));
+        assert!(!quiet.is_quiet(&drop(None, "NO_SOCKET")));
+        assert!(!quiet.is_quiet(&drop(None, "OVS_DROP_LAST_ACTION")));
+        assert!(!quiet.is_quiet(&Event::new()));
+
+        assert!(!quiet.filter(&drop(None, "TCP_INVALID")));
+        assert!(quiet.filter(&drop(None, "NO_SOCKET")));
+        assert_eq!(quiet.take_suppressed(), 1);
+        assert_eq!(quiet.take_suppressed(), 0);
+    }
+}
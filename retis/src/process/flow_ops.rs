@@ -0,0 +1,241 @@
+//! OVS flow operations aggregator.
+//!
+//! On a busy datapath, individual flow operation events (flow_put, flow_exec, etc.) are too many
+//! to be looked at one by one. This accumulates them into per operation type and per upcall queue
+//! counters, which are reported periodically (based on the events timestamp) or on demand.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{ErrorKind, Write},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::events::*;
+
+/// Flow operation counters over a time window.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FlowOpCounts {
+    /// Timestamp of the first operation accounted for.
+    pub(crate) start: u64,
+    /// Timestamp of the last operation accounted for.
+    pub(crate) end: u64,
+    /// Number of operations, by operation type (OVS_OP_*).
+    pub(crate) by_type: BTreeMap<u8, u64>,
+    /// Number of operations, by upcall queue id.
+    pub(crate) by_queue: BTreeMap<u32, u64>,
+}
+
+impl FlowOpCounts {
+    /// Total number of operations.
+    pub(crate) fn total(&self) -> u64 {
+        self.by_type.values().sum()
+    }
+}
+
+impl fmt::Display for FlowOpCounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "flow operations [{} - {}]: {}",
+            self.start,
+            self.end,
+            self.total()
+        )?;
+        self.by_type.iter().try_for_each(|(op_type, n)| {
+            write!(f, " flow_{} {n}", OperationEvent::operation_str(*op_type))
+        })?;
+        self.by_queue
+            .iter()
+            .try_for_each(|(queue_id, n)| write!(f, " q{queue_id} {n}"))
+    }
+}
+
+/// Reports flushed flow operation counts, if any.
+pub(crate) fn write_flow_ops(writer: &mut dyn Write, counts: Option<FlowOpCounts>) -> Result<()> {
+    if let Some(counts) = counts {
+        if let Err(e) = writeln!(writer, "{counts}") {
+            if e.kind() != ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Is the event a flow operation?
+pub(crate) fn is_flow_op(event: &Event) -> bool {
+    matches!(
+        event.get_section::<OvsEvent>(SectionId::Ovs),
+        Some(OvsEvent {
+            event: OvsEventType::Operation(_),
+        })
+    )
+}
+
+/// Accumulates flow operation events into `FlowOpCounts`.
+pub(crate) struct FlowOpsAggregator {
+    /// Length of the time windows, in ns. Counts are only flushed on demand if not set.
+    interval: Option<u64>,
+    counts: Option<FlowOpCounts>,
+}
+
+impl FlowOpsAggregator {
+    pub(crate) fn new(interval: Option<u64>) -> Self {
+        Self {
+            interval,
+            counts: None,
+        }
+    }
+
+    /// Creates an aggregator reporting counts over windows of `interval` milliseconds, or only on
+    /// demand if 0.
+    pub(crate) fn from_interval_ms(interval: u64) -> Result<Self> {
+        let interval = match interval {
+            0 => None,
+            ms => Some(
+                ms.checked_mul(1_000_000)
+                    .ok_or_else(|| anyhow!("Flow operations interval is too large ({ms}ms)"))?,
+            ),
+        };
+        Ok(Self::new(interval))
+    }
+
+    /// Accounts for an event, if it is a flow operation (see `is_flow_op`).
+    ///
+    /// If an interval is set and the event falls after the current time window, the counts of the
+    /// window are flushed first and returned.
+    pub(crate) fn record(&mut self, event: &Event) -> Option<FlowOpCounts> {
+        let op = match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event: OvsEventType::Operation(op),
+            }) => op,
+            _ => return None,
+        };
+        let ts = event
+            .get_section::<CommonEvent>(SectionId::Common)
+            .map(|common| common.timestamp)
+            .unwrap_or_default();
+
+        let mut flushed = None;
+        if let (Some(interval), Some(counts)) = (self.interval, self.counts.as_ref()) {
+            if ts >= counts.start.saturating_add(interval) {
+                flushed = self.flush();
+            }
+        }
+
+        let counts = self.counts.get_or_insert_with(|| FlowOpCounts {
+            start: ts,
+            ..Default::default()
+        });
+        counts.end = counts.end.max(ts);
+        *counts.by_type.entry(op.op_type).or_default() += 1;
+        *counts.by_queue.entry(op.queue_id).or_default() += 1;
+        flushed
+    }
+
+    /// Returns the counts accumulated so far, if any, and resets them.
+    pub(crate) fn flush(&mut self) -> Option<FlowOpCounts> {
+        self.counts.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::ovs_event;
+
+    fn op(timestamp: u64, op_type: u8, queue_id: u32) -> Event {
+        ovs_event(
+            timestamp,
+            OvsEventType::Operation(OperationEvent {
+                op_type,
+                queue_id,
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn flow_ops_on_demand() {
+        let mut aggr = FlowOpsAggregator::new(None);
+
+        assert!(aggr.flush().is_none());
+
+        for event in [
+            op(1000, OVS_OP_PUT, 1),
+            op(2000, OVS_OP_EXEC, 1),
+            op(3000, OVS_OP_PUT, 2),
+            op(u64::MAX, OVS_OP_EXEC, 2),
+            op(4000, OVS_OP_EXEC, 1),
+        ] {
+            assert!(is_flow_op(&event));
+            assert!(aggr.record(&event).is_none());
+        }
+        // Not a flow operation.
+        let upcall = ovs_event(5000, OvsEventType::Upcall(UpcallEvent::default()));
+        assert!(!is_flow_op(&upcall));
+        assert!(aggr.record(&upcall).is_none());
+
+        let counts = aggr.flush().unwrap();
+        assert_eq!(counts.start, 1000);
+        assert_eq!(counts.end, u64::MAX);
+        assert_eq!(counts.total(), 5);
+        assert_eq!(
+            counts.by_type,
+            BTreeMap::from([(OVS_OP_EXEC, 3), (OVS_OP_PUT, 2)])
+        );
+        assert_eq!(counts.by_queue, BTreeMap::from([(1, 3), (2, 2)]));
+        assert_eq!(
+            format!("{counts}"),
+            format!(
+                "flow operations [1000 - {}]: 5 flow_exec 3 flow_put 2 q1 3 q2 2",
+                u64::MAX
+            )
+        );
+
+        assert!(aggr.flush().is_none());
+    }
+
+    #[test]
+    fn flow_ops_interval() {
+        let mut aggr = FlowOpsAggregator::new(Some(1000));
+
+        assert!(aggr.record(&op(1000, OVS_OP_PUT, 1)).is_none());
+        assert!(aggr.record(&op(1500, OVS_OP_EXEC, 1)).is_none());
+        assert!(aggr.record(&op(1999, OVS_OP_EXEC, 1)).is_none());
+
+        // Starts a new window; the previous one is flushed.
+        let counts = aggr.record(&op(2000, OVS_OP_PUT, 3)).unwrap();
+        assert_eq!((counts.start, counts.end), (1000, 1999));
+        assert_eq!(
+            counts.by_type,
+            BTreeMap::from([(OVS_OP_EXEC, 2), (OVS_OP_PUT, 1)])
+        );
+        assert_eq!(counts.by_queue, BTreeMap::from([(1, 3)]));
+
+        let counts = aggr.flush().unwrap();
+        assert_eq!((counts.start, counts.end), (2000, 2000));
+        assert_eq!(counts.by_type, BTreeMap::from([(OVS_OP_PUT, 1)]));
+        assert_eq!(counts.by_queue, BTreeMap::from([(3, 1)]));
+    }
+
+    #[test]
+    fn flow_ops_interval_ms() {
+        assert_eq!(
+            FlowOpsAggregator::from_interval_ms(0).unwrap().interval,
+            None
+        );
+        assert_eq!(
+            FlowOpsAggregator::from_interval_ms(1500).unwrap().interval,
+            Some(1_500_000_000)
+        );
+        assert!(FlowOpsAggregator::from_interval_ms(u64::MAX / 1_000_000)
+            .unwrap()
+            .interval
+            .is_some());
+        assert!(FlowOpsAggregator::from_interval_ms(u64::MAX / 1_000_000 + 1).is_err());
+        assert!(FlowOpsAggregator::from_interval_ms(u64::MAX).is_err());
+    }
+}
@@ -4,6 +4,13 @@
 
 pub(crate) mod cli;
 
+pub(crate) mod ct_join;
 pub(crate) mod display;
+pub(crate) mod flow_ops;
+pub(crate) mod ovs_batch;
 pub(crate) mod series;
+pub(crate) mod summary;
+pub(crate) mod top_n;
 pub(crate) mod tracking;
+pub(crate) mod trunc;
+pub(crate) mod upcall_latency;
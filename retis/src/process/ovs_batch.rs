@@ -0,0 +1,372 @@
+//! OVS userspace batches.
+//!
+//! ovs-vswitchd receives upcalls in batches and handles them one by one, possibly issuing flow
+//! operations (e.g. installing a flow and executing actions on the packet). The upcall receive
+//! and flow operation events of a batch share its timestamp (`batch_ts`) and report the index of
+//! the upcall they relate to within the batch (`batch_idx`). Grouping them gives a view of each
+//! batch: every received upcall followed by the operations it triggered. Handler threads run
+//! their batches concurrently, batches are thus identified by their handler thread and timestamp.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    io::{ErrorKind, Write},
+};
+
+use anyhow::Result;
+
+use crate::events::*;
+
+/// Default maximum number of batches waiting for their events.
+const OVS_BATCH_MAX_PENDING: usize = 1024;
+/// Default time after which a batch no event was added to is considered complete, in ns.
+pub(crate) const OVS_BATCH_TIMEOUT_NS: u64 = 1_000_000_000;
+
+/// Event of a batch.
+pub(crate) struct OvsBatchEntry {
+    /// Index of the upcall the event relates to, within the batch.
+    pub(crate) batch_idx: u8,
+    pub(crate) event: OvsEvent,
+}
+
+/// Identifies a batch: its handler thread id, if known, and timestamp.
+type BatchKey = (Option<i32>, u64);
+
+/// Upcall receive and flow operation events of a batch.
+pub(crate) struct OvsBatch {
+    /// Id of the handler thread which processed the batch, if known.
+    pub(crate) handler: Option<i32>,
+    /// Timestamp of the beginning of the batch.
+    pub(crate) batch_ts: u64,
+    /// Events of the batch, ordered by index. Upcall receive events come first.
+    pub(crate) entries: Vec<OvsBatchEntry>,
+    /// Timestamp of the last event added to the batch.
+    last_seen: u64,
+}
+
+impl OvsBatch {
+    /// Formats the batch, one event per line.
+    pub(crate) fn format(&self, format: &DisplayFormat) -> String {
+        let mut out = format!("ovs batch {}", self.batch_ts);
+        if let Some(handler) = self.handler {
+            // Writing to a String can't fail.
+            let _ = write!(out, " handler {handler}");
+        }
+        self.entries.iter().for_each(|entry| {
+            // Writing to a String can't fail.
+            let _ = write!(
+                out,
+                "\n  [{}] {}",
+                entry.batch_idx,
+                entry.event.display(format, &FormatterConf::new())
+            );
+        });
+        out
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by_key(|entry| {
+            (
+                entry.batch_idx,
+                !matches!(entry.event.event, OvsEventType::RecvUpcall(_)),
+            )
+        });
+    }
+}
+
+/// Prints batches, if any.
+pub(crate) fn write_ovs_batches(
+    writer: &mut dyn Write,
+    batches: Vec<OvsBatch>,
+    format: &DisplayFormat,
+) -> Result<()> {
+    for batch in batches.iter() {
+        if let Err(e) = writeln!(writer, "{}", batch.format(format)) {
+            if e.kind() != ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Batch timestamp and index of an event, if it belongs to a batch.
+fn batch_info(event: &Event) -> Option<(u64, u8, &OvsEvent)> {
+    let ovs = event.get_section::<OvsEvent>(SectionId::Ovs)?;
+    match &ovs.event {
+        OvsEventType::RecvUpcall(recv) => Some((recv.batch_ts, recv.batch_idx, ovs)),
+        OvsEventType::Operation(op) => Some((op.batch_ts, op.batch_idx, ovs)),
+        _ => None,
+    }
+}
+
+/// Is the event part of a batch (upcall receive or flow operation)?
+pub(crate) fn is_batch_event(event: &Event) -> bool {
+    batch_info(event).is_some()
+}
+
+/// OvsBatcher groups upcall receive and flow operation events by batch. Batches are returned
+/// once no event was added to them for a while (based on the events timestamp), or when too many
+/// batches are pending.
+pub(crate) struct OvsBatcher {
+    /// Pending batches.
+    pending: HashMap<BatchKey, OvsBatch>,
+    /// Pending batches, by the timestamp of the last event added to them.
+    last_seen: BTreeSet<(u64, BatchKey)>,
+    /// Time after which a batch no event was added to is complete, in ns.
+    timeout: u64,
+    /// Maximum number of pending batches.
+    max_pending: usize,
+}
+
+impl OvsBatcher {
+    pub(crate) fn new(timeout: u64) -> Self {
+        Self::with_max_pending(timeout, OVS_BATCH_MAX_PENDING)
+    }
+
+    pub(crate) fn with_max_pending(timeout: u64, max_pending: usize) -> Self {
+        Self {
+            pending: HashMap::new(),
+            last_seen: BTreeSet::new(),
+            timeout,
+            max_pending,
+        }
+    }
+
+    /// Process one event, adding it to its batch. Returns the batches completed, ordered by
+    /// batch timestamp.
+    pub(crate) fn process_one(&mut self, event: &Event) -> Vec<OvsBatch> {
+        let (batch_ts, batch_idx, ovs) = match batch_info(event) {
+            Some(info) => info,
+            None => return Vec::new(),
+        };
+        let common = event.get_section::<CommonEvent>(SectionId::Common);
+        let ts = common.map(|common| common.timestamp).unwrap_or_default();
+        let handler = common.and_then(|common| common.task.as_ref().map(|task| task.pid));
+
+        // Take the batches which timed out.
+        let mut done = Vec::new();
+        while let Some((last_seen, key)) = self.last_seen.first().copied() {
+            if ts < last_seen.saturating_add(self.timeout) {
+                break;
+            }
+            done.extend(self.take(last_seen, key));
+        }
+
+        let key = (handler, batch_ts);
+        let batch = self.pending.entry(key).or_insert_with(|| OvsBatch {
+            handler,
+            batch_ts,
+            entries: Vec::new(),
+            last_seen: ts,
+        });
+        self.last_seen.remove(&(batch.last_seen, key));
+        batch.last_seen = batch.last_seen.max(ts);
+        self.last_seen.insert((batch.last_seen, key));
+        batch.entries.push(OvsBatchEntry {
+            batch_idx,
+            event: ovs.clone(),
+        });
+
+        if self.pending.len() > self.max_pending {
+            // Evict the batch which was not updated for the longest time.
+            if let Some((last_seen, key)) = self.last_seen.first().copied() {
+                done.extend(self.take(last_seen, key));
+            }
+        }
+
+        done.sort_by_key(|batch| (batch.batch_ts, batch.handler));
+        done
+    }
+
+    /// Returns all the pending batches, ordered by batch timestamp.
+    pub(crate) fn flush(&mut self) -> Vec<OvsBatch> {
+        self.last_seen.clear();
+        let mut done = self
+            .pending
+            .drain()
+            .map(|(_, mut batch)| {
+                batch.sort();
+                batch
+            })
+            .collect::<Vec<_>>();
+        done.sort_by_key(|batch| (batch.batch_ts, batch.handler));
+        done
+    }
+
+    /// Removes and returns a pending batch.
+    fn take(&mut self, last_seen: u64, key: BatchKey) -> Option<OvsBatch> {
+        self.last_seen.remove(&(last_seen, key));
+        self.pending.remove(&key).map(|mut batch| {
+            batch.sort();
+            batch
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::ovs_event;
+
+    fn recv(timestamp: u64, batch_ts: u64, batch_idx: u8) -> Event {
+        ovs_event(
+            timestamp,
+            OvsEventType::RecvUpcall(RecvUpcallEvent {
+                r#type: 1,
+                queue_id: 7,
+                pkt_size: 98,
+                batch_ts,
+                batch_idx,
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn op(timestamp: u64, op_type: u8, batch_ts: u64, batch_idx: u8) -> Event {
+        ovs_event(
+            timestamp,
+            OvsEventType::Operation(OperationEvent {
+                op_type,
+                queue_id: 7,
+                batch_ts,
+                batch_idx,
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn ovs_batch() {
+        let mut batcher = OvsBatcher::new(1000);
+
+        // Events are not necessarily seen in order.
+        assert!(batcher
+            .process_one(&op(2100, OVS_OP_PUT, 2000, 0))
+            .is_empty());
+        assert!(batcher.process_one(&recv(2050, 2000, 0)).is_empty());
+        assert!(batcher
+            .process_one(&op(2200, OVS_OP_EXEC, 2000, 0))
+            .is_empty());
+        // Not part of a batch.
+        assert!(batcher
+            .process_one(&ovs_event(
+                2300,
+                OvsEventType::Upcall(UpcallEvent::default())
+            ))
+            .is_empty());
+
+        // Another batch, while the first one timed out.
+        let done = batcher.process_one(&recv(3200, 3100, 0));
+        assert_eq!(done.len(), 1);
+        let batch = &done[0];
+        assert_eq!(batch.batch_ts, 2000);
+        assert_eq!(
+            batch.format(&DisplayFormat::new()),
+            "ovs batch 2000
+  [0] upcall_recv (miss) q 7 pkt_size 98
+  [0] flow_put q 7 ts 2000 (0)
+  [0] flow_exec q 7 ts 2000 (0)"
+        );
+
+        let done = batcher.flush();
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].batch_ts, 3100);
+        assert_eq!(done[0].entries.len(), 1);
+        assert!(batcher.flush().is_empty());
+    }
+
+    #[test]
+    fn ovs_batch_handlers() {
+        let mut batcher = OvsBatcher::new(1000);
+        let handler = |mut event: Event, pid| {
+            event
+                .get_section_mut::<CommonEvent>(SectionId::Common)
+                .unwrap()
+                .task = Some(TaskEvent {
+                pid,
+                tgid: 1,
+                comm: "handler".to_string(),
+            });
+            event
+        };
+
+        // Batches of different handlers can share a timestamp.
+        batcher.process_one(&handler(recv(1100, 1000, 0), 12));
+        batcher.process_one(&handler(recv(1150, 1000, 0), 11));
+        batcher.process_one(&handler(op(1200, OVS_OP_PUT, 1000, 0), 12));
+
+        let done = batcher.flush();
+        assert_eq!(
+            done.iter()
+                .map(|b| (b.handler, b.batch_ts, b.entries.len()))
+                .collect::<Vec<_>>(),
+            vec![(Some(11), 1000, 1), (Some(12), 1000, 2)]
+        );
+        assert_eq!(
+            done[0].format(&DisplayFormat::new()),
+            "ovs batch 1000 handler 11
+  [0] upcall_recv (miss) q 7 pkt_size 98"
+        );
+    }
+
+    #[test]
+    fn ovs_batch_order() {
+        let mut batcher = OvsBatcher::new(1000);
+
+        batcher.process_one(&op(1300, OVS_OP_EXEC, 1000, 1));
+        batcher.process_one(&recv(1100, 1000, 1));
+        batcher.process_one(&op(1200, OVS_OP_PUT, 1000, 0));
+        batcher.process_one(&recv(1050, 1000, 0));
+
+        let done = batcher.flush();
+        let order = done[0]
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.batch_idx,
+                    matches!(entry.event.event, OvsEventType::RecvUpcall(_)),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![(0, true), (0, false), (1, true), (1, false)]);
+    }
+
+    #[test]
+    fn ovs_batch_eviction() {
+        let mut batcher = OvsBatcher::with_max_pending(u64::MAX, 2);
+
+        assert!(batcher.process_one(&recv(1100, 1000, 0)).is_empty());
+        assert!(batcher.process_one(&recv(2100, 2000, 0)).is_empty());
+        let done = batcher.process_one(&recv(3100, 3000, 0));
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].batch_ts, 1000);
+
+        let done = batcher.flush();
+        assert_eq!(
+            done.iter().map(|b| b.batch_ts).collect::<Vec<_>>(),
+            vec![2000, 3000]
+        );
+    }
+
+    #[test]
+    fn ovs_batch_eviction_update() {
+        let mut batcher = OvsBatcher::with_max_pending(u64::MAX, 2);
+
+        assert!(batcher.process_one(&recv(1100, 1000, 0)).is_empty());
+        assert!(batcher.process_one(&recv(2100, 2000, 0)).is_empty());
+        // Updating a batch makes it the most recent one.
+        assert!(batcher.process_one(&recv(2200, 1000, 1)).is_empty());
+        let done = batcher.process_one(&recv(3100, 3000, 0));
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].batch_ts, 2000);
+
+        let done = batcher.flush();
+        assert_eq!(
+            done.iter().map(|b| b.batch_ts).collect::<Vec<_>>(),
+            vec![1000, 3000]
+        );
+        assert_eq!(done[0].entries.len(), 2);
+    }
+}
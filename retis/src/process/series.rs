@@ -11,9 +11,13 @@ use anyhow::{anyhow, Result};
 
 use crate::events::{CommonEvent, Event, EventSeries, SectionId, TrackingInfo};
 
+/// Series are identified by their tracking information and collection session: events of different
+/// sessions are never part of the same series.
+type SeriesKey = (TrackingInfo, Option<u64>);
+
 #[derive(Default)]
 pub(crate) struct EventSorter {
-    series: BTreeMap<TrackingInfo, Vec<Event>>,
+    series: BTreeMap<SeriesKey, Vec<Event>>,
     untracked: VecDeque<Event>,
     n_events: usize,
 }
@@ -36,14 +40,15 @@ impl EventSorter {
     /// Adds an event to the EventSorter.
     pub(crate) fn add(&mut self, event: Event) {
         match event.get_section::<TrackingInfo>(SectionId::Tracking) {
-            Some(track) => match self.series.get_mut(track) {
-                Some(series) => {
-                    series.push(event);
-                }
-                None => {
-                    self.series.insert(track.clone(), vec![event]);
-                }
-            },
+            Some(track) => {
+                let key = (
+                    track.clone(),
+                    event
+                        .get_section::<CommonEvent>(SectionId::Common)
+                        .and_then(|c| c.session_id),
+                );
+                self.series.entry(key).or_default().push(event);
+            }
             None => {
                 self.untracked.push_back(event);
             }
@@ -63,7 +68,7 @@ impl EventSorter {
             // Pop whatever is oldest
             // It's safe to unwrap because we've already checked both series and untracked are
             // non-empty.
-            if self.series.iter().next().unwrap().0.skb.timestamp
+            if self.series.iter().next().unwrap().0 .0.skb.timestamp
                 < self
                     .untracked
                     .front()
@@ -107,3 +112,94 @@ impl EventSorter {
         }
     }
 }
+
+/// SeriesOrderCheck checks the events of series read from files marked as sorted are in timestamp
+/// order. Those files are not sorted again, this reports when they were not actually sorted
+/// instead of silently using unsorted events.
+#[derive(Default)]
+pub(crate) struct SeriesOrderCheck {
+    /// Number of series whose timestamps went backwards.
+    out_of_order: u64,
+}
+
+impl SeriesOrderCheck {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the events of a series are in timestamp order. Events without a timestamp are
+    /// ignored.
+    pub(crate) fn check(&mut self, series: &EventSeries) {
+        let mut last = None;
+        for ts in series
+            .events
+            .iter()
+            .filter_map(|e| e.get_section::<CommonEvent>(SectionId::Common))
+            .map(|c| c.timestamp)
+        {
+            if last.is_some_and(|last| ts < last) {
+                self.out_of_order += 1;
+                return;
+            }
+            last = Some(ts);
+        }
+    }
+
+    /// Number of series whose events were not in timestamp order.
+    pub(crate) fn out_of_order(&self) -> u64 {
+        self.out_of_order
+    }
+
+    /// Warn if events were not in timestamp order.
+    pub(crate) fn report(&self) {
+        if self.out_of_order() > 0 {
+            log::warn!(
+                "{} series with events not in timestamp order: the file is not properly sorted",
+                self.out_of_order()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(timestamps: &[u64]) -> EventSeries {
+        EventSeries {
+            events: timestamps
+                .iter()
+                .map(|&timestamp| {
+                    let mut event = Event::new();
+                    event
+                        .insert_section(
+                            SectionId::Common,
+                            Box::new(CommonEvent {
+                                timestamp,
+                                ..Default::default()
+                            }),
+                        )
+                        .unwrap();
+                    event
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn series_order_check() {
+        let mut check = SeriesOrderCheck::new();
+        check.check(&series(&[]));
+        check.check(&series(&[1, 2, 2, 5]));
+        // Events without a timestamp are ignored.
+        let mut untimed = series(&[3, 4]);
+        untimed.events.insert(1, Event::new());
+        check.check(&untimed);
+        assert_eq!(check.out_of_order(), 0);
+
+        check.check(&series(&[1, 3, 2, 4]));
+        // Series are checked one by one.
+        check.check(&series(&[0]));
+        assert_eq!(check.out_of_order(), 1);
+    }
+}
@@ -0,0 +1,1304 @@
+//! Summary processor.
+//!
+//! Aggregates events to provide an overview of what happened during a capture, e.g. which kernel
+//! functions dropped most packets and for what reasons, or how fast and how often OvS flows were
+//! installed and removed, or how events were distributed across CPUs.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Write,
+};
+
+use anyhow::Result;
+
+use super::top_n::TopN;
+use crate::events::{
+    helpers::{errno_fmt, errno_str, ret_errno_str},
+    *,
+};
+
+/// Name used to aggregate drops whose location is not known.
+const UNKNOWN_LOCATION: &str = "unknown";
+
+/// A drop location, its number of drops and its (reason, number of drops) breakdown.
+type DropBreakdown<'a> = (&'a str, u64, Vec<(&'a str, u64)>);
+
+/// DropSummary aggregates drops by location (the kernel function which dropped the packet) and
+/// then by drop reason, for a two-level breakdown. Both levels are memory-bounded (see `TopN`),
+/// counts being estimates once more locations or reasons than the capacity were seen.
+pub(crate) struct DropSummary {
+    /// Total number of drops.
+    total: u64,
+    /// Maximum number of locations, and of reasons per location, tracked.
+    capacity: usize,
+    /// Number of drops per location.
+    locations: TopN<String>,
+    /// Number of drops per reason, indexed by location. Only tracked locations have reasons.
+    reasons: HashMap<String, TopN<String>>,
+}
+
+impl DropSummary {
+    /// Creates a summary tracking at most `capacity` locations, and reasons per location.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            total: 0,
+            capacity,
+            locations: TopN::new(capacity),
+            reasons: HashMap::new(),
+        }
+    }
+
+    /// Process one event, accounting for it if it is a drop.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        let drop = match event.get_section::<SkbDropEvent>(SectionId::SkbDrop) {
+            // Normal frees are not drops.
+            Some(drop) if !drop.consumed => drop,
+            _ => return,
+        };
+
+        let reason = match &drop.subsys {
+            Some(subsys) => format!("{subsys}/{}", drop.drop_reason),
+            None => drop.drop_reason.clone(),
+        };
+        let location = drop
+            .drop_location
+            .clone()
+            .unwrap_or_else(|| UNKNOWN_LOCATION.to_string());
+
+        if let Some(evicted) = self.locations.insert(location.clone()) {
+            self.reasons.remove(&evicted);
+        }
+        self.reasons
+            .entry(location)
+            .or_insert_with(|| TopN::new(self.capacity))
+            .insert(reason);
+        self.total += 1;
+    }
+
+    /// Are the reported counts exact?
+    fn is_exact(&self) -> bool {
+        self.locations.is_exact() && self.reasons.values().all(|r| r.is_exact())
+    }
+
+    /// Returns the `n` locations with the most drops, along with their `n` most common reasons.
+    pub(crate) fn top(&self, n: usize) -> Vec<DropBreakdown<'_>> {
+        self.locations
+            .top(n)
+            .into_iter()
+            .map(|(name, count, _)| {
+                let reasons = self
+                    .reasons
+                    .get(name)
+                    .map(|reasons| {
+                        reasons
+                            .top(n)
+                            .into_iter()
+                            .map(|(r, count, _)| (r.as_str(), count))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (name.as_str(), count, reasons)
+            })
+            .collect()
+    }
+
+    /// Write the `n` locations with the most drops, and their `n` most common reasons.
+    pub(crate) fn write(&self, writer: &mut dyn Write, n: usize) -> Result<()> {
+        writeln!(
+            writer,
+            "{} drop(s){}",
+            self.total,
+            if self.is_exact() {
+                ""
+            } else {
+                " (estimated counts)"
+            }
+        )?;
+
+        for (location, count, reasons) in self.top(n) {
+            writeln!(
+                writer,
+                "  {location}: {count} ({:.1}%)",
+                count as f64 * 100.0 / self.total as f64
+            )?;
+            for (reason, count) in reasons {
+                writeln!(writer, "    {reason}: {count}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// WindowedRate counts events in fixed-size time windows, to report both their average and their
+/// peak rate over a capture.
+pub(crate) struct WindowedRate {
+    /// Size of a window, in nanoseconds.
+    window: u64,
+    /// Number of events, indexed by window (timestamp / window).
+    counts: BTreeMap<u64, u64>,
+    /// Total number of events.
+    total: u64,
+}
+
+impl WindowedRate {
+    /// Creates a new aggregator using windows of `window` nanoseconds (at least 1).
+    pub(crate) fn new(window: u64) -> Self {
+        Self {
+            window: window.max(1),
+            counts: BTreeMap::new(),
+            total: 0,
+        }
+    }
+
+    /// Accounts for an event generated at `timestamp` (in nanoseconds).
+    pub(crate) fn record(&mut self, timestamp: u64) {
+        *self.counts.entry(timestamp / self.window).or_default() += 1;
+        self.total += 1;
+    }
+
+    /// Total number of events.
+    pub(crate) fn total(&self) -> u64 {
+        self.total
+    }
+
+    fn per_sec(&self, count: u64) -> f64 {
+        count as f64 * 1_000_000_000.0 / self.window as f64
+    }
+
+    /// Average number of events per second, between the first and the last windows having
+    /// events.
+    pub(crate) fn mean(&self) -> f64 {
+        match (self.counts.keys().next(), self.counts.keys().next_back()) {
+            (Some(first), Some(last)) => self.per_sec(self.total) / (last - first + 1) as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Number of events per second in the busiest window.
+    pub(crate) fn peak(&self) -> f64 {
+        self.per_sec(self.counts.values().copied().max().unwrap_or_default())
+    }
+}
+
+/// OvsRateSummary tracks the rate of upcalls and of flow operations, as a measure of the flow
+/// table churn. High flow put and deletion rates usually go with upcall storms and revalidation
+/// pressure.
+pub(crate) struct OvsRateSummary {
+    upcalls: WindowedRate,
+    puts: WindowedRate,
+    dels: WindowedRate,
+}
+
+impl OvsRateSummary {
+    /// Creates a new summary using windows of `window` nanoseconds.
+    pub(crate) fn new(window: u64) -> Self {
+        Self {
+            upcalls: WindowedRate::new(window),
+            puts: WindowedRate::new(window),
+            dels: WindowedRate::new(window),
+        }
+    }
+
+    /// Process one event, accounting for it if it is an upcall or a flow put/del operation.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        let (ovs, common) = match (
+            event.get_section::<OvsEvent>(SectionId::Ovs),
+            event.get_section::<CommonEvent>(SectionId::Common),
+        ) {
+            (Some(ovs), Some(common)) => (ovs, common),
+            _ => return,
+        };
+
+        let rate = match &ovs.event {
+            OvsEventType::Upcall(_) => &mut self.upcalls,
+            OvsEventType::Operation(op) => match op.op_type {
+                OVS_OP_PUT => &mut self.puts,
+                OVS_OP_DEL => &mut self.dels,
+                _ => return,
+            },
+            _ => return,
+        };
+        rate.record(common.timestamp);
+    }
+
+    /// Write the upcall and flow operation rates, if any was seen. Flow deletions are only
+    /// reported if seen, as they are not retrieved by all probes.
+    pub(crate) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        let rates = [
+            ("upcall", &self.upcalls),
+            ("flow_put", &self.puts),
+            ("flow_del", &self.dels),
+        ];
+        if rates.iter().all(|(_, rate)| rate.total() == 0) {
+            return Ok(());
+        }
+
+        writeln!(writer, "ovs rates (per second)")?;
+        for (name, rate) in rates {
+            if name == "flow_del" && rate.total() == 0 {
+                continue;
+            }
+            writeln!(
+                writer,
+                "  {name}: {} total, {:.1} avg, {:.1} peak",
+                rate.total(),
+                rate.mean(),
+                rate.peak()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// UpcallPortSummary reports the ports generating the most upcalls. It is memory-bounded (see
+/// `TopN`).
+pub(crate) struct UpcallPortSummary {
+    total: u64,
+    ports: TopN<u32>,
+}
+
+impl UpcallPortSummary {
+    /// Creates a summary tracking at most `capacity` ports.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            total: 0,
+            ports: TopN::new(capacity),
+        }
+    }
+
+    /// Process one event, accounting for it if it is an upcall.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        if let Some(OvsEvent {
+            event: OvsEventType::Upcall(upcall),
+        }) = event.get_section::<OvsEvent>(SectionId::Ovs)
+        {
+            self.ports.insert(upcall.port);
+            self.total += 1;
+        }
+    }
+
+    /// Write the `n` ports generating the most upcalls, if any upcall was seen.
+    pub(crate) fn write(&self, writer: &mut dyn Write, n: usize) -> Result<()> {
+        if self.total == 0 {
+            return Ok(());
+        }
+
+        writeln!(
+            writer,
+            "upcalls per port{}",
+            if self.ports.is_exact() {
+                ""
+            } else {
+                " (estimated counts)"
+            }
+        )?;
+        for (port, count, _) in self.ports.top(n) {
+            writeln!(
+                writer,
+                "  port {port}: {count} ({:.1}%)",
+                count as f64 * 100.0 / self.total as f64
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Upcall command for flow misses.
+const OVS_PACKET_CMD_MISS: u8 = 1;
+
+/// Default maximum number of miss upcalls waiting for their flow put.
+const FLOW_INSTALL_MAX_PENDING: usize = 8192;
+
+/// Identifies an upcall: its timestamp and CPU.
+type UpcallKey = (u64, u32);
+
+/// FlowInstallLatency measures the time between flow miss upcalls and the installation of the
+/// corresponding flows (the flow put operation), correlating both using the upcall queue id.
+pub(crate) struct FlowInstallLatency {
+    /// Upcalls waiting for their flow put, indexed by queue id. An upcall can be enqueued
+    /// multiple times (e.g. GSO segments).
+    queued: HashMap<u32, UpcallKey>,
+    /// Upcalls not matched to a flow put yet, oldest first, with their queue ids.
+    pending: BTreeMap<UpcallKey, Vec<u32>>,
+    /// Maximum number of pending upcalls. The oldest ones are evicted first.
+    max_pending: usize,
+    /// Number of upcalls evicted before a flow put was seen.
+    evicted: u64,
+    /// Flow install latencies, in nanoseconds.
+    latencies: Vec<u64>,
+}
+
+/// Returns the value at the `p` percentile (nearest rank) of a sorted, non-empty, slice.
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+impl FlowInstallLatency {
+    pub(crate) fn new() -> Self {
+        Self::with_max_pending(FLOW_INSTALL_MAX_PENDING)
+    }
+
+    pub(crate) fn with_max_pending(max_pending: usize) -> Self {
+        Self {
+            queued: HashMap::new(),
+            pending: BTreeMap::new(),
+            max_pending,
+            evicted: 0,
+            latencies: Vec::new(),
+        }
+    }
+
+    /// Stops tracking an upcall, returning whether it was pending.
+    fn remove(&mut self, key: &UpcallKey) -> bool {
+        match self.pending.remove(key) {
+            Some(queue_ids) => {
+                queue_ids.iter().for_each(|queue_id| {
+                    // The queue id can be reused by a later upcall.
+                    if self.queued.get(queue_id) == Some(key) {
+                        self.queued.remove(queue_id);
+                    }
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Process one event, accounting for it if it is a miss upcall enqueue or a flow put.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        let ovs = match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(ovs) => ovs,
+            None => return,
+        };
+
+        match &ovs.event {
+            // Failed enqueues never reach userspace.
+            OvsEventType::UpcallEnqueue(enqueue)
+                if enqueue.cmd == OVS_PACKET_CMD_MISS && enqueue.ret == 0 =>
+            {
+                let key = (enqueue.upcall_ts, enqueue.upcall_cpu);
+                self.queued.insert(enqueue.queue_id, key);
+                self.pending.entry(key).or_default().push(enqueue.queue_id);
+
+                if self.pending.len() > self.max_pending {
+                    if let Some(oldest) = self.pending.keys().next().copied() {
+                        self.remove(&oldest);
+                        self.evicted += 1;
+                    }
+                }
+            }
+            OvsEventType::Operation(op) if op.op_type == OVS_OP_PUT => {
+                let common = match event.get_section::<CommonEvent>(SectionId::Common) {
+                    Some(common) => common,
+                    None => return,
+                };
+                if let Some(key) = self.queued.get(&op.queue_id).copied() {
+                    if self.remove(&key) {
+                        self.latencies.push(common.timestamp.saturating_sub(key.0));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Number of miss upcalls which did not result in a flow put, e.g. because the packet was
+    /// dropped in userspace. Includes the upcalls evicted before a flow put was seen.
+    pub(crate) fn unmatched(&self) -> u64 {
+        self.pending.len() as u64 + self.evicted
+    }
+
+    /// Write the flow install latency distribution and the number of unmatched upcalls, if any
+    /// miss upcall was seen.
+    pub(crate) fn write(&mut self, writer: &mut dyn Write) -> Result<()> {
+        if self.latencies.is_empty() && self.unmatched() == 0 {
+            return Ok(());
+        }
+
+        let us = |ns: u64| ns as f64 / 1000.0;
+        writeln!(writer, "flow install latency (miss upcall to flow put)")?;
+        if !self.latencies.is_empty() {
+            self.latencies.sort_unstable();
+            let lat = &self.latencies;
+            writeln!(
+                writer,
+                "  {} flow(s): min {:.1}us p50 {:.1}us p90 {:.1}us p99 {:.1}us max {:.1}us",
+                lat.len(),
+                us(lat[0]),
+                us(percentile(lat, 50)),
+                us(percentile(lat, 90)),
+                us(percentile(lat, 99)),
+                us(lat[lat.len() - 1]),
+            )?;
+        }
+        write!(writer, "  {} unmatched miss upcall(s)", self.unmatched())?;
+        if self.evicted > 0 {
+            write!(writer, " ({} evicted)", self.evicted)?;
+        }
+        writeln!(writer)?;
+
+        Ok(())
+    }
+}
+
+/// FailedOpsSummary counts flow operations which failed in the kernel, by operation type and
+/// error, e.g. flow puts failing with EEXIST or ENOMEM.
+#[derive(Default)]
+pub(crate) struct FailedOpsSummary {
+    /// Number of failed operations, indexed by (operation type, error).
+    failures: BTreeMap<(u8, i32), u64>,
+}
+
+impl FailedOpsSummary {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, accounting for it if it is a failed flow operation.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        if let Some(OvsEvent {
+            event:
+                OvsEventType::Operation(OperationEvent {
+                    op_type,
+                    error: Some(error),
+                    ..
+                }),
+        }) = event.get_section::<OvsEvent>(SectionId::Ovs)
+        {
+            *self.failures.entry((*op_type, *error)).or_default() += 1;
+        }
+    }
+
+    /// Write the number of failed flow operations per type and error, if any.
+    pub(crate) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(
+            writer,
+            "{} failed flow operation(s)",
+            self.failures.values().sum::<u64>()
+        )?;
+        for ((op_type, error), count) in &self.failures {
+            let op = match *op_type {
+                OVS_OP_EXEC => "exec",
+                OVS_OP_PUT => "put",
+                OVS_OP_DEL => "del",
+                OVS_OP_GET => "get",
+                _ => "?",
+            };
+            writeln!(
+                writer,
+                "  flow_{op} {}: {count}",
+                errno_fmt(*error, errno_str(*error))
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// FailedActionsSummary counts the executions of action lists which failed in the datapath, by
+/// type of the action which failed and error, e.g. outputs to a port which is down.
+#[derive(Default)]
+pub(crate) struct FailedActionsSummary {
+    failures: BTreeMap<(&'static str, i32), u64>,
+}
+
+impl FailedActionsSummary {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, accounting for it if it is a failed action.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        if let Some(OvsEvent {
+            event:
+                OvsEventType::Action(ActionEvent {
+                    action,
+                    error: Some(error),
+                    ..
+                }),
+        }) = event.get_section::<OvsEvent>(SectionId::Ovs)
+        {
+            let name = action.as_ref().map(|a| a.name()).unwrap_or("unspec");
+            *self.failures.entry((name, *error)).or_default() += 1;
+        }
+    }
+
+    /// Write the number of failed actions per type and error, if any.
+    pub(crate) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(
+            writer,
+            "{} failed action execution(s)",
+            self.failures.values().sum::<u64>()
+        )?;
+        for ((action, error), count) in &self.failures {
+            writeln!(
+                writer,
+                "  {action} {}: {count}",
+                errno_fmt(*error, errno_str(*error))
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Default minimum number of consecutive enqueue failures for an upcall queue to be reported as
+/// persistently full.
+pub(crate) const QUEUE_FULL_THRESHOLD_DEFAULT: u64 = 5;
+
+/// Run of consecutive enqueue failures on a queue.
+#[derive(Debug, PartialEq)]
+struct QueueFullRun {
+    /// Upcall port, identifying the queue.
+    port: u32,
+    failures: u64,
+    /// Timestamps of the first and last failures.
+    first: u64,
+    last: u64,
+}
+
+/// QueueFullDetector reports upcall queues which were persistently full: runs of consecutive
+/// upcalls failing to be enqueued with ENOBUFS. Queues are identified by the upcall port, i.e. the
+/// netlink socket upcalls are sent to, as the enqueue queue id identifies the packet. A successful
+/// enqueue on a queue ends its run.
+pub(crate) struct QueueFullDetector {
+    /// Minimum number of failures for a run to be reported.
+    threshold: u64,
+    /// Runs in progress, indexed by port.
+    current: HashMap<u32, QueueFullRun>,
+    /// Runs which ended and reached the threshold, in order.
+    full: Vec<QueueFullRun>,
+}
+
+impl QueueFullDetector {
+    pub(crate) fn new(threshold: u64) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            current: HashMap::new(),
+            full: Vec::new(),
+        }
+    }
+
+    /// Process one event, accounting for it if it is an upcall enqueue.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        let enqueue = match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event: OvsEventType::UpcallEnqueue(enqueue),
+            }) => enqueue,
+            _ => return,
+        };
+
+        match enqueue.ret {
+            0 => {
+                if let Some(run) = self.current.remove(&enqueue.port) {
+                    if run.failures >= self.threshold {
+                        self.full.push(run);
+                    }
+                }
+            }
+            // Error returned when enqueuing an upcall to a full netlink socket.
+            ret if ret_errno_str(ret) == Some("ENOBUFS") => {
+                let ts = event
+                    .get_section::<CommonEvent>(SectionId::Common)
+                    .map(|common| common.timestamp)
+                    .unwrap_or_default();
+                let run = self
+                    .current
+                    .entry(enqueue.port)
+                    .or_insert_with(|| QueueFullRun {
+                        port: enqueue.port,
+                        failures: 0,
+                        first: ts,
+                        last: ts,
+                    });
+                run.failures += 1;
+                run.last = ts;
+            }
+            // Other failures are not related to the queue being full.
+            _ => (),
+        }
+    }
+
+    /// Write the runs of consecutive enqueue failures reaching the threshold, including the ones
+    /// still in progress at the end of the capture, if any.
+    pub(crate) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        let mut ongoing: Vec<_> = self
+            .current
+            .values()
+            .filter(|run| run.failures >= self.threshold)
+            .collect();
+        if self.full.is_empty() && ongoing.is_empty() {
+            return Ok(());
+        }
+        ongoing.sort_by_key(|run| (run.first, run.port));
+
+        writeln!(writer, "upcall queue(s) persistently full")?;
+        let runs = self.full.iter().map(|run| (run, false));
+        for (run, in_progress) in runs.chain(ongoing.into_iter().map(|run| (run, true))) {
+            writeln!(
+                writer,
+                "  port {}: {} consecutive ENOBUFS failure(s) over {:.1}us from {}{}",
+                run.port,
+                run.failures,
+                run.last.saturating_sub(run.first) as f64 / 1000.0,
+                run.first,
+                if in_progress { " (until the end)" } else { "" },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maximum length of the recirculation cycles looked for. Longer chains, cyclic or not, are not
+/// reported.
+const RECIRC_CYCLE_MAX_LEN: usize = 16;
+/// Maximum number of recirculation cycles looked for, bounding the detection on dense graphs.
+const RECIRC_CYCLE_MAX: usize = 64;
+
+/// RecircCycles detects cycles in the recirculation graph, i.e. flows recirculating packets to
+/// recirculation ids eventually leading back to themselves. The graph is built from recirc
+/// actions: an edge goes from the recirculation id of the flow executing the action to its target
+/// id.
+#[derive(Default)]
+pub(crate) struct RecircCycles {
+    /// Recirculation targets, indexed by the recirculation id executing the recirc action.
+    edges: BTreeMap<u32, BTreeSet<u32>>,
+}
+
+impl RecircCycles {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, accounting for it if it is a recirc action.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        if let Some(OvsEvent {
+            event:
+                OvsEventType::Action(ActionEvent {
+                    action: Some(OvsAction::Recirc(recirc)),
+                    recirc_id,
+                    ..
+                }),
+        }) = event.get_section::<OvsEvent>(SectionId::Ovs)
+        {
+            self.edges.entry(*recirc_id).or_default().insert(recirc.id);
+        }
+    }
+
+    /// Returns the cycles of the graph, up to `RECIRC_CYCLE_MAX` of them and of at most
+    /// `RECIRC_CYCLE_MAX_LEN` recirculation ids each. Each cycle starts with its lowest id.
+    fn cycles(&self) -> Vec<Vec<u32>> {
+        let mut cycles = Vec::new();
+
+        // Look for paths leading back to each id, only going through higher ids so each cycle
+        // is found once, from its lowest id.
+        for &start in self.edges.keys() {
+            let mut path = vec![start];
+            self.find_cycles(start, &mut path, &mut cycles);
+            if cycles.len() >= RECIRC_CYCLE_MAX {
+                break;
+            }
+        }
+
+        cycles
+    }
+
+    fn find_cycles(&self, start: u32, path: &mut Vec<u32>, cycles: &mut Vec<Vec<u32>>) {
+        // Unwrap as the path is never empty.
+        let targets = match self.edges.get(path.last().unwrap()) {
+            Some(targets) => targets,
+            None => return,
+        };
+
+        for &next in targets {
+            if cycles.len() >= RECIRC_CYCLE_MAX {
+                return;
+            }
+            if next == start {
+                cycles.push(path.clone());
+            } else if next > start && path.len() < RECIRC_CYCLE_MAX_LEN && !path.contains(&next) {
+                path.push(next);
+                self.find_cycles(start, path, cycles);
+                path.pop();
+            }
+        }
+    }
+
+    /// Write the recirculation cycles found, if any.
+    pub(crate) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        let cycles = self.cycles();
+        if cycles.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(writer, "recirc cycle(s) detected")?;
+        for cycle in cycles.iter() {
+            let ids: Vec<_> = cycle
+                .iter()
+                .chain(std::iter::once(&cycle[0]))
+                .map(|id| format!("{id:#x}"))
+                .collect();
+            writeln!(writer, "  {}", ids.join(" -> "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Width of the per-CPU bars, in characters.
+const CPU_BAR_WIDTH: u64 = 40;
+/// Sparkline levels, from lowest to highest. CPUs w/o events are left blank so gaps stand out.
+const SPARKLINE: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// CpuSummary counts events per CPU, to visualize how the load was distributed.
+#[derive(Default)]
+pub(crate) struct CpuSummary {
+    counts: BTreeMap<u32, u64>,
+}
+
+impl CpuSummary {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, accounting for it on the CPU it was generated on. The common section
+    /// is used if it holds a CPU, otherwise upcall events provide theirs.
+    pub(crate) fn process_one(&mut self, event: &Event) {
+        let cpu = event
+            .get_section::<CommonEvent>(SectionId::Common)
+            .and_then(|common| common.smp_id)
+            .or_else(|| match event.get_section::<OvsEvent>(SectionId::Ovs) {
+                Some(OvsEvent {
+                    event: OvsEventType::Upcall(upcall),
+                }) => Some(upcall.cpu),
+                _ => None,
+            });
+
+        if let Some(cpu) = cpu {
+            *self.counts.entry(cpu).or_default() += 1;
+        }
+    }
+
+    /// Event counts for all CPUs up to the highest one seen, including CPUs w/o events.
+    fn counts(&self) -> Vec<(u32, u64)> {
+        match self.counts.last_key_value() {
+            Some((max, _)) => (0..=*max)
+                .map(|cpu| (cpu, self.counts.get(&cpu).copied().unwrap_or(0)))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Write a sparkline of the per-CPU event counts followed by a bar per CPU, if any event
+    /// had CPU information.
+    pub(crate) fn write(&self, writer: &mut dyn Write) -> Result<()> {
+        let counts = self.counts();
+        let max = match counts.iter().map(|(_, n)| *n).max() {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+
+        let sparkline: String = counts
+            .iter()
+            .map(|(_, n)| match n {
+                0 => ' ',
+                n => SPARKLINE[((n * SPARKLINE.len() as u64 - 1) / max) as usize],
+            })
+            .collect();
+        writeln!(writer, "events per cpu [{sparkline}]")?;
+
+        let width = counts
+            .last()
+            .map(|(cpu, _)| cpu.to_string().len())
+            .unwrap_or(1);
+        for (cpu, n) in counts {
+            writeln!(
+                writer,
+                "  cpu {cpu:>width$}: {} {n}",
+                "#".repeat((n * CPU_BAR_WIDTH).div_ceil(max) as usize),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::ovs_event;
+    use crate::process::top_n::TOP_N_CAPACITY_DEFAULT;
+
+    fn drop_event(location: Option<&str>, subsys: Option<&str>, reason: &str) -> Event {
+        let mut event = Event::new();
+        event
+            .insert_section(
+                SectionId::SkbDrop,
+                Box::new(SkbDropEvent {
+                    subsys: subsys.map(String::from),
+                    drop_reason: reason.to_string(),
+                    reason_resolved: true,
+                    drop_location: location.map(String::from),
+                    consumed: false,
+                    ct: None,
+                }),
+            )
+            .unwrap();
+        event
+    }
+
+    #[test]
+    fn drop_summary() -> Result<()> {
+        let mut summary = DropSummary::new(TOP_N_CAPACITY_DEFAULT);
+
+        summary.process_one(&Event::new());
+        for _ in 0..3 {
+            summary.process_one(&drop_event(Some("tcp_v4_rcv"), None, "NO_SOCKET"));
+        }
+        summary.process_one(&drop_event(Some("tcp_v4_rcv"), None, "TCP_CSUM"));
+        summary.process_one(&drop_event(Some("__udp4_lib_rcv"), None, "UDP_CSUM"));
+        summary.process_one(&drop_event(
+            None,
+            Some("openvswitch"),
+            "OVS_DROP_LAST_ACTION",
+        ));
+        // Normal frees are not accounted.
+        let mut free = drop_event(None, None, "NOT_SPECIFIED");
+        free.get_section_mut::<SkbDropEvent>(SectionId::SkbDrop)
+            .unwrap()
+            .consumed = true;
+        summary.process_one(&free);
+
+        assert_eq!(summary.total, 6);
+        assert_eq!(
+            summary.top(2),
+            vec![
+                ("tcp_v4_rcv", 4, vec![("NO_SOCKET", 3), ("TCP_CSUM", 1)]),
+                ("__udp4_lib_rcv", 1, vec![("UDP_CSUM", 1)]),
+            ]
+        );
+        assert_eq!(
+            summary.top(3)[2],
+            ("unknown", 1, vec![("openvswitch/OVS_DROP_LAST_ACTION", 1)])
+        );
+
+        let mut out = Vec::new();
+        summary.write(&mut out, 1)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "6 drop(s)\n  tcp_v4_rcv: 4 (66.7%)\n    NO_SOCKET: 3\n"
+        );
+
+        Ok(())
+    }
+
+    fn op(op_type: u8) -> OvsEventType {
+        OvsEventType::Operation(OperationEvent {
+            op_type,
+            ..Default::default()
+        })
+    }
+
+    fn enqueue(upcall_ts: u64, queue_id: u32, cmd: u8, ret: i32) -> OvsEventType {
+        OvsEventType::UpcallEnqueue(UpcallEnqueueEvent {
+            ret,
+            cmd,
+            upcall_ts,
+            queue_id,
+            ..Default::default()
+        })
+    }
+
+    fn put(queue_id: u32) -> OvsEventType {
+        OvsEventType::Operation(OperationEvent {
+            op_type: OVS_OP_PUT,
+            queue_id,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn upcall_port_summary() -> Result<()> {
+        let mut summary = UpcallPortSummary::new(2);
+
+        let upcall = |port| {
+            OvsEventType::Upcall(UpcallEvent {
+                port,
+                ..Default::default()
+            })
+        };
+        (0..3).for_each(|_| summary.process_one(&ovs_event(0, upcall(7))));
+        summary.process_one(&ovs_event(0, upcall(5)));
+        summary.process_one(&ovs_event(0, op(OVS_OP_PUT)));
+
+        let mut out = Vec::new();
+        summary.write(&mut out, 1)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "upcalls per port\n  port 7: 3 (75.0%)\n"
+        );
+
+        // Port 5 is evicted, port 2 inherits its count.
+        summary.process_one(&ovs_event(0, upcall(2)));
+        let mut out = Vec::new();
+        summary.write(&mut out, 2)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "upcalls per port (estimated counts)\n  port 7: 3 (60.0%)\n  port 2: 2 (40.0%)\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn flow_install_latency() -> Result<()> {
+        let mut lat = FlowInstallLatency::new();
+
+        // Upcall 1000, enqueued twice (segments), installed at 21000.
+        lat.process_one(&ovs_event(1000, enqueue(1000, 1, OVS_PACKET_CMD_MISS, 0)));
+        lat.process_one(&ovs_event(1100, enqueue(1000, 2, OVS_PACKET_CMD_MISS, 0)));
+        lat.process_one(&ovs_event(21000, put(1)));
+        lat.process_one(&ovs_event(22000, put(2)));
+        // Upcall 5000, installed at 10000.
+        lat.process_one(&ovs_event(5000, enqueue(5000, 3, OVS_PACKET_CMD_MISS, 0)));
+        lat.process_one(&ovs_event(10000, put(3)));
+        // Upcall 7000, never installed.
+        lat.process_one(&ovs_event(7000, enqueue(7000, 4, OVS_PACKET_CMD_MISS, 0)));
+        // Ignored: failed enqueue, action upcall and exec operation.
+        lat.process_one(&ovs_event(8000, enqueue(8000, 5, OVS_PACKET_CMD_MISS, -11)));
+        lat.process_one(&ovs_event(8000, enqueue(8000, 6, 2, 0)));
+        lat.process_one(&ovs_event(9000, op(OVS_OP_EXEC)));
+
+        assert_eq!(lat.latencies, vec![20000, 5000]);
+        assert_eq!(lat.unmatched(), 1);
+        // Matched upcalls are not tracked anymore.
+        assert_eq!(lat.queued.len(), 1);
+
+        let mut out = Vec::new();
+        lat.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "flow install latency (miss upcall to flow put)\n  2 flow(s): min 5.0us p50 5.0us p90 20.0us p99 20.0us max 20.0us\n  1 unmatched miss upcall(s)\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn flow_install_latency_eviction() -> Result<()> {
+        let mut lat = FlowInstallLatency::with_max_pending(2);
+
+        // Upcalls 1000 and 2000 are never installed, the oldest gets evicted.
+        lat.process_one(&ovs_event(1000, enqueue(1000, 1, OVS_PACKET_CMD_MISS, 0)));
+        lat.process_one(&ovs_event(2000, enqueue(2000, 2, OVS_PACKET_CMD_MISS, 0)));
+        lat.process_one(&ovs_event(3000, enqueue(3000, 3, OVS_PACKET_CMD_MISS, 0)));
+        assert_eq!(lat.pending.len(), 2);
+        assert_eq!(lat.queued.len(), 2);
+        assert_eq!(lat.unmatched(), 3);
+
+        // Evicted, not matched.
+        lat.process_one(&ovs_event(4000, put(1)));
+        lat.process_one(&ovs_event(5000, put(3)));
+        assert_eq!(lat.latencies, vec![2000]);
+        assert_eq!(lat.unmatched(), 2);
+
+        let mut out = Vec::new();
+        lat.write(&mut out)?;
+        assert!(String::from_utf8(out)?.ends_with("  2 unmatched miss upcall(s) (1 evicted)\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn ovs_rate_summary() -> Result<()> {
+        const SEC: u64 = 1_000_000_000;
+        let mut summary = OvsRateSummary::new(SEC);
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert!(out.is_empty());
+
+        // 3 puts in the first second, 1 in the third one.
+        for ts in [0, SEC / 2, SEC - 1, 2 * SEC] {
+            summary.process_one(&ovs_event(10 * SEC + ts, op(OVS_OP_PUT)));
+        }
+        summary.process_one(&ovs_event(10 * SEC, op(OVS_OP_EXEC)));
+        summary.process_one(&ovs_event(
+            11 * SEC,
+            OvsEventType::Upcall(UpcallEvent::default()),
+        ));
+        summary.process_one(&Event::new());
+
+        assert_eq!(summary.puts.total(), 4);
+        assert_eq!(summary.puts.peak(), 3.0);
+        assert!((summary.puts.mean() - 4.0 / 3.0).abs() < f64::EPSILON);
+
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "ovs rates (per second)\n  upcall: 1 total, 1.0 avg, 1.0 peak\n  flow_put: 4 total, 1.3 avg, 3.0 peak\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn ovs_rate_summary_dels() -> Result<()> {
+        const SEC: u64 = 1_000_000_000;
+        let mut summary = OvsRateSummary::new(SEC);
+
+        summary.process_one(&ovs_event(10 * SEC, op(OVS_OP_PUT)));
+        for ts in [0, SEC / 2, 2 * SEC] {
+            summary.process_one(&ovs_event(10 * SEC + ts, op(OVS_OP_DEL)));
+        }
+        assert_eq!(summary.dels.total(), 3);
+        assert_eq!(summary.dels.peak(), 2.0);
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "ovs rates (per second)\n  upcall: 0 total, 0.0 avg, 0.0 peak\n  flow_put: 1 total, 1.0 avg, 1.0 peak\n  flow_del: 3 total, 1.0 avg, 2.0 peak\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_ops_summary() -> Result<()> {
+        let mut summary = FailedOpsSummary::new();
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert!(out.is_empty());
+
+        let failed = |op_type, error| {
+            OvsEventType::Operation(OperationEvent {
+                op_type,
+                error: Some(error),
+                ..Default::default()
+            })
+        };
+        summary.process_one(&ovs_event(0, failed(OVS_OP_PUT, -17)));
+        summary.process_one(&ovs_event(0, failed(OVS_OP_PUT, -12)));
+        summary.process_one(&ovs_event(0, failed(OVS_OP_PUT, -17)));
+        summary.process_one(&ovs_event(0, failed(OVS_OP_EXEC, -22)));
+        // Successful operations are not accounted for.
+        summary.process_one(&ovs_event(0, op(OVS_OP_PUT)));
+
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "4 failed flow operation(s)\n  flow_exec EINVAL (-22): 1\n  flow_put EEXIST (-17): 2\n  flow_put ENOMEM (-12): 1\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_actions_summary() -> Result<()> {
+        let mut summary = FailedActionsSummary::new();
+
+        let action = |action, error| {
+            OvsEventType::Action(ActionEvent {
+                action: Some(action),
+                error,
+                ..Default::default()
+            })
+        };
+        let output = || OvsAction::Output(OvsActionOutput::default());
+        summary.process_one(&ovs_event(0, action(output(), Some(-19))));
+        summary.process_one(&ovs_event(0, action(output(), Some(-19))));
+        summary.process_one(&ovs_event(
+            0,
+            action(OvsAction::Ct(OvsActionCt::default()), Some(-22)),
+        ));
+        // Successful actions are not accounted for.
+        summary.process_one(&ovs_event(0, action(output(), None)));
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "3 failed action execution(s)\n  ct EINVAL (-22): 1\n  output ENODEV (-19): 2\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_ops_summary_unknown_errno() -> Result<()> {
+        let mut summary = FailedOpsSummary::new();
+        summary.process_one(&ovs_event(
+            0,
+            OvsEventType::Operation(OperationEvent {
+                op_type: OVS_OP_DEL,
+                error: Some(-42),
+                ..Default::default()
+            }),
+        ));
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "1 failed flow operation(s)\n  flow_del -42: 1\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_actions_summary_unknown_errno() -> Result<()> {
+        let mut summary = FailedActionsSummary::new();
+        summary.process_one(&ovs_event(
+            0,
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Output(OvsActionOutput::default())),
+                error: Some(-42),
+                ..Default::default()
+            }),
+        ));
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "1 failed action execution(s)\n  output -42: 1\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn queue_full_detector() -> Result<()> {
+        let mut detector = QueueFullDetector::new(3);
+
+        let mut out = Vec::new();
+        detector.write(&mut out)?;
+        assert!(out.is_empty());
+
+        let enqueue = |port, ret| {
+            OvsEventType::UpcallEnqueue(UpcallEnqueueEvent {
+                ret,
+                port,
+                ..Default::default()
+            })
+        };
+        // Port 7: 3 failures (one unrelated error in between), then a success ending the run.
+        detector.process_one(&ovs_event(1000, enqueue(7, -105)));
+        detector.process_one(&ovs_event(2000, enqueue(7, -105)));
+        detector.process_one(&ovs_event(2500, enqueue(7, -22)));
+        detector.process_one(&ovs_event(3500, enqueue(7, -105)));
+        detector.process_one(&ovs_event(4000, enqueue(7, 0)));
+        // Port 8: below the threshold.
+        detector.process_one(&ovs_event(5000, enqueue(8, -105)));
+        detector.process_one(&ovs_event(6000, enqueue(8, -105)));
+        detector.process_one(&ovs_event(7000, enqueue(8, 0)));
+        // Port 9: still failing at the end of the capture.
+        (0..4).for_each(|i| detector.process_one(&ovs_event(8000 + i * 1000, enqueue(9, -105))));
+
+        detector.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "upcall queue(s) persistently full
+  port 7: 3 consecutive ENOBUFS failure(s) over 2.5us from 1000
+  port 9: 4 consecutive ENOBUFS failure(s) over 3.0us from 8000 (until the end)
+"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn recirc_cycles() -> Result<()> {
+        let mut summary = RecircCycles::new();
+        let recirc = |recirc_id, id| {
+            OvsEventType::Action(ActionEvent {
+                action: Some(OvsAction::Recirc(OvsActionRecirc { id })),
+                recirc_id,
+                ..Default::default()
+            })
+        };
+
+        // A long, acyclic, chain is not flagged.
+        (0..32).for_each(|id| summary.process_one(&ovs_event(0, recirc(id, id + 1))));
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert!(out.is_empty());
+
+        // 0x1 -> 0x2 -> 0x1 (0x1 -> 0x2 is already known), and 0x40 recirculating to itself.
+        summary.process_one(&ovs_event(0, recirc(0x2, 0x1)));
+        summary.process_one(&ovs_event(0, recirc(0x40, 0x40)));
+        // A cycle longer than the detection bound is not flagged.
+        summary.process_one(&ovs_event(0, recirc(0x20, 0x3)));
+
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "recirc cycle(s) detected\n  0x1 -> 0x2 -> 0x1\n  0x40 -> 0x40\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpu_summary() -> Result<()> {
+        let mut summary = CpuSummary::new();
+
+        let mut out = Vec::new();
+        summary.write(&mut out)?;
+        assert!(out.is_empty());
+
+        let cpu_event = |smp_id| {
+            let mut e = Event::new();
+            e.insert_section(
+                SectionId::Common,
+                Box::new(CommonEvent {
+                    smp_id,
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+            e
+        };
+        (0..4).for_each(|_| summary.process_one(&cpu_event(Some(0))));
+        summary.process_one(&cpu_event(Some(3)));
+        // No CPU in the common section, use the upcall one.
+        summary.process_one(&ovs_event(
+            0,
+            OvsEventType::Upcall(UpcallEvent {
+                cpu: 3,
+                ..Default::default()
+            }),
+        ));
+        summary.process_one(&cpu_event(None));
+
+        assert_eq!(summary.counts(), vec![(0, 4), (1, 0), (2, 0), (3, 2)]);
+
+        summary.write(&mut out)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "events per cpu [█  ▄]\n  cpu 0: {} 4\n  cpu 1:  0\n  cpu 2:  0\n  cpu 3: {} 2\n",
+                "#".repeat(40),
+                "#".repeat(20)
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Memory-bounded top-N tracker.
+//!
+//! Aggregations over unbounded streams (drop reasons, ports, functions, etc.) can't store a
+//! counter per key. `TopN` implements the Space-Saving algorithm: at most `capacity` keys are
+//! tracked and when a new key is seen while full, it replaces the key with the lowest count and
+//! inherits that count.
+//!
+//! Counts can thus be overestimated, by at most the number of events divided by the capacity;
+//! the maximum overestimation of each key is reported as its error. Keys whose real count is
+//! higher than this bound are guaranteed to be tracked. As long as no key was evicted, counts
+//! are exact.
+
+use std::{collections::HashMap, hash::Hash};
+
+/// Default number of keys tracked by aggregations.
+pub(crate) const TOP_N_CAPACITY_DEFAULT: usize = 1024;
+
+#[derive(Clone, Copy, Default)]
+struct Counter {
+    count: u64,
+    error: u64,
+}
+
+/// Space-Saving top-N tracker.
+pub(crate) struct TopN<K> {
+    capacity: usize,
+    counters: HashMap<K, Counter>,
+    /// At least one key was evicted, counts are estimates.
+    evicted: bool,
+}
+
+impl<K> TopN<K>
+where
+    K: Clone + Eq + Hash + Ord,
+{
+    /// Creates a new tracker holding at most `capacity` keys (at least one).
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters: HashMap::new(),
+            evicted: false,
+        }
+    }
+
+    /// Accounts for one occurrence of `key`. Returns the key evicted to make room for it, if
+    /// any.
+    pub(crate) fn insert(&mut self, key: K) -> Option<K> {
+        if let Some(counter) = self.counters.get_mut(&key) {
+            counter.count += 1;
+            return None;
+        }
+
+        if self.counters.len() < self.capacity {
+            self.counters.insert(key, Counter { count: 1, error: 0 });
+            return None;
+        }
+
+        // Evict the key with the lowest count, using the key itself as a tie breaker for the
+        // result to be deterministic. Unwrap as the tracker is full, hence not empty.
+        let (min_key, min) = self
+            .counters
+            .iter()
+            .min_by(|a, b| a.1.count.cmp(&b.1.count).then_with(|| a.0.cmp(b.0)))
+            .map(|(k, c)| (k.clone(), *c))
+            .unwrap();
+        self.counters.remove(&min_key);
+        self.counters.insert(
+            key,
+            Counter {
+                count: min.count + 1,
+                error: min.count,
+            },
+        );
+        self.evicted = true;
+
+        Some(min_key)
+    }
+
+    /// Returns the `n` keys with the highest counts as (key, count, error), by decreasing count
+    /// and by key for equal counts.
+    pub(crate) fn top(&self, n: usize) -> Vec<(&K, u64, u64)> {
+        let mut items: Vec<_> = self
+            .counters
+            .iter()
+            .map(|(k, c)| (k, c.count, c.error))
+            .collect();
+        items.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        items.truncate(n);
+        items
+    }
+
+    /// Are the counts exact, i.e. no key was ever evicted?
+    pub(crate) fn is_exact(&self) -> bool {
+        !self.evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_n() {
+        let mut top = TopN::new(3);
+
+        for key in ["a", "a", "a", "b", "b", "c"] {
+            assert_eq!(top.insert(key), None);
+        }
+        assert!(top.is_exact());
+        assert_eq!(top.top(2), vec![(&"a", 3, 0), (&"b", 2, 0)]);
+
+        // "c" has the lowest count and is replaced.
+        assert_eq!(top.insert("d"), Some("c"));
+        assert!(!top.is_exact());
+        assert_eq!(top.top(3), vec![(&"a", 3, 0), (&"b", 2, 0), (&"d", 2, 1)]);
+
+        // Frequent keys are kept on a long tail of unique ones.
+        (0..100).for_each(|i| {
+            top.insert("a");
+            top.insert(["x", "y", "z"][i % 3]);
+        });
+        assert_eq!(top.top(1)[0].0, &"a");
+    }
+}
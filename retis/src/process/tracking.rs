@@ -39,6 +39,9 @@ pub(crate) struct AddTracking {
     /// When an upcall happens, the packet might get fragmented. This map is used to use the same
     /// TrackingInfo for all fragments.
     ovs_upcalls_tracking: HashMap<UpcallKey, Arc<Mutex<TrackingInfo>>>,
+    /// Collection session of the events being processed. Events of different sessions are never
+    /// correlated: the tracking state is reset when a new session starts.
+    session_id: Option<u64>,
 }
 
 impl AddTracking {
@@ -47,11 +50,22 @@ impl AddTracking {
             skb_tracking: HashMap::new(),
             ovs_queue_tracking: HashMap::new(),
             ovs_upcalls_tracking: HashMap::new(),
+            session_id: None,
         }
     }
 
     /// Process one event adding TrackingInfo section.
     pub(crate) fn process_one(&mut self, event: &mut Event) -> Result<()> {
+        let session_id = event
+            .get_section::<CommonEvent>(SectionId::Common)
+            .and_then(|c| c.session_id);
+        if session_id != self.session_id {
+            *self = Self {
+                session_id,
+                ..Self::new()
+            };
+        }
+
         if let Some(ovs) = event.get_section::<OvsEvent>(SectionId::Ovs) {
             use OvsEventType::*;
             match &ovs.event {
@@ -116,8 +130,10 @@ impl AddTracking {
                     Some(queue_id) => {
                         // This action event came from an upcall. Restore the tracking id of the
                         // original packet.
+                        let recirc_id = act.recirc_id;
                         let info = self.lookup_ovs_queue(queue_id)?;
                         info.lock().unwrap().idx += 1;
+                        Self::process_ovs_recirc(&info, recirc_id);
 
                         // Add an entry in the skb tracking table so that futre non-ovs events also
                         // get the tracking id from the original (upcalled) packet.
@@ -130,7 +146,11 @@ impl AddTracking {
                         Self::insert_info(event, &info)?;
                     }
                     None => {
-                        self.process_skb(event)?;
+                        let recirc_id = act.recirc_id;
+                        if let Some(info) = self.lookup_skb(event)? {
+                            Self::process_ovs_recirc(&info, recirc_id);
+                            Self::insert_info(event, &info)?;
+                        }
                     }
                 },
             }
@@ -155,9 +175,30 @@ impl AddTracking {
         Ok(())
     }
 
+    // Extend the OvS recirculation chain of a packet, given the recirculation id an action was
+    // executed with. Recirc actions nested in sample or clone actions are deferred by the
+    // datapath, and other actions can be executed in between: the chain is built from the
+    // recirculation id actions are executed with, not from the recirc actions themselves.
+    fn process_ovs_recirc(info: &Arc<Mutex<TrackingInfo>>, recirc_id: u32) {
+        let mut info = info.lock().unwrap();
+        if info.ovs_recirc.last() != Some(&recirc_id) {
+            info.ovs_recirc.push(recirc_id);
+        }
+    }
+
     // Add tracking information to an event based on skb-tracking id if it exists.
     // Returns the TrackingInformation pointer if skb-tracking information was available.
     fn process_skb(&mut self, event: &mut Event) -> Result<Option<Arc<Mutex<TrackingInfo>>>> {
+        let info = self.lookup_skb(event)?;
+        if let Some(info) = &info {
+            Self::insert_info(event, info)?;
+        }
+        Ok(info)
+    }
+
+    // Lookup the tracking information of an event based on skb-tracking id if it exists, without
+    // adding it to the event.
+    fn lookup_skb(&mut self, event: &Event) -> Result<Option<Arc<Mutex<TrackingInfo>>>> {
         if let Some(skb) = event.get_section::<SkbTrackingEvent>(SectionId::SkbTracking) {
             let tracking_id = skb.tracking_id();
             let info = match self.skb_tracking.get(&tracking_id) {
@@ -173,7 +214,6 @@ impl AddTracking {
                     info
                 }
             };
-            Self::insert_info(event, &info)?;
             Ok(Some(info))
         } else {
             Ok(None)
@@ -207,6 +247,7 @@ mod tests {
                     skb: 18446616575340381184,
                 },
                 idx: 9,
+                ovs_recirc: Vec::new(),
             },
         ),
     ];
@@ -234,4 +275,130 @@ mod tests {
         }
         Ok(())
     }
+
+    fn action_event(recirc_id: u32, action: OvsAction) -> Result<Event> {
+        session_action_event(None, recirc_id, action)
+    }
+
+    fn session_action_event(
+        session_id: Option<u64>,
+        recirc_id: u32,
+        action: OvsAction,
+    ) -> Result<Event> {
+        let mut event = Event::new();
+        event.insert_section(
+            SectionId::Common,
+            Box::new(CommonEvent {
+                session_id,
+                ..Default::default()
+            }),
+        )?;
+        event.insert_section(
+            SectionId::SkbTracking,
+            Box::new(SkbTrackingEvent {
+                orig_head: 18446616575029637120,
+                timestamp: 689436955471671,
+                skb: 18446616575340381184,
+            }),
+        )?;
+        event.insert_section(
+            SectionId::Ovs,
+            Box::new(OvsEvent {
+                event: OvsEventType::Action(ActionEvent {
+                    action: Some(action),
+                    recirc_id,
+                    queue_id: None,
+                    error: None,
+                    has_detail: None,
+                }),
+            }),
+        )?;
+        Ok(event)
+    }
+
+    #[test]
+    fn test_ovs_nested_recirc() -> Result<()> {
+        let mut tracker = AddTracking::new();
+
+        // sample(recirc(0x5)),output(2) followed by the deferred execution of the nested recirc
+        // action. The output action is executed after the recirc one but before recirculation.
+        let mut events = [
+            action_event(0, OvsAction::Sample(OvsActionSample::default()))?,
+            action_event(0, OvsAction::Recirc(OvsActionRecirc { id: 0x5 }))?,
+            action_event(
+                0,
+                OvsAction::Output(OvsActionOutput {
+                    port: 2,
+                    ..Default::default()
+                }),
+            )?,
+            action_event(
+                0x5,
+                OvsAction::Output(OvsActionOutput {
+                    port: 3,
+                    ..Default::default()
+                }),
+            )?,
+        ];
+
+        let mut chains = Vec::new();
+        for event in events.iter_mut() {
+            tracker.process_one(event)?;
+            let info = event
+                .get_section::<TrackingInfo>(SectionId::Tracking)
+                .ok_or_else(|| anyhow!("no tracking info"))?;
+            chains.push((info.idx, info.ovs_recirc.clone()));
+        }
+
+        assert_eq!(
+            chains,
+            vec![(0, vec![0]), (1, vec![0]), (2, vec![0]), (3, vec![0, 0x5]),]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_boundary() -> Result<()> {
+        let mut tracker = AddTracking::new();
+
+        // The same packet seen in two sessions only gets correlated within a session.
+        let mut events = [
+            session_action_event(
+                Some(1),
+                0,
+                OvsAction::Output(OvsActionOutput {
+                    port: 2,
+                    ..Default::default()
+                }),
+            )?,
+            session_action_event(
+                Some(1),
+                0,
+                OvsAction::Output(OvsActionOutput {
+                    port: 3,
+                    ..Default::default()
+                }),
+            )?,
+            session_action_event(
+                Some(2),
+                0,
+                OvsAction::Output(OvsActionOutput {
+                    port: 2,
+                    ..Default::default()
+                }),
+            )?,
+        ];
+
+        let mut idx = Vec::new();
+        for event in events.iter_mut() {
+            tracker.process_one(event)?;
+            let info = event
+                .get_section::<TrackingInfo>(SectionId::Tracking)
+                .ok_or_else(|| anyhow!("no tracking info"))?;
+            idx.push(info.idx);
+        }
+
+        assert_eq!(idx, vec![0, 1, 0]);
+        Ok(())
+    }
 }
@@ -0,0 +1,201 @@
+//! Truncation attribution processor.
+//!
+//! The OvS trunc action does not modify the packet but makes the next output action send a
+//! truncated copy. A sample action executes its nested actions on a clone of the packet (unless
+//! it is the last action), so a trunc inside a sample only applies to the sampled copy and not to
+//! the packet itself.
+//!
+//! Using the skb tracking information, outputs are flagged when a trunc was executed before them
+//! on the same skb, along with the max_len of that trunc, and truncations of copies made by sample
+//! actions are flagged as such.
+
+use std::collections::HashMap;
+
+use crate::events::*;
+
+/// Identifies an skb: its tracking id and its address, to tell clones apart.
+type SkbKey = (u128, u64);
+
+/// TruncAttribution attributes trunc actions to the packet, or to the copy of the packet, they
+/// apply to.
+#[derive(Default)]
+pub(crate) struct TruncAttribution {
+    /// Packets on which a sample action was executed, indexed by tracking id, along with the
+    /// address of the copy executing the nested actions, once seen.
+    sampling: HashMap<u128, (u64, Option<u64>)>,
+    /// Skbs on which a trunc action was executed and not followed by an output yet, along with
+    /// the max_len of the trunc, if known.
+    truncated: HashMap<SkbKey, Option<u32>>,
+}
+
+impl TruncAttribution {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, updating its trunc or output action information if any.
+    pub(crate) fn process_one(&mut self, event: &mut Event) {
+        let (id, skb) = match event.get_section::<SkbTrackingEvent>(SectionId::SkbTracking) {
+            Some(tracking) => (tracking.tracking_id(), tracking.skb),
+            None => return,
+        };
+        let action = match event.get_section_mut::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event:
+                    OvsEventType::Action(ActionEvent {
+                        action: Some(action),
+                        ..
+                    }),
+            }) => action,
+            _ => return,
+        };
+
+        // Nested actions of a sample are executed right after it, on a copy of the packet. Once
+        // the sampled packet itself shows up again, the copy is done.
+        let mut sampled = false;
+        if let Some((orig, copy)) = self.sampling.get_mut(&id) {
+            if *orig == skb {
+                self.sampling.remove(&id);
+            } else {
+                sampled = *copy.get_or_insert(skb) == skb;
+            }
+        }
+
+        match action {
+            OvsAction::Sample(_) => {
+                self.sampling.insert(id, (skb, None));
+            }
+            OvsAction::Trunc(trunc) => {
+                trunc.sampled = sampled;
+                self.truncated.insert((id, skb), trunc.max_len);
+            }
+            OvsAction::Output(output) => {
+                let max_len = self.truncated.remove(&(id, skb));
+                output.truncated = max_len.is_some();
+                output.max_len = max_len.flatten();
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action_event(skb: u64, action: OvsAction) -> Event {
+        let mut event = Event::new();
+        event
+            .insert_section(
+                SectionId::SkbTracking,
+                Box::new(SkbTrackingEvent {
+                    orig_head: 0xffff,
+                    timestamp: 1,
+                    skb,
+                }),
+            )
+            .unwrap();
+        event
+            .insert_section(
+                SectionId::Ovs,
+                Box::new(OvsEvent {
+                    event: OvsEventType::Action(ActionEvent {
+                        action: Some(action),
+                        ..Default::default()
+                    }),
+                }),
+            )
+            .unwrap();
+        event
+    }
+
+    fn action(event: &Event) -> &OvsAction {
+        match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event:
+                    OvsEventType::Action(ActionEvent {
+                        action: Some(action),
+                        ..
+                    }),
+            }) => action,
+            _ => panic!("not an action event"),
+        }
+    }
+
+    fn output_truncated(event: &Event) -> bool {
+        match action(event) {
+            OvsAction::Output(output) => output.truncated,
+            _ => panic!("not an output action"),
+        }
+    }
+
+    fn output_max_len(event: &Event) -> Option<u32> {
+        match action(event) {
+            OvsAction::Output(output) => output.max_len,
+            _ => panic!("not an output action"),
+        }
+    }
+
+    #[test]
+    fn sample_trunc_output() {
+        let mut trunc = TruncAttribution::new();
+
+        // sample(actions(trunc,output:3)),output:2, the sample actions being executed on a
+        // clone of the packet (skb 0xb).
+        let mut events = [
+            action_event(0xa, OvsAction::Sample(OvsActionSample::default())),
+            action_event(0xb, OvsAction::Trunc(OvsActionTrunc::default())),
+            action_event(
+                0xb,
+                OvsAction::Output(OvsActionOutput {
+                    port: 3,
+                    ..Default::default()
+                }),
+            ),
+            action_event(
+                0xa,
+                OvsAction::Output(OvsActionOutput {
+                    port: 2,
+                    ..Default::default()
+                }),
+            ),
+        ];
+        events.iter_mut().for_each(|e| trunc.process_one(e));
+
+        match action(&events[1]) {
+            OvsAction::Trunc(t) => assert!(t.sampled),
+            _ => panic!("not a trunc action"),
+        }
+        assert!(output_truncated(&events[2]));
+        assert_eq!(output_max_len(&events[2]), None);
+        // The packet itself is not truncated.
+        assert!(!output_truncated(&events[3]));
+        assert_eq!(output_max_len(&events[3]), None);
+
+        // trunc(100),output:2 on the packet itself.
+        let mut events = [
+            action_event(
+                0xa,
+                OvsAction::Trunc(OvsActionTrunc {
+                    max_len: Some(100),
+                    ..Default::default()
+                }),
+            ),
+            action_event(
+                0xa,
+                OvsAction::Output(OvsActionOutput {
+                    port: 2,
+                    ..Default::default()
+                }),
+            ),
+        ];
+        events.iter_mut().for_each(|e| trunc.process_one(e));
+
+        match action(&events[0]) {
+            OvsAction::Trunc(t) => assert!(!t.sampled),
+            _ => panic!("not a trunc action"),
+        }
+        assert!(output_truncated(&events[1]));
+        assert_eq!(output_max_len(&events[1]), Some(100));
+    }
+}
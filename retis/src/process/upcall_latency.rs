@@ -0,0 +1,187 @@
+//! Upcall latency processor.
+//!
+//! Upcall enqueue and upcall return events are reported separately by the OvS kernel datapath
+//! probes, but both hold the cpu and timestamp of the upcall they belong to. Matching them gives
+//! the time it took for an upcall to be enqueued to userspace, which is reported on the upcall
+//! return event.
+
+use std::collections::BTreeMap;
+
+use crate::events::*;
+
+/// Default maximum number of upcalls waiting for their return event.
+const UPCALL_LATENCY_MAX_PENDING: usize = 1024;
+
+/// Identifies an upcall: its timestamp and cpu. Upcalls are ordered by age.
+type UpcallKey = (u64, u32);
+
+/// UpcallLatency sets the latency of upcall return events, based on the first enqueue event of
+/// their upcall.
+pub(crate) struct UpcallLatency {
+    /// Timestamp of the first enqueue event of upcalls not returned yet. The oldest upcalls are
+    /// evicted first, as their return event is the least likely to be seen.
+    pending: BTreeMap<UpcallKey, u64>,
+    /// Maximum number of pending upcalls.
+    max_pending: usize,
+    /// Number of upcalls evicted before their return event was seen.
+    evicted: u64,
+}
+
+impl UpcallLatency {
+    pub(crate) fn new() -> Self {
+        Self::with_max_pending(UPCALL_LATENCY_MAX_PENDING)
+    }
+
+    pub(crate) fn with_max_pending(max_pending: usize) -> Self {
+        Self {
+            pending: BTreeMap::new(),
+            max_pending,
+            evicted: 0,
+        }
+    }
+
+    /// Process one event: record upcall enqueues and set the latency of upcall returns.
+    pub(crate) fn process_one(&mut self, event: &mut Event) {
+        let ts = match event.get_section::<CommonEvent>(SectionId::Common) {
+            Some(common) => common.timestamp,
+            None => return,
+        };
+
+        match event.get_section_mut::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event: OvsEventType::UpcallEnqueue(enqueue),
+            }) => {
+                let key = (enqueue.upcall_ts, enqueue.upcall_cpu);
+                // Upcalls can be enqueued multiple times (e.g. GSO segments); keep the first one.
+                if self.pending.contains_key(&key) {
+                    return;
+                }
+
+                self.pending.insert(key, ts);
+                if self.pending.len() > self.max_pending {
+                    self.pending.pop_first();
+                    self.evicted += 1;
+                }
+            }
+            Some(OvsEvent {
+                event: OvsEventType::UpcallReturn(ret),
+            }) => {
+                let key = (ret.upcall_ts, ret.upcall_cpu);
+                if let Some(enqueue_ts) = self.pending.remove(&key) {
+                    ret.latency_ns = Some(ts.saturating_sub(enqueue_ts));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Number of upcalls evicted before their return event was seen.
+    pub(crate) fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    /// Number of upcalls whose return event was not seen yet.
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::testing::ovs_event;
+
+    fn enqueue(timestamp: u64, upcall_ts: u64) -> Event {
+        ovs_event(
+            timestamp,
+            OvsEventType::UpcallEnqueue(UpcallEnqueueEvent {
+                upcall_ts,
+                upcall_cpu: 1,
+                queue_id: upcall_ts as u32,
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn ret(timestamp: u64, upcall_ts: u64) -> Event {
+        ovs_event(
+            timestamp,
+            OvsEventType::UpcallReturn(UpcallReturnEvent {
+                upcall_ts,
+                upcall_cpu: 1,
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn latency(event: &Event) -> Option<u64> {
+        match event.get_section::<OvsEvent>(SectionId::Ovs) {
+            Some(OvsEvent {
+                event: OvsEventType::UpcallReturn(ret),
+            }) => ret.latency_ns,
+            _ => panic!("not an upcall return event"),
+        }
+    }
+
+    #[test]
+    fn upcall_latency() {
+        let mut latency_proc = UpcallLatency::new();
+
+        latency_proc.process_one(&mut enqueue(1200, 1000));
+        // Second enqueue of the same upcall.
+        latency_proc.process_one(&mut enqueue(1300, 1000));
+        let mut event = ret(1500, 1000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), Some(300));
+
+        // Already returned.
+        let mut event = ret(1600, 1000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), None);
+        assert_eq!(latency_proc.pending(), 0);
+    }
+
+    #[test]
+    fn upcall_latency_eviction() {
+        let mut latency_proc = UpcallLatency::with_max_pending(2);
+
+        // The return of the first upcall is never seen, and it gets evicted.
+        latency_proc.process_one(&mut enqueue(1100, 1000));
+        latency_proc.process_one(&mut enqueue(2100, 2000));
+        latency_proc.process_one(&mut enqueue(3100, 3000));
+        assert_eq!(latency_proc.evicted(), 1);
+
+        let mut event = ret(4000, 1000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), None);
+
+        let mut event = ret(4000, 2000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), Some(1900));
+        assert_eq!(latency_proc.pending(), 1);
+    }
+
+    #[test]
+    fn upcall_latency_eviction_order() {
+        let mut latency_proc = UpcallLatency::with_max_pending(2);
+
+        // Upcalls are evicted by age, not by the order their enqueue events are seen in.
+        latency_proc.process_one(&mut enqueue(2100, 2000));
+        latency_proc.process_one(&mut enqueue(3100, 3000));
+        latency_proc.process_one(&mut enqueue(3200, 1000));
+        assert_eq!(latency_proc.evicted(), 1);
+        assert_eq!(latency_proc.pending(), 2);
+
+        let mut event = ret(4000, 1000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), None);
+
+        let mut event = ret(4000, 2000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), Some(1900));
+        let mut event = ret(4000, 3000);
+        latency_proc.process_one(&mut event);
+        assert_eq!(latency(&event), Some(900));
+        assert_eq!(latency_proc.pending(), 0);
+    }
+}